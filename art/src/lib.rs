@@ -1,56 +1,121 @@
-use std::{
-    fs::File,
-    io::{BufReader, Read, Seek},
-};
+// Malformed files are reported as errors and never panic, as the
+// processes that embed these crates cannot recover from a panic.
+// clippy::arithmetic_side_effects is not denied, as it flags every operator
+// including those on bounded values. Arithmetic on values read from files
+// is checked or saturating instead, which tests/error_injection.rs covers.
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::expect_used,
+        clippy::indexing_slicing,
+        clippy::panic,
+        clippy::unwrap_used
+    )
+)]
+
+pub mod testing;
+mod tile_set;
+
+pub use tile_set::{TileFile, TileSet, DUKE3D_TILE_LIMIT};
+
+#[cfg(feature = "palette")]
+use palette::Palette;
+use std::io::{BufReader, Read, Seek};
 
 /// File reader for .art files, which are used by the Build engine.
 /// The format is used to store sprites and textures of a game.
 /// Both sprites and textures are stored as tiles.
 ///
 /// See https://moddingwiki.shikadi.net/wiki/ART_Format_(Build)
-pub struct ArtFileReader<'a> {
-    reader: BufReader<&'a File>,
+///
+/// Besides files, the reader accepts anything that can be read and seeked,
+/// e.g. a `std::io::Cursor` over an entry read from a .grp file.
+///
+/// ```
+/// use art::{testing::ArtFixture, ArtFileReader};
+/// use std::fs::File;
+///
+/// let path = std::env::temp_dir().join("art_doc_reader.art");
+/// ArtFixture::new(2472)
+///     .tile(64, 128, 31)
+///     .tile(32, 32, 0)
+///     .write_to(&path)
+///     .unwrap();
+///
+/// let file = File::open(&path).unwrap();
+/// let mut art_reader = ArtFileReader::new(&file).unwrap();
+/// let tiles = art_reader.read_tiles().unwrap();
+///
+/// assert_eq!(tiles.len(), 2);
+/// assert_eq!(tiles[0].number(), 2472);
+/// assert_eq!((tiles[0].width(), tiles[0].height()), (64, 128));
+/// ```
+pub struct ArtFileReader<R> {
+    reader: BufReader<R>,
+    /// The tiles, once [`Self::read_tiles`] has read them.
+    tiles: Option<Vec<ArtTile>>,
+    version: ArtVersion,
 }
-impl<'a> ArtFileReader<'a> {
-    pub fn new(file: &'a File) -> Result<Self, String> {
-        let mut reader = BufReader::new(file);
+impl<R: Read + Seek> ArtFileReader<R> {
+    pub fn new(reader: R) -> Result<Self, String> {
+        let mut reader = BufReader::new(reader);
 
-        // Ensure the header contains valid version number.
+        // Ensure the header contains a known version number.
         // Read the version number as a little-endian 32-bit unsigned integer.
-        const SUPPORTED_VERSION_NUMBER: u32 = 1;
-
         let mut version_number = [0u8; 4];
         reader
             .read_exact(&mut version_number)
-            .map_err(|_| "Failed to read version number from .art file.")?;
+            .map_err(|_| truncated("Version number", 0))?;
         let version_number = u32::from_le_bytes(version_number);
 
-        if version_number != SUPPORTED_VERSION_NUMBER {
+        let Some(version) = ArtVersion::from_number(version_number) else {
             return Err(format!(
-                "Unsupported version number {} (should be {})",
-                version_number, SUPPORTED_VERSION_NUMBER
+                "Unsupported version number {} (supported: {})",
+                version_number,
+                ArtVersion::ALL
+                    .iter()
+                    .map(|v| v.number().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
             ));
-        }
+        };
 
         // The header contains the number of tiles in the file,
         // but there is no need to read it.
 
-        Ok(Self { reader })
+        Ok(Self {
+            reader,
+            tiles: None,
+            version,
+        })
     }
+    /// The on-disk format version of the file, which determines
+    /// the layout of the header and the available capabilities.
+    pub fn version(&self) -> ArtVersion {
+        self.version
+    }
+    /// Reads the sizes and picanm values of all tiles. They are read from
+    /// the file once and kept for later calls and [`Self::get_tile`].
     pub fn read_tiles(&mut self) -> Result<Vec<ArtTile>, String> {
+        if let Some(tiles) = &self.tiles {
+            return Ok(tiles.clone());
+        }
         // Ensure the file reader is set after the version number and the number of tiles.
         self.reader
-            .seek(std::io::SeekFrom::Start(4 + 4))
+            .seek(std::io::SeekFrom::Start(
+                self.version.capabilities().tile_range_offset,
+            ))
             .map_err(|_| {
                 "Failed to set the file reader after the version number and the number of tiles."
             })?;
 
         // Read the number of the first tile (localtilestart).
         // Read the number of the first tile as a little-endian 32-bit unsigned integer.
+        let tile_range_offset = self.version.capabilities().tile_range_offset;
         let mut first_tile_number = [0u8; 4];
         self.reader
             .read_exact(&mut first_tile_number)
-            .map_err(|_| "Failed to read first tile number from .art file.")?;
+            .map_err(|_| truncated("First tile number", tile_range_offset))?;
         let first_tile_number = u32::from_le_bytes(first_tile_number);
 
         // Read the number of the last tile (localtileend).
@@ -58,65 +123,432 @@ impl<'a> ArtFileReader<'a> {
         let mut last_tile_number = [0u8; 4];
         self.reader
             .read_exact(&mut last_tile_number)
-            .map_err(|_| "Failed to read last tile number from .art file.")?;
+            .map_err(|_| truncated("Last tile number", tile_range_offset + 4))?;
         let last_tile_number = u32::from_le_bytes(last_tile_number);
 
-        let tile_count = last_tile_number - first_tile_number + 1;
-
-        // Read x-dimensions of all of the tiles in the file.
-        // Each x-dimension is stored as a little-endian 16-bit signed integer.
-        let mut tile_widths = Vec::with_capacity(tile_count as usize);
-        for _ in 0..tile_count {
-            let mut tile_width = [0u8; 2];
-            self.reader
-                .read_exact(&mut tile_width)
-                .map_err(|_| "Failed to read tile width from .art file.")?;
-            let tile_width = i16::from_le_bytes(tile_width);
-            tile_widths.push(tile_width);
-        }
+        // Counted in 64 bits, as the range may not fit in memory.
+        let Some(tile_count) = last_tile_number
+            .checked_sub(first_tile_number)
+            .map(|n| n as u64 + 1)
+        else {
+            return Err(format!(
+                "Last tile number {} is smaller than the first tile number {}.",
+                last_tile_number, first_tile_number
+            ));
+        };
 
-        // Read y-dimensions of all of the tiles in the file.
-        // Each y-dimension is stored as a little-endian 16-bit signed integer.
-        let mut tile_heights = Vec::with_capacity(tile_count as usize);
-        for _ in 0..tile_count {
-            let mut tile_height = [0u8; 2];
-            self.reader
-                .read_exact(&mut tile_height)
-                .map_err(|_| "Failed to read tile height from .art file.")?;
-            let tile_height = i16::from_le_bytes(tile_height);
-            tile_heights.push(tile_height);
+        // Read the x-dimensions, the y-dimensions and, when the version has them,
+        // the picanm values of all of the tiles in the file with one read.
+        // Each x- and y-dimension is stored as a little-endian 16-bit signed integer
+        // and each picanm value as a little-endian 32-bit unsigned integer.
+        let picanm_bytes = if self.version.capabilities().has_picanm {
+            4
+        } else {
+            0
+        };
+        let metadata_offset = tile_range_offset + 4 + 4;
+        let metadata_bytes = tile_count * (2 + 2 + picanm_bytes);
+        // The buffer grows as the data arrives, so a bogus tile range in a short
+        // file does not allocate gigabytes up front.
+        let mut metadata_buf = Vec::new();
+        (&mut self.reader)
+            .take(metadata_bytes)
+            .read_to_end(&mut metadata_buf)
+            .map_err(|e| format!("Failed to read tile metadata from .art file: {}", e))?;
+        let read = metadata_buf.len() as u64;
+        if read < metadata_bytes {
+            // Name the first value that is missing.
+            let (value, array_offset, value_bytes) = if read < tile_count * 2 {
+                ("width", 0, 2)
+            } else if read < tile_count * 4 {
+                ("height", tile_count * 2, 2)
+            } else {
+                ("picanm", tile_count * 4, 4)
+            };
+            let i = (read - array_offset) / value_bytes;
+            return Err(truncated(
+                &format!("Tile {} {}", first_tile_number as u64 + i, value),
+                metadata_offset + array_offset + i * value_bytes,
+            ));
         }
 
-        // "Merge" the tile widths and heights together into a vector or tiles.
-        let tiles = tile_widths
+        // All of the metadata was read, so the tile count fits in memory.
+        let tile_count = tile_count as usize;
+        let (dimensions, picanms) = metadata_buf.split_at(tile_count * 4);
+        let (tile_widths, tile_heights) = dimensions.split_at(tile_count * 2);
+
+        let tile_widths = tile_widths
+            .as_chunks()
+            .0
+            .iter()
+            .map(|b| i16::from_le_bytes(*b));
+        let tile_heights = tile_heights
+            .as_chunks()
+            .0
             .iter()
-            .zip(tile_heights.iter())
+            .map(|b| i16::from_le_bytes(*b));
+        let picanms = picanms
+            .as_chunks()
+            .0
+            .iter()
+            .map(|b| u32::from_le_bytes(*b))
+            .chain(std::iter::repeat(0));
+
+        // "Merge" the tile widths, heights and picanm values together into a vector of tiles.
+        // The pixels of the tiles follow the metadata in the same order.
+        let mut data_offset = metadata_offset + metadata_bytes;
+        let tiles = tile_widths
+            .zip(tile_heights)
+            .zip(picanms)
             .enumerate()
-            .map(|(i, (w, h))| ArtTile {
-                height: *h,
-                number: first_tile_number + i as u32,
-                width: *w,
+            .map(|(i, ((width, height), picanm))| {
+                let tile = ArtTile {
+                    data_offset,
+                    height,
+                    number: first_tile_number + i as u32,
+                    picanm,
+                    width,
+                };
+                data_offset += tile.pixel_count() as u64;
+                tile
             })
             .collect::<Vec<_>>();
 
+        self.tiles = Some(tiles.clone());
         Ok(tiles)
     }
+    /// The tile with an absolute tile number (picnum), which the tiles of the
+    /// file are numbered by from its first tile (localtilestart).
+    ///
+    /// ```
+    /// use art::{testing::ArtFixture, ArtFileReader};
+    /// use std::io::Cursor;
+    ///
+    /// let bytes = ArtFixture::new(2472).tile(64, 128, 31).tile(32, 32, 0).build();
+    /// let mut art_reader = ArtFileReader::new(Cursor::new(bytes)).unwrap();
+    ///
+    /// assert_eq!(art_reader.get_tile(2473).unwrap().width(), 32);
+    /// assert_eq!(
+    ///     art_reader.get_tile(0).err().as_deref(),
+    ///     Some("Tile 0 is outside the tiles 2472 to 2473 of the .art file.")
+    /// );
+    /// ```
+    pub fn get_tile(&mut self, tile_number: u32) -> Result<ArtTile, String> {
+        if self.tiles.is_none() {
+            self.read_tiles()?;
+        }
+        let tiles = self.tiles.as_deref().unwrap_or_default();
+        let (Some(first), Some(last)) = (tiles.first(), tiles.last()) else {
+            return Err(format!("Tile {} is not in the .art file.", tile_number));
+        };
+        tile_number
+            .checked_sub(first.number)
+            .and_then(|i| tiles.get(i as usize))
+            .cloned()
+            .ok_or_else(|| {
+                format!(
+                    "Tile {} is outside the tiles {} to {} of the .art file.",
+                    tile_number, first.number, last.number
+                )
+            })
+    }
+    /// Reads the pixels of a tile returned by [`Self::read_tiles`]: one palette
+    /// index per pixel, column by column, as the engine stores them.
+    pub fn read_pixels(&mut self, tile: &ArtTile) -> Result<Vec<u8>, String> {
+        self.reader
+            .seek(std::io::SeekFrom::Start(tile.data_offset))
+            .map_err(|_| "Failed to seek to tile pixels.")?;
+        let mut pixels = Vec::new();
+        (&mut self.reader)
+            .take(tile.pixel_count() as u64)
+            .read_to_end(&mut pixels)
+            .map_err(|e| format!("Failed to read tile pixels from .art file: {}", e))?;
+        if pixels.len() < tile.pixel_count() {
+            return Err(truncated(
+                &format!("Pixels of tile {}", tile.number),
+                tile.data_offset,
+            ));
+        }
+        Ok(pixels)
+    }
+}
+
+/// Reads the first and last tile numbers from the header of an .art file in memory.
+pub fn read_tile_range(art: &[u8]) -> Result<(u32, u32), String> {
+    let offset = tile_range_offset(art)?;
+    let Some(range) = art.get(offset..).and_then(|a| a.first_chunk::<8>()) else {
+        return Err(truncated("Tile range", offset as u64));
+    };
+    let first_tile_number = u32::from_le_bytes([range[0], range[1], range[2], range[3]]);
+    let last_tile_number = u32::from_le_bytes([range[4], range[5], range[6], range[7]]);
+    if last_tile_number < first_tile_number {
+        return Err(format!(
+            "Last tile number {} is smaller than the first tile number {}.",
+            last_tile_number, first_tile_number
+        ));
+    }
+    Ok((first_tile_number, last_tile_number))
+}
+
+/// Moves the tiles of an .art file in memory to start at `first_tile_number`
+/// by rewriting localtilestart and localtileend. Returns the previous range.
+///
+/// ```
+/// use art::testing::ArtFixture;
+///
+/// let mut bytes = ArtFixture::new(1400).tile(8, 8, 0).tile(8, 8, 0).build();
+/// assert_eq!(art::renumber(&mut bytes, 3584), Ok((1400, 1401)));
+/// assert_eq!(art::read_tile_range(&bytes), Ok((3584, 3585)));
+/// ```
+pub fn renumber(art: &mut [u8], first_tile_number: u32) -> Result<(u32, u32), String> {
+    let (previous_first, previous_last) = read_tile_range(art)?;
+    let Some(last_tile_number) = first_tile_number.checked_add(previous_last - previous_first)
+    else {
+        return Err(format!("Tile number {} is too large.", first_tile_number));
+    };
+    let offset = tile_range_offset(art)?;
+    // read_tile_range has checked that the range is there.
+    if let Some(range) = art.get_mut(offset..).and_then(|a| a.first_chunk_mut::<8>()) {
+        let (first, last) = range.split_at_mut(4);
+        first.copy_from_slice(&first_tile_number.to_le_bytes());
+        last.copy_from_slice(&last_tile_number.to_le_bytes());
+    }
+    Ok((previous_first, previous_last))
+}
+
+/// Replaces every pixel of an .art file in memory with its entry in `table`,
+/// e.g. a [`palette::RemapTable`] to another palette. Returns the number of
+/// pixels that changed.
+///
+/// Requires the `palette` feature.
+///
+/// ```
+/// use art::testing::ArtFixture;
+///
+/// let mut bytes = ArtFixture::new(0).tile(2, 2, 5).tile(1, 1, 9).build();
+/// let mut table = [0; 256];
+/// for (i, entry) in table.iter_mut().enumerate() {
+///     *entry = i as u8;
+/// }
+/// table[5] = 6;
+/// assert_eq!(art::remap_pixels(&mut bytes, &table), Ok(4));
+/// assert_eq!(bytes[bytes.len() - 5..], [6, 6, 6, 6, 9]);
+/// ```
+#[cfg(feature = "palette")]
+pub fn remap_pixels(art: &mut [u8], table: &[u8; 256]) -> Result<usize, String> {
+    let (first_tile_number, last_tile_number) = read_tile_range(art)?;
+    let tile_count = (last_tile_number - first_tile_number) as usize + 1;
+    // The widths and heights of all tiles are followed by their picanm
+    // values, when the version has them, and then their pixels.
+    let capabilities = version_of(art)?.capabilities();
+    let picanm_bytes = if capabilities.has_picanm { 4 } else { 0 };
+    let sizes_offset = capabilities.tile_range_offset as usize + 8;
+    let pixels_offset = sizes_offset + tile_count * (4 + picanm_bytes);
+    let Some(sizes) = art.get(sizes_offset..sizes_offset + tile_count * 4) else {
+        return Err(truncated("Tile sizes", sizes_offset as u64));
+    };
+    let (widths, heights) = sizes.as_chunks::<2>().0.split_at(tile_count);
+    let pixel_count = widths
+        .iter()
+        .zip(heights)
+        .map(|(w, h)| {
+            i16::from_le_bytes(*w).max(0) as usize * i16::from_le_bytes(*h).max(0) as usize
+        })
+        .sum::<usize>();
+    let Some(pixels) = art.get_mut(pixels_offset..pixels_offset + pixel_count) else {
+        return Err(truncated("Tile pixels", pixels_offset as u64));
+    };
+
+    let mut changed = 0;
+    for pixel in pixels {
+        let remapped = table.get(*pixel as usize).copied().unwrap_or(*pixel);
+        changed += (remapped != *pixel) as usize;
+        *pixel = remapped;
+    }
+    Ok(changed)
+}
+
+fn tile_range_offset(art: &[u8]) -> Result<usize, String> {
+    Ok(version_of(art)?.capabilities().tile_range_offset as usize)
+}
+
+/// The version of an .art file in memory.
+fn version_of(art: &[u8]) -> Result<ArtVersion, String> {
+    let Some(version_number) = art.first_chunk::<4>() else {
+        return Err(truncated("Version number", 0));
+    };
+    let version_number = u32::from_le_bytes(*version_number);
+    ArtVersion::from_number(version_number)
+        .ok_or_else(|| format!("Unsupported version number {}", version_number))
+}
+
+/// The error for a value that the file ends within,
+/// e.g. "Tile 143 width at offset 0x12A is truncated."
+fn truncated(value: &str, offset: u64) -> String {
+    format!("{} at offset 0x{:X} is truncated.", value, offset)
+}
+
+/// Known on-disk versions of the ART format.
+///
+/// New versions, such as variants written by third-party editors, are added
+/// here together with their capabilities, and the reader dispatches on them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtVersion {
+    /// The early layout without animation data: the tile sizes are
+    /// followed directly by the pixels.
+    V0,
+    /// The version used by Duke Nukem 3D and other Build games.
+    V1,
+}
+impl ArtVersion {
+    pub const ALL: &'static [ArtVersion] = &[ArtVersion::V0, ArtVersion::V1];
+
+    pub fn from_number(number: u32) -> Option<Self> {
+        Self::ALL.iter().copied().find(|v| v.number() == number)
+    }
+    pub fn number(self) -> u32 {
+        match self {
+            ArtVersion::V0 => 0,
+            ArtVersion::V1 => 1,
+        }
+    }
+    pub fn capabilities(self) -> ArtCapabilities {
+        match self {
+            ArtVersion::V0 => ArtCapabilities {
+                has_picanm: false,
+                tile_range_offset: 4 + 4,
+            },
+            ArtVersion::V1 => ArtCapabilities {
+                has_picanm: true,
+                tile_range_offset: 4 + 4,
+            },
+        }
+    }
 }
 
-#[derive(Debug)]
+/// Describes which parts of the format a given [`ArtVersion`] provides.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArtCapabilities {
+    /// Whether the file stores animation and offset data (picanm) for each tile.
+    pub has_picanm: bool,
+    /// Byte offset of the first and last tile numbers (localtilestart and localtileend).
+    pub tile_range_offset: u64,
+}
+
+#[derive(Clone, Debug)]
 pub struct ArtTile {
+    data_offset: u64,
     height: i16,
     number: u32,
+    picanm: u32,
     width: i16,
 }
+impl ArtTile {
+    pub fn height(&self) -> i16 {
+        self.height
+    }
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+    /// The raw animation and offset bit field of the tile.
+    pub fn picanm(&self) -> u32 {
+        self.picanm
+    }
+    /// The animation and offset fields of [`Self::picanm`].
+    pub fn animation(&self) -> Picanm {
+        Picanm::from_raw(self.picanm)
+    }
+    pub fn width(&self) -> i16 {
+        self.width
+    }
+    /// The number of pixels of the tile, which is 0 for empty tiles.
+    pub fn pixel_count(&self) -> usize {
+        self.width.max(0) as usize * self.height.max(0) as usize
+    }
+    /// Converts pixels read with [`ArtFileReader::read_pixels`] to RGBA,
+    /// row by row. Palette index 255 becomes transparent. Requires the
+    /// `palette` feature.
+    #[cfg(feature = "palette")]
+    pub fn to_rgba(&self, pixels: &[u8], palette: &Palette) -> Vec<u8> {
+        let (width, height) = (self.width.max(0) as usize, self.height.max(0) as usize);
+        let mut indices = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                indices.push(pixels.get(x * height + y).copied().unwrap_or(0));
+            }
+        }
+        palette.to_rgba(&indices, true)
+    }
+}
+
+/// How the frames of an animated tile are played.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationType {
+    None,
+    Oscillating,
+    Forward,
+    Backward,
+}
+
+/// The fields of a picanm value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Picanm {
+    /// The number of tiles following this one that belong to its animation.
+    pub frames: u8,
+    pub animation_type: AnimationType,
+    /// Offset of the tile center from the middle of the tile, in pixels.
+    pub x_offset: i8,
+    pub y_offset: i8,
+    /// Animation speed, where each step halves the duration of a frame.
+    pub speed: u8,
+}
+impl Picanm {
+    pub fn from_raw(picanm: u32) -> Self {
+        Self {
+            frames: (picanm & 0x3F) as u8,
+            animation_type: match (picanm >> 6) & 0x3 {
+                0 => AnimationType::None,
+                1 => AnimationType::Oscillating,
+                2 => AnimationType::Forward,
+                _ => AnimationType::Backward,
+            },
+            x_offset: (picanm >> 8) as u8 as i8,
+            y_offset: (picanm >> 16) as u8 as i8,
+            speed: ((picanm >> 24) & 0xF) as u8,
+        }
+    }
+}
 
 #[test]
 fn should_read_art() {
-    let curr_dir = std::env::current_dir().unwrap();
-    let file_path = curr_dir.join("../tmp/TILES000.ART");
-    let file = File::open(file_path).unwrap();
+    let file_path = std::env::temp_dir().join("art_should_read_art.art");
+    let mut fixture = testing::ArtFixture::new(0);
+    for i in 0..16 {
+        fixture = fixture
+            .tile(i * 8, 64 - i, i as u8)
+            .picanm(i as u32 * 0x100);
+    }
+    fixture.write_to(&file_path).unwrap();
+
+    let file = std::fs::File::open(&file_path).unwrap();
     let mut art_reader = ArtFileReader::new(&file).unwrap();
     let tiles = art_reader.read_tiles().unwrap();
+    assert_eq!(art_reader.version(), ArtVersion::V1);
+    assert_eq!(tiles.len(), 16);
+    assert_eq!(
+        (tiles[3].number, tiles[3].width, tiles[3].height),
+        (3, 24, 61)
+    );
+    assert_eq!(tiles[15].picanm, 0xF00);
+
+    // 16 widths, 3 heights and one byte of the fourth height are left.
+    let bytes = std::fs::read(&file_path).unwrap();
+    let truncated = ArtFileReader::new(std::io::Cursor::new(&bytes[..16 + 19 * 2 + 1]))
+        .and_then(|mut art_reader| art_reader.read_tiles());
+    assert_eq!(
+        truncated.err().as_deref(),
+        Some("Tile 3 height at offset 0x36 is truncated.")
+    );
     println!(
         "tiles: {:#?}",
         tiles[0..15]
@@ -125,3 +557,71 @@ fn should_read_art() {
             .collect::<Vec<_>>()
     );
 }
+
+#[test]
+fn should_read_pixels() {
+    let file_path = std::env::temp_dir().join("art_should_read_pixels.art");
+    testing::ArtFixture::new(2472)
+        .tile(2, 3, 7)
+        .picanm(0x0201_FE83)
+        .tile(0, 0, 0)
+        .tile(1, 2, 255)
+        .write_to(&file_path)
+        .unwrap();
+
+    let file = std::fs::File::open(file_path).unwrap();
+    let mut art_reader = ArtFileReader::new(&file).unwrap();
+    let tiles = art_reader.read_tiles().unwrap();
+    assert_eq!(art_reader.read_pixels(&tiles[0]).unwrap(), [7; 6]);
+    assert_eq!(art_reader.read_pixels(&tiles[1]).unwrap(), []);
+    assert_eq!(art_reader.read_pixels(&tiles[2]).unwrap(), [255; 2]);
+    assert_eq!(
+        tiles[0].animation(),
+        Picanm {
+            frames: 3,
+            animation_type: AnimationType::Forward,
+            x_offset: -2,
+            y_offset: 1,
+            speed: 2,
+        }
+    );
+
+    #[cfg(feature = "palette")]
+    {
+        let mut colors = [[0u8; 3]; palette::COLOR_COUNT];
+        colors[7] = [10, 20, 30];
+        let rgba = tiles[2].to_rgba(&[255, 7], &Palette::from_colors(colors));
+        assert_eq!(rgba, [0, 0, 0, 0, 10, 20, 30, 255]);
+    }
+}
+
+#[test]
+fn should_read_version_0_without_picanm() {
+    let bytes = testing::ArtFixture::new(10)
+        .version(ArtVersion::V0)
+        .tile(2, 2, 5)
+        .tile(1, 3, 9)
+        .build();
+    // 16 bytes of header and 2 + 2 bytes of sizes per tile, then the pixels.
+    assert_eq!(bytes.len(), 16 + 2 * 4 + 4 + 3);
+
+    let mut art_reader = ArtFileReader::new(std::io::Cursor::new(&bytes)).unwrap();
+    assert_eq!(art_reader.version(), ArtVersion::V0);
+    let tile = art_reader.get_tile(11).unwrap();
+    assert_eq!((tile.width(), tile.height()), (1, 3));
+    assert_eq!(tile.animation().frames, 0);
+    assert_eq!(art_reader.read_pixels(&tile).unwrap(), [9; 3]);
+    assert_eq!(read_tile_range(&bytes), Ok((10, 11)));
+
+    #[cfg(feature = "palette")]
+    {
+        let mut table = [0; 256];
+        for (i, entry) in table.iter_mut().enumerate() {
+            *entry = i as u8;
+        }
+        table[9] = 8;
+        let mut bytes = bytes;
+        assert_eq!(remap_pixels(&mut bytes, &table), Ok(3));
+        assert_eq!(bytes[bytes.len() - 4..], [5, 8, 8, 8]);
+    }
+}
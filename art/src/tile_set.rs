@@ -0,0 +1,163 @@
+use crate::ArtTile;
+
+/// The tiles of a game, which are spread over several .art files,
+/// e.g. TILES000.ART to TILES019.ART of Duke Nukem 3D.
+///
+/// ```
+/// use art::{testing::ArtFixture, ArtFileReader, TileSet};
+/// use std::io::Cursor;
+///
+/// let mut tile_set = TileSet::new();
+/// for (name, first_tile_number) in [("TILES000.ART", 0), ("TILES002.ART", 512)] {
+///     let bytes = ArtFixture::new(first_tile_number).tile(8, 8, 0).tile(0, 0, 0).build();
+///     let tiles = ArtFileReader::new(Cursor::new(bytes)).unwrap().read_tiles().unwrap();
+///     tile_set.add_file(name, tiles);
+/// }
+///
+/// assert_eq!(tile_set.tiles().count(), 4);
+/// assert_eq!(tile_set.gaps(), [(2, 511)]);
+/// ```
+#[derive(Debug, Default)]
+pub struct TileSet {
+    files: Vec<TileFile>,
+}
+impl TileSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds the tiles read from an .art file. Files are kept ordered by their first tile.
+    pub fn add_file(&mut self, name: impl Into<String>, tiles: Vec<ArtTile>) {
+        let file = TileFile {
+            name: name.into(),
+            tiles,
+        };
+        let at = self
+            .files
+            .partition_point(|f| f.first_tile_number() <= file.first_tile_number());
+        self.files.insert(at, file);
+    }
+    pub fn files(&self) -> &[TileFile] {
+        &self.files
+    }
+    /// All tiles, ordered by their number.
+    pub fn tiles(&self) -> impl Iterator<Item = &ArtTile> {
+        self.files.iter().flat_map(|f| f.tiles.iter())
+    }
+    /// The file that covers an absolute tile number (picnum). Of overlapping
+    /// files, the one with the lower first tile is returned.
+    pub fn file_of(&self, tile_number: u32) -> Option<&TileFile> {
+        self.files
+            .iter()
+            .find(|f| f.get_tile(tile_number).is_some())
+    }
+    /// The tile with an absolute tile number (picnum), from whichever file covers it.
+    pub fn get_tile(&self, tile_number: u32) -> Result<&ArtTile, String> {
+        self.file_of(tile_number)
+            .and_then(|f| f.get_tile(tile_number))
+            .ok_or_else(|| format!("Tile {} is not in any .art file.", tile_number))
+    }
+    /// Ranges of tile numbers, first and last inclusive, that no file covers
+    /// up to the last tile of the set. Overlapping files leave no gap.
+    pub fn gaps(&self) -> Vec<(u32, u32)> {
+        let mut gaps = Vec::new();
+        let mut next_tile_number = 0;
+        for file in self.files.iter().filter(|f| !f.tiles.is_empty()) {
+            if file.first_tile_number() > next_tile_number {
+                gaps.push((next_tile_number, file.first_tile_number() - 1));
+            }
+            next_tile_number = next_tile_number.max(file.last_tile_number().saturating_add(1));
+        }
+        gaps
+    }
+    /// Ranges of tile numbers below `tile_limit`, first and last inclusive,
+    /// that are free for new art: empty tiles and numbers no file covers.
+    pub fn free_ranges(&self, tile_limit: u32) -> Vec<(u32, u32)> {
+        let mut used = self
+            .tiles()
+            .filter(|t| t.pixel_count() > 0)
+            .map(ArtTile::number)
+            .collect::<Vec<_>>();
+        used.sort_unstable();
+
+        let mut ranges = Vec::new();
+        let mut next_tile_number = 0;
+        for number in used.into_iter().chain([tile_limit]) {
+            let number = number.min(tile_limit);
+            if number > next_tile_number {
+                ranges.push((next_tile_number, number - 1));
+            }
+            next_tile_number = next_tile_number.max(number.saturating_add(1));
+        }
+        ranges
+    }
+}
+
+/// The number of tiles supported by Duke Nukem 3D 1.5.
+pub const DUKE3D_TILE_LIMIT: u32 = 6144;
+
+/// The tiles of one .art file of a [`TileSet`].
+#[derive(Debug)]
+pub struct TileFile {
+    pub name: String,
+    pub tiles: Vec<ArtTile>,
+}
+impl TileFile {
+    pub fn first_tile_number(&self) -> u32 {
+        self.tiles.first().map_or(0, ArtTile::number)
+    }
+    pub fn last_tile_number(&self) -> u32 {
+        self.tiles.last().map_or(0, ArtTile::number)
+    }
+    /// The tile with an absolute tile number, if the file covers it.
+    pub fn get_tile(&self, tile_number: u32) -> Option<&ArtTile> {
+        tile_number
+            .checked_sub(self.first_tile_number())
+            .and_then(|i| self.tiles.get(i as usize))
+    }
+}
+
+#[test]
+fn should_order_files_and_find_gaps() {
+    use crate::{testing::ArtFixture, ArtFileReader};
+
+    // Builds a file of square tiles with the given sizes, 0 being an empty tile.
+    let read = |first_tile_number, sizes: &[i16]| {
+        let fixture = sizes
+            .iter()
+            .fold(ArtFixture::new(first_tile_number), |f, size| {
+                f.tile(*size, *size, 0)
+            });
+        ArtFileReader::new(std::io::Cursor::new(fixture.build()))
+            .unwrap()
+            .read_tiles()
+            .unwrap()
+    };
+    let mut tile_set = TileSet::new();
+    tile_set.add_file("TILES003.ART", read(20, &[4, 4, 4, 4, 4]));
+    tile_set.add_file("TILES001.ART", read(4, &[4, 4, 4, 4]));
+    tile_set.add_file("TILES002.ART", read(8, &[4, 0, 0, 4]));
+
+    let names = tile_set.files().iter().map(|f| f.name.as_str());
+    assert_eq!(
+        names.collect::<Vec<_>>(),
+        ["TILES001.ART", "TILES002.ART", "TILES003.ART"]
+    );
+    assert_eq!(tile_set.gaps(), [(0, 3), (12, 19)]);
+    // Empty tiles are free even though a file covers them.
+    assert_eq!(
+        tile_set.free_ranges(40),
+        [(0, 3), (9, 10), (12, 19), (25, 39)]
+    );
+    assert_eq!(tile_set.free_ranges(22), [(0, 3), (9, 10), (12, 19)]);
+
+    assert_eq!(tile_set.get_tile(9).map(ArtTile::pixel_count), Ok(0));
+    assert_eq!(tile_set.get_tile(24).map(ArtTile::number), Ok(24));
+    assert_eq!(
+        tile_set.file_of(11).map(|f| f.name.as_str()),
+        Some("TILES002.ART")
+    );
+    assert_eq!(
+        tile_set.get_tile(12).err().as_deref(),
+        Some("Tile 12 is not in any .art file.")
+    );
+}
@@ -0,0 +1,55 @@
+//! Checksums shared by the format crates and the image encoders.
+
+// Malformed files are reported as errors and never panic, as the
+// processes that embed these crates cannot recover from a panic.
+// clippy::arithmetic_side_effects is not denied, as it flags every operator
+// including those on bounded values. Arithmetic on values read from files
+// is checked or saturating instead, which tests/error_injection.rs covers.
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::expect_used,
+        clippy::indexing_slicing,
+        clippy::panic,
+        clippy::unwrap_used
+    )
+)]
+
+/// Lookup table for the reflected CRC-32 (IEEE 802.3) polynomial.
+// Built at compile time, where indexing out of bounds fails the build.
+#[allow(clippy::indexing_slicing)]
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes the CRC-32 checksum of `data`, which is the checksum
+/// source ports use to identify .grp files and their entries and that
+/// PNG files use for their chunks.
+///
+/// ```
+/// assert_eq!(checksum::crc32(b"123456789"), 0xCBF4_3926);
+/// ```
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        let index = (crc ^ *byte as u32) as u8;
+        crc = (crc >> 8) ^ CRC32_TABLE.get(index as usize).copied().unwrap_or_default();
+    }
+    !crc
+}
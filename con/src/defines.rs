@@ -0,0 +1,142 @@
+use crate::{find_defines, find_includes, Define};
+use std::collections::BTreeMap;
+
+/// A define together with the file it was found in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DefineSource {
+    pub file: String,
+    pub define: Define,
+}
+
+/// Collects the defines of a script and, recursively, of every script it
+/// includes, in the order the game would read them.
+///
+/// `load` returns the contents of a script by name, or `None` when it does
+/// not exist. Every file is read at most once.
+pub fn collect_defines<F>(root: &str, mut load: F) -> Result<Vec<DefineSource>, String>
+where
+    F: FnMut(&str) -> Result<Option<Vec<u8>>, String>,
+{
+    fn visit<F>(
+        file: &str,
+        load: &mut F,
+        visited: &mut Vec<String>,
+        defines: &mut Vec<DefineSource>,
+    ) -> Result<(), String>
+    where
+        F: FnMut(&str) -> Result<Option<Vec<u8>>, String>,
+    {
+        visited.push(file.to_uppercase());
+        let Some(source) = load(file)? else {
+            return Err(format!("Script \"{}\" not found.", file));
+        };
+
+        // Defines and includes can be interleaved, so walk both in line order.
+        let mut file_defines = find_defines(&source).into_iter().peekable();
+        for (include, line) in find_includes(&source) {
+            while let Some(define) = file_defines.next_if(|d| d.line <= line) {
+                defines.push(DefineSource {
+                    file: file.to_string(),
+                    define,
+                });
+            }
+            if !visited.contains(&include.to_uppercase()) {
+                visit(&include, load, visited, defines)
+                    .map_err(|e| format!("{} (included from {} line {})", e, file, line))?;
+            }
+        }
+        defines.extend(file_defines.map(|define| DefineSource {
+            file: file.to_string(),
+            define,
+        }));
+        Ok(())
+    }
+
+    let mut defines = Vec::new();
+    visit(root, &mut load, &mut Vec::new(), &mut defines)?;
+    Ok(defines)
+}
+
+/// Finds names that are defined more than once with different values,
+/// returning every definition of such names.
+pub fn find_conflicts(defines: &[DefineSource]) -> Vec<(&str, Vec<&DefineSource>)> {
+    let mut by_name = BTreeMap::<&str, Vec<&DefineSource>>::new();
+    for define in defines {
+        by_name.entry(&define.define.name).or_default().push(define);
+    }
+    by_name
+        .into_iter()
+        .filter(|(_, sources)| {
+            sources
+                .first()
+                .is_some_and(|first| sources.iter().any(|s| s.define.value != first.define.value))
+        })
+        .collect()
+}
+
+/// Generates a canonical block of `define` statements: one per name (the first
+/// definition wins, as the game only uses that one), sorted by numeric value
+/// and then by name.
+pub fn canonical_block(defines: &[DefineSource]) -> String {
+    let mut first_defines = BTreeMap::<&str, &str>::new();
+    for define in defines {
+        first_defines
+            .entry(&define.define.name)
+            .or_insert(&define.define.value);
+    }
+
+    let mut sorted = first_defines.into_iter().collect::<Vec<_>>();
+    sorted.sort_by_key(|(name, value)| (value.parse::<i64>().ok(), *value, *name));
+
+    let name_width = sorted.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    sorted
+        .iter()
+        .map(|(name, value)| format!("define {:<width$} {}\n", name, value, width = name_width))
+        .collect()
+}
+
+#[test]
+fn should_report_conflicting_defines_across_includes() {
+    let files = [
+        (
+            "GAME.CON",
+            &b"include defs.con\ndefine EXTRA 5\ninclude user.con\n"[..],
+        ),
+        (
+            "DEFS.CON",
+            b"define LIZTROOP 1680\ndefine RECON 1960\ninclude game.con\n",
+        ),
+        ("USER.CON", b"define LIZTROOP 1680\ndefine RECON 1961\n"),
+    ];
+    let defines = collect_defines("GAME.CON", |name| {
+        Ok(files
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, source)| source.to_vec()))
+    })
+    .unwrap();
+
+    assert_eq!(
+        defines
+            .iter()
+            .map(|d| format!("{}:{}", d.file, d.define.name))
+            .collect::<Vec<_>>(),
+        [
+            "defs.con:LIZTROOP",
+            "defs.con:RECON",
+            "GAME.CON:EXTRA",
+            "user.con:LIZTROOP",
+            "user.con:RECON"
+        ]
+    );
+
+    let conflicts = find_conflicts(&defines);
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].0, "RECON");
+
+    assert_eq!(
+        canonical_block(&defines),
+        "define EXTRA    5\ndefine LIZTROOP 1680\ndefine RECON    1960\n"
+    );
+    assert!(collect_defines("MISSING.CON", |_| Ok(None)).is_err());
+}
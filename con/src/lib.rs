@@ -0,0 +1,349 @@
+//! Lexical tools for CON scripts, the game logic scripts used by Duke Nukem 3D.
+//!
+//! Scripts are handled as raw bytes, because they are usually written in
+//! code page 437 and rewriting tools must preserve everything they do not touch.
+
+// Malformed files are reported as errors and never panic, as the
+// processes that embed these crates cannot recover from a panic.
+// clippy::arithmetic_side_effects is not denied, as it flags every operator
+// including those on bounded values. Arithmetic on values read from files
+// is checked or saturating instead, which tests/error_injection.rs covers.
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::expect_used,
+        clippy::indexing_slicing,
+        clippy::panic,
+        clippy::unwrap_used
+    )
+)]
+
+pub mod defines;
+
+use std::collections::BTreeSet;
+
+/// A whitespace-separated word of a script, outside of comments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Token {
+    /// Byte offset of the first byte of the token.
+    pub start: usize,
+    /// Byte offset one past the last byte of the token.
+    pub end: usize,
+    /// 1-based line number of the token.
+    pub line: usize,
+}
+impl Token {
+    /// The text of the token, or nothing when the token is not from `source`.
+    pub fn text<'a>(&self, source: &'a [u8]) -> &'a [u8] {
+        source.get(self.start..self.end).unwrap_or_default()
+    }
+    fn is_keyword(&self, source: &[u8], keyword: &str) -> bool {
+        self.text(source).eq_ignore_ascii_case(keyword.as_bytes())
+    }
+}
+
+/// Splits a script into tokens, skipping `//` line comments and `/* */` block comments.
+///
+/// ```
+/// let source = b"define PIGCOP 2000 // comment\n/* block */ include \"defs.con\"";
+/// let tokens = con::tokenize(source)
+///     .iter()
+///     .map(|t| String::from_utf8_lossy(t.text(source)).into_owned())
+///     .collect::<Vec<_>>();
+/// assert_eq!(tokens, ["define", "PIGCOP", "2000", "include", "\"defs.con\""]);
+/// ```
+pub fn tokenize(source: &[u8]) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line = 1;
+
+    while let Some(rest) = source.get(i..).filter(|rest| !rest.is_empty()) {
+        match rest {
+            [b'\n', ..] => {
+                line += 1;
+                i += 1;
+            }
+            [b, ..] if b.is_ascii_whitespace() => i += 1,
+            [b'/', b'/', ..] => {
+                i += rest.iter().position(|b| *b == b'\n').unwrap_or(rest.len());
+            }
+            [b'/', b'*', body @ ..] => {
+                let len = body
+                    .windows(2)
+                    .position(|w| w == b"*/")
+                    .unwrap_or(body.len());
+                line += body.iter().take(len).filter(|b| **b == b'\n').count();
+                i += 2 + (len + 2).min(body.len());
+            }
+            _ => {
+                // The first byte starts the token, as the cases above did not match it.
+                let len = (1..rest.len())
+                    .find(|j| {
+                        let rest = rest.get(*j..).unwrap_or_default();
+                        rest.first().is_some_and(u8::is_ascii_whitespace)
+                            || rest.starts_with(b"//")
+                            || rest.starts_with(b"/*")
+                    })
+                    .unwrap_or(rest.len());
+                tokens.push(Token {
+                    start: i,
+                    end: i + len,
+                    line,
+                });
+                i += len;
+            }
+        }
+    }
+
+    tokens
+}
+
+/// A `define NAME VALUE` statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Define {
+    pub name: String,
+    pub value: String,
+    pub line: usize,
+}
+
+/// Finds all `define` statements of a script.
+pub fn find_defines(source: &[u8]) -> Vec<Define> {
+    let tokens = tokenize(source);
+    tokens
+        .windows(3)
+        .filter_map(|w| match w {
+            [keyword, name, value] if keyword.is_keyword(source, "define") => Some(Define {
+                name: String::from_utf8_lossy(name.text(source)).into_owned(),
+                value: String::from_utf8_lossy(value.text(source)).into_owned(),
+                line: keyword.line,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds the file names of all `include` statements of a script, in order.
+/// Quotes around a file name are removed.
+pub fn find_includes(source: &[u8]) -> Vec<(String, usize)> {
+    let tokens = tokenize(source);
+    tokens
+        .windows(2)
+        .filter_map(|w| match w {
+            [keyword, name] if keyword.is_keyword(source, "include") => {
+                let name = String::from_utf8_lossy(name.text(source));
+                Some((name.trim_matches('"').to_string(), keyword.line))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A `definelevelname EPISODE LEVEL FILE PARTIME DESIGNERTIME TITLE` statement.
+/// The episode and level are counted from 0, as in the script.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LevelName {
+    pub episode: u32,
+    pub level: u32,
+    pub file_name: String,
+    /// The rest of the line, which may contain spaces.
+    pub title: String,
+    pub line: usize,
+}
+
+/// Finds all `definelevelname` statements of a script.
+///
+/// ```
+/// let source = b"definelevelname 0 0 e1l1.map 02:30 01:45 HOLLYWOOD HOLOCAUST\r\n";
+/// let level_names = con::find_level_names(source);
+/// assert_eq!(level_names[0].file_name, "e1l1.map");
+/// assert_eq!(level_names[0].title, "HOLLYWOOD HOLOCAUST");
+/// ```
+pub fn find_level_names(source: &[u8]) -> Vec<LevelName> {
+    let tokens = tokenize(source);
+    tokens
+        .windows(6)
+        .filter_map(|w| match w {
+            [keyword, episode, level, file_name, _, designer_time]
+                if keyword.is_keyword(source, "definelevelname") =>
+            {
+                let number = |t: &Token| std::str::from_utf8(t.text(source)).ok()?.parse().ok();
+                let rest = source.get(designer_time.end..).unwrap_or_default();
+                let title = rest.split(|b| *b == b'\n').next().unwrap_or_default();
+                Some(LevelName {
+                    episode: number(episode)?,
+                    level: number(level)?,
+                    file_name: String::from_utf8_lossy(file_name.text(source)).into_owned(),
+                    title: String::from_utf8_lossy(title).trim().to_string(),
+                    line: keyword.line,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Commands that take a tile number, with the index of the tile argument.
+const TILE_COMMANDS: &[(&str, usize)] = &[
+    ("actor", 0),
+    ("cactor", 0),
+    ("debris", 0),
+    ("guts", 0),
+    ("ifactor", 0),
+    ("ifspawnedby", 0),
+    ("ifwasweapon", 0),
+    ("shoot", 0),
+    ("spawn", 0),
+    ("useractor", 1),
+];
+
+/// The tile arguments of commands such as `actor` and `spawn`.
+fn tile_arguments(source: &[u8], tokens: &[Token]) -> Vec<Token> {
+    let mut arguments = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let argument = TILE_COMMANDS
+            .iter()
+            .find(|(keyword, _)| token.is_keyword(source, keyword))
+            .and_then(|(_, argument)| tokens.get(i + 1 + argument));
+        arguments.extend(argument);
+    }
+    arguments
+}
+
+/// The names of defines that a script uses as tile arguments of commands
+/// such as `actor` and `spawn`. Tiles are usually defined in another script
+/// than they are used in, so the names of all scripts of a game are needed
+/// to tell which defines are tiles, see [`find_tile_references`].
+///
+/// ```
+/// let names = con::find_tile_names(b"actor LIZTROOP LIZTROOPSTRENGTH spawn 1405");
+/// assert_eq!(names.into_iter().collect::<Vec<_>>(), ["LIZTROOP"]);
+/// ```
+pub fn find_tile_names(source: &[u8]) -> BTreeSet<String> {
+    tile_arguments(source, &tokenize(source))
+        .iter()
+        .map(|t| t.text(source))
+        .filter(|text| !text.iter().all(u8::is_ascii_digit))
+        .map(|text| String::from_utf8_lossy(text).into_owned())
+        .collect()
+}
+
+/// Finds the tokens of a script that refer to tile numbers: the tile
+/// arguments of commands such as `actor` and `spawn`, and the values of the
+/// `define` statements whose names are in `tile_names`. The tokens may be
+/// numbers or names of defines.
+///
+/// Defines of sounds, strengths and other numbers are not tiles, so only
+/// the defines named in `tile_names`, e.g. collected with
+/// [`find_tile_names`] from every script of a game, are included.
+pub fn find_tile_references(source: &[u8], tile_names: &BTreeSet<String>) -> Vec<Token> {
+    let tokens = tokenize(source);
+    let mut references = tile_arguments(source, &tokens);
+    for (i, token) in tokens.iter().enumerate() {
+        if !token.is_keyword(source, "define") {
+            continue;
+        }
+        if let (Some(name), Some(value)) = (tokens.get(i + 1), tokens.get(i + 2)) {
+            if tile_names.contains(String::from_utf8_lossy(name.text(source)).as_ref()) {
+                references.push(*value);
+            }
+        }
+    }
+    references.sort_by_key(|t| t.start);
+    references
+}
+
+/// Rewrites a script by replacing whole tokens. `replace` is called with the
+/// text of each token, without surrounding quotes, and returns the new text
+/// or `None` to keep the token. Quotes and everything outside of replaced
+/// tokens, including comments, is preserved byte for byte.
+///
+/// Returns the rewritten script and the number of replaced tokens.
+///
+/// ```
+/// let (script, count) = con::replace_tokens(b"definesound 1 \"BOSS1.VOC\" // BOSS1.VOC", |t| {
+///     t.eq_ignore_ascii_case(b"BOSS1.VOC").then(|| b"BOSSROAR.VOC".to_vec())
+/// });
+/// assert_eq!(script, b"definesound 1 \"BOSSROAR.VOC\" // BOSS1.VOC");
+/// assert_eq!(count, 1);
+/// ```
+pub fn replace_tokens<F>(source: &[u8], mut replace: F) -> (Vec<u8>, usize)
+where
+    F: FnMut(&[u8]) -> Option<Vec<u8>>,
+{
+    let mut output = Vec::with_capacity(source.len());
+    let mut copied_until = 0;
+    let mut count = 0;
+
+    for token in tokenize(source) {
+        let text = token.text(source);
+        let quoted = text.len() >= 2 && text.starts_with(b"\"") && text.ends_with(b"\"");
+        let (start, end) = if quoted {
+            (token.start + 1, token.end - 1)
+        } else {
+            (token.start, token.end)
+        };
+
+        // The tokens are from `source`, so the ranges are always there.
+        let range = |from: usize, to: usize| source.get(from..to).unwrap_or_default();
+        if let Some(replacement) = replace(range(start, end)) {
+            output.extend_from_slice(range(copied_until, start));
+            output.extend_from_slice(&replacement);
+            copied_until = end;
+            count += 1;
+        }
+    }
+    output.extend_from_slice(source.get(copied_until..).unwrap_or_default());
+
+    (output, count)
+}
+
+#[test]
+fn should_find_defines_and_includes() {
+    let source = b"include defs.con\r\n\
+        define LIZTROOP 1680 // trooper\n\
+        /* define COMMENTED 1\n*/\n\
+        DEFINE RECON 1960\n\
+        include \"user.con\"\n";
+
+    assert_eq!(
+        find_defines(source),
+        [
+            Define {
+                name: "LIZTROOP".to_string(),
+                value: "1680".to_string(),
+                line: 2
+            },
+            Define {
+                name: "RECON".to_string(),
+                value: "1960".to_string(),
+                line: 5
+            }
+        ]
+    );
+    assert_eq!(
+        find_includes(source),
+        [("defs.con".to_string(), 1), ("user.con".to_string(), 6)]
+    );
+}
+
+#[test]
+fn should_find_tile_references() {
+    let source = b"define BOSS1 2630\n\
+        define BOSS1STRENGTH 2630\n\
+        useractor enemy BOSS1 BOSS1STRENGTH spawn 1405 enda\n\
+        // spawn 99\n\
+        ifactor APLAYER shoot FIRELASER";
+    let tile_names = find_tile_names(source);
+    assert_eq!(
+        tile_names.iter().map(String::as_str).collect::<Vec<_>>(),
+        ["APLAYER", "BOSS1", "FIRELASER"]
+    );
+    let references = find_tile_references(source, &tile_names)
+        .iter()
+        .map(|t| String::from_utf8_lossy(t.text(source)).into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        references,
+        ["2630", "BOSS1", "1405", "APLAYER", "FIRELASER"]
+    );
+}
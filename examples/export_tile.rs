@@ -0,0 +1,62 @@
+//! Exports a tile of an .art file as a PNG image.
+//!
+//! ```text
+//! cargo run --example export_tile -- TILES000.ART PALETTE.DAT 0 TILE0.png
+//! ```
+//!
+//! Without arguments, a tile and a grayscale palette are generated and the
+//! image is written to the temporary directory.
+
+use duke3d_file_utility::{
+    art::{testing::ArtFixture, ArtFileReader},
+    image,
+    palette::{Palette, COLOR_COUNT},
+};
+use std::{io::Cursor, path::PathBuf};
+
+fn main() -> Result<(), String> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (art, palette, tile_number, output_path) = match args.as_slice() {
+        [art_path, palette_path, tile_number, output_path] => {
+            let read = |path: &str| {
+                std::fs::read(path).map_err(|e| format!("Failed to read \"{}\": {}", path, e))
+            };
+            (
+                read(art_path)?,
+                Palette::from_palette_dat(&read(palette_path)?)?,
+                tile_number
+                    .parse::<u32>()
+                    .map_err(|_| format!("Invalid tile number: {}", tile_number))?,
+                PathBuf::from(output_path),
+            )
+        }
+        [] => {
+            let mut colors = [[0u8; 3]; COLOR_COUNT];
+            for (i, color) in colors.iter_mut().enumerate() {
+                *color = [i as u8; 3];
+            }
+            (
+                ArtFixture::new(2472).tile(64, 32, 200).build(),
+                Palette::from_colors(colors),
+                2472,
+                std::env::temp_dir().join("duke3d_example_export_tile.png"),
+            )
+        }
+        _ => return Err("Usage: export_tile [ART PALETTE.DAT TILE OUTPUT.png]".to_string()),
+    };
+
+    let mut art_reader = ArtFileReader::new(Cursor::new(art))?;
+    let tile = art_reader.get_tile(tile_number)?;
+    let rgba = tile.to_rgba(&art_reader.read_pixels(&tile)?, &palette);
+    let png = image::encode_png(tile.width() as u32, tile.height() as u32, &rgba);
+    std::fs::write(&output_path, png)
+        .map_err(|e| format!("Failed to write \"{}\": {}", output_path.display(), e))?;
+    println!(
+        "Tile {} ({}x{}) written to \"{}\"",
+        tile_number,
+        tile.width(),
+        tile.height(),
+        output_path.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,36 @@
+//! Lists the entries of a .grp file with their sizes.
+//!
+//! ```text
+//! cargo run --example list_grp -- DUKE3D.GRP
+//! ```
+//!
+//! Without a path, a small archive is generated in the temporary directory
+//! and listed instead.
+
+use duke3d_file_utility::grp::{testing::GrpFixture, GrpFileReader};
+use std::{fs::File, path::PathBuf};
+
+fn main() -> Result<(), String> {
+    let path = match std::env::args().nth(1) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let path = std::env::temp_dir().join("duke3d_example_list_grp.grp");
+            GrpFixture::new()
+                .entry("GAME.CON", b"include defs.con")
+                .entry("DEFS.CON", b"define BOSS1 2630")
+                .entry("E1L1.MAP", &[7, 0, 0, 0])
+                .write_to(&path)
+                .map_err(|e| e.to_string())?;
+            path
+        }
+    };
+
+    let file =
+        File::open(&path).map_err(|e| format!("Failed to open \"{}\": {}", path.display(), e))?;
+    let grp_reader = GrpFileReader::new(file)?;
+    for entry in grp_reader.entries() {
+        println!("{:<12} {:>10}", entry.name(), entry.size());
+    }
+    println!("{} entries", grp_reader.file_count);
+    Ok(())
+}
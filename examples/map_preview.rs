@@ -0,0 +1,84 @@
+//! Renders the walls of a .map file from above as a PNG image: solid walls
+//! in white and walls between sectors in red, as the Build editor draws them.
+//!
+//! ```text
+//! cargo run --example map_preview -- E1L1.MAP E1L1.png
+//! ```
+//!
+//! Without arguments, two generated rooms are rendered to the temporary
+//! directory.
+
+use duke3d_file_utility::{
+    image,
+    map::{testing::two_rooms, Map},
+};
+use std::path::PathBuf;
+
+/// The width and height of the image.
+const SIZE: i64 = 256;
+
+fn main() -> Result<(), String> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (map, output_path) = match args.as_slice() {
+        [map_path, output_path] => {
+            let data = std::fs::read(map_path)
+                .map_err(|e| format!("Failed to read \"{}\": {}", map_path, e))?;
+            (Map::parse(&data)?, PathBuf::from(output_path))
+        }
+        [] => (
+            two_rooms(),
+            std::env::temp_dir().join("duke3d_example_map_preview.png"),
+        ),
+        _ => return Err("Usage: map_preview [MAP OUTPUT.png]".to_string()),
+    };
+
+    // Fit the walls in the image, keeping a margin of one pixel.
+    let xs = map.walls.iter().map(|w| w.x as i64);
+    let ys = map.walls.iter().map(|w| w.y as i64);
+    let (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) =
+        (xs.clone().min(), xs.max(), ys.clone().min(), ys.max())
+    else {
+        return Err("The map has no walls.".to_string());
+    };
+    let extent = (max_x - min_x).max(max_y - min_y).max(1);
+    let to_pixel = |x: i32, y: i32| {
+        (
+            1 + (x as i64 - min_x) * (SIZE - 3) / extent,
+            1 + (y as i64 - min_y) * (SIZE - 3) / extent,
+        )
+    };
+
+    let mut rgba = [0, 0, 0, 255].repeat((SIZE * SIZE) as usize);
+    for wall in &map.walls {
+        let Some(next) = map.walls.get(wall.point2 as usize) else {
+            continue;
+        };
+        let color = if wall.next_wall >= 0 {
+            [255, 0, 0, 255]
+        } else {
+            [255, 255, 255, 255]
+        };
+        let (x0, y0) = to_pixel(wall.x, wall.y);
+        let (x1, y1) = to_pixel(next.x, next.y);
+        let steps = (x1 - x0).abs().max((y1 - y0).abs()).max(1);
+        for step in 0..=steps {
+            let x = x0 + (x1 - x0) * step / steps;
+            let y = y0 + (y1 - y0) * step / steps;
+            let at = ((y * SIZE + x) * 4) as usize;
+            rgba[at..at + 4].copy_from_slice(&color);
+        }
+    }
+
+    std::fs::write(
+        &output_path,
+        image::encode_png(SIZE as u32, SIZE as u32, &rgba),
+    )
+    .map_err(|e| format!("Failed to write \"{}\": {}", output_path.display(), e))?;
+    println!(
+        "{} sectors and {} walls drawn to \"{}\"",
+        map.sectors.len(),
+        map.walls.len(),
+        output_path.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,514 @@
+use crate::{
+    cancel::{self, CancellationToken},
+    fs_name,
+    layout::{GrpHeader, GrpTableRecord},
+    metrics,
+    temp_file::TempFile,
+    CountingReader, EntryName, Sha256,
+};
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// What [`GrpBuilder`] does when an entry with an existing name is added.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Refuse to add the entry.
+    #[default]
+    Error,
+    /// Replace the data of the existing entry, keeping its position.
+    Replace,
+    /// Keep the existing entry and ignore the new one.
+    Skip,
+}
+
+/// Where the contents of an entry come from when the archive is written.
+enum EntrySource {
+    Memory(Vec<u8>),
+    /// A file that is copied through in chunks. The size is taken from the
+    /// file metadata when the entry is added.
+    Path {
+        path: PathBuf,
+        size: u32,
+    },
+    /// An entry of an existing archive, copied through in chunks.
+    Archive {
+        path: PathBuf,
+        offset: u64,
+        size: u32,
+    },
+}
+impl EntrySource {
+    fn size(&self) -> u32 {
+        match self {
+            EntrySource::Memory(data) => data.len() as u32,
+            EntrySource::Path { size, .. } | EntrySource::Archive { size, .. } => *size,
+        }
+    }
+}
+
+/// Passes writes through, hashing them on the way when there is a hasher.
+struct DigestWriter<'a, W: Write> {
+    inner: &'a mut W,
+    hasher: Option<Sha256>,
+}
+impl<W: Write> Write for DigestWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.write_all(buf)?;
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(buf);
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Builder for creating a new .grp file.
+///
+/// Entries are validated when they are added, so that errors such as
+/// too long names are reported before anything is written.
+///
+/// ```
+/// use grp::GrpBuilder;
+///
+/// let mut bytes = Vec::new();
+/// GrpBuilder::new()
+///     .add_file("E1L1.MAP", vec![7, 0, 0, 0])?
+///     .add_file("GAME.CON", b"include defs.con".to_vec())?
+///     .write_to(&mut bytes)?;
+///
+/// assert_eq!(&bytes[..12], b"KenSilverman");
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Default)]
+pub struct GrpBuilder {
+    cancellation: Option<CancellationToken>,
+    duplicate_policy: DuplicatePolicy,
+    entries: Vec<(EntryName, EntrySource)>,
+}
+impl GrpBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Makes [`GrpBuilder::write_to`] stop with an error once the token is
+    /// cancelled. The token is checked between entries and between chunks
+    /// of streamed entries.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+    pub fn duplicate_policy(mut self, duplicate_policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = duplicate_policy;
+        self
+    }
+    /// Adds an entry with the given name and contents to the end of the archive.
+    pub fn add_file(self, name: &str, data: Vec<u8>) -> Result<Self, String> {
+        self.add_entry(name, data.len() as u64, EntrySource::Memory(data))
+    }
+    /// Adds a file from the file system, named after the file name of the path.
+    /// The file is read into memory right away.
+    pub fn add_from_path(self, path: &Path) -> Result<Self, String> {
+        let name = Self::entry_name_of(path)?;
+        let data = std::fs::read(path)
+            .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+        self.add_file(name, data)
+    }
+    /// Adds a file from the file system like [`Self::add_from_path`], but only
+    /// records its size now and copies the contents through in chunks when the
+    /// archive is written, so that large inputs never have to fit in memory.
+    pub fn add_from_path_streamed(self, path: &Path) -> Result<Self, String> {
+        let name = Self::entry_name_of(path)?;
+        self.add_from_path_streamed_as(EntryName::new(name)?, path)
+    }
+    /// Adds every file directly inside `dir` like [`Self::add_from_path_streamed`],
+    /// in order of their file names. File names are mapped to entry names with
+    /// [`fs_name::from_fs_name`], and hidden files, whose names start with a
+    /// dot, are skipped. Files whose names only differ in case would become
+    /// the same entry, so they are reported whatever the duplicate policy.
+    pub fn add_dir(mut self, dir: &Path) -> Result<Self, String> {
+        let dir_entries = std::fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read directory \"{}\": {}", dir.display(), e))?;
+        let mut files = Vec::new();
+        for dir_entry in dir_entries {
+            let dir_entry =
+                dir_entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+            let file_name = dir_entry.file_name().to_string_lossy().into_owned();
+            if dir_entry.path().is_file() && !file_name.starts_with('.') {
+                files.push((file_name, dir_entry.path()));
+            }
+        }
+        files.sort();
+
+        let mut added: Vec<(EntryName, String)> = Vec::new();
+        for (file_name, path) in files {
+            let name = fs_name::from_fs_name(&file_name)?;
+            if let Some((_, other)) = added.iter().find(|(n, _)| *n == name) {
+                return Err(format!(
+                    "Files \"{}\" and \"{}\" in \"{}\" would both be packed as \"{}\".",
+                    other,
+                    file_name,
+                    dir.display(),
+                    name
+                ));
+            }
+            self = self.add_from_path_streamed_as(name, &path)?;
+            added.push((name, file_name));
+        }
+        Ok(self)
+    }
+    /// Like [`Self::add_from_path_streamed`], but under an entry name given
+    /// by the caller, e.g. one decoded with [`fs_name::from_fs_name`].
+    pub fn add_from_path_streamed_as(self, name: EntryName, path: &Path) -> Result<Self, String> {
+        let size = std::fs::metadata(path)
+            .map_err(|e| format!("Failed to read metadata of \"{}\": {}", path.display(), e))?
+            .len();
+        let source = EntrySource::Path {
+            path: path.to_path_buf(),
+            size: size.try_into().unwrap_or(u32::MAX),
+        };
+        self.add_named_entry(name, size, source)
+    }
+    fn entry_name_of(path: &Path) -> Result<&str, String> {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| format!("Path \"{}\" has no valid file name.", path.display()))
+    }
+    /// Adds an entry of an existing archive, keeping its name as it is.
+    /// The duplicate policy only applies to new entries, so that archives
+    /// which already contain a name twice are copied as they are.
+    pub(crate) fn add_archive_entry(
+        mut self,
+        name: EntryName,
+        archive_path: &Path,
+        offset: u64,
+        size: u32,
+    ) -> Result<Self, String> {
+        let source = EntrySource::Archive {
+            path: archive_path.to_path_buf(),
+            offset,
+            size,
+        };
+        self.entries.push((name, source));
+        Ok(self)
+    }
+    /// Adds an entry with an already validated name.
+    pub(crate) fn add_named_file(self, name: EntryName, data: Vec<u8>) -> Result<Self, String> {
+        self.add_named_entry(name, data.len() as u64, EntrySource::Memory(data))
+    }
+    /// The number of entries added so far.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// The names and sizes of the entries in the order they will be written.
+    pub(crate) fn table(&self) -> Vec<(EntryName, u32)> {
+        self.entries
+            .iter()
+            .map(|(name, source)| (*name, source.size()))
+            .collect()
+    }
+    fn add_entry(self, name: &str, size: u64, source: EntrySource) -> Result<Self, String> {
+        self.add_named_entry(EntryName::new(name)?, size, source)
+    }
+    fn add_named_entry(
+        mut self,
+        name: EntryName,
+        size: u64,
+        source: EntrySource,
+    ) -> Result<Self, String> {
+        if u32::try_from(size).is_err() {
+            return Err(format!(
+                "Entry \"{}\" is {} bytes long, which does not fit in a .grp file.",
+                name, size
+            ));
+        }
+
+        match self.entries.iter_mut().find(|(n, _)| *n == name) {
+            Some(_) if self.duplicate_policy == DuplicatePolicy::Error => {
+                return Err(format!("Entry \"{}\" has already been added.", name));
+            }
+            Some((_, existing)) if self.duplicate_policy == DuplicatePolicy::Replace => {
+                *existing = source;
+            }
+            Some(_) => {}
+            None => self.entries.push((name, source)),
+        }
+
+        Ok(self)
+    }
+    /// Writes the signature, the entry table and the entry contents in order.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), String> {
+        self.write_entries(writer, false).map(drop)
+    }
+    /// Like [`GrpBuilder::write_to`], but also returns the SHA-256 of every
+    /// entry that was not copied from an existing archive, computed from the
+    /// bytes as they are written rather than by reading the sources again.
+    pub fn write_to_digested<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<Vec<(EntryName, [u8; 32])>, String> {
+        self.write_entries(writer, true)
+    }
+    fn write_entries<W: Write>(
+        &self,
+        writer: &mut W,
+        digest: bool,
+    ) -> Result<Vec<(EntryName, [u8; 32])>, String> {
+        let map_err = |_| "Failed to write .grp file.";
+
+        let header = GrpHeader::new(self.entries.len() as u32);
+        writer.write_all(&header.to_bytes()).map_err(map_err)?;
+        for (name, source) in &self.entries {
+            let record = GrpTableRecord {
+                name: *name,
+                size: source.size(),
+            };
+            writer.write_all(&record.to_bytes()).map_err(map_err)?;
+        }
+        let token = self.cancellation.as_ref();
+        let mut digests = Vec::new();
+        for (name, source) in &self.entries {
+            CancellationToken::check(token)?;
+            let hasher =
+                (digest && !matches!(source, EntrySource::Archive { .. })).then(Sha256::new);
+            let mut writer = DigestWriter {
+                inner: &mut *writer,
+                hasher,
+            };
+            match source {
+                EntrySource::Memory(data) => writer.write_all(data).map_err(map_err)?,
+                EntrySource::Path { path, size } => {
+                    let file = File::open(path)
+                        .map_err(|e| format!("Failed to open \"{}\": {}", path.display(), e))?;
+                    // Copy one byte past the recorded size to detect files
+                    // that have grown since they were added.
+                    let copied = cancel::copy_chunked(
+                        &mut file.take(*size as u64 + 1),
+                        &mut writer,
+                        token,
+                        &|| format!("\"{}\"", path.display()),
+                    )?;
+                    if copied != *size as u64 {
+                        return Err(format!(
+                            "Entry \"{}\" changed size while the archive was written (expected {} bytes).",
+                            name, size
+                        ));
+                    }
+                }
+                EntrySource::Archive { path, offset, size } => {
+                    let mut file = File::open(path)
+                        .map(CountingReader::new)
+                        .map_err(|e| format!("Failed to open \"{}\": {}", path.display(), e))?;
+                    file.seek(SeekFrom::Start(*offset))
+                        .map_err(|_| "Failed to seek to file offset.")?;
+                    let copied = cancel::copy_chunked(
+                        &mut (&mut file).take(*size as u64),
+                        &mut writer,
+                        token,
+                        &|| format!("\"{}\"", path.display()),
+                    )?;
+                    metrics::record(|m| m.bytes_read(file.bytes_read()));
+                    if copied != *size as u64 {
+                        return Err(format!(
+                            "Entry \"{}\" extends past the end of \"{}\".",
+                            name,
+                            path.display()
+                        ));
+                    }
+                }
+            }
+            metrics::record(|m| m.entry_written(*name));
+            if let Some(hasher) = writer.hasher {
+                digests.push((*name, hasher.finish()));
+            }
+        }
+
+        Ok(digests)
+    }
+    /// Writes the archive into a temporary file next to `path`, which is
+    /// synced to disk and then renamed over `path`. An existing file at
+    /// `path` is only replaced once the whole archive has been written.
+    pub fn write_to_file(&self, path: &Path) -> Result<(), String> {
+        self.write_to_file_checked(path, || Ok(()))
+    }
+    /// Like [`GrpBuilder::write_to_file`], but calls `check` once the archive
+    /// has been written and leaves `path` untouched if it fails, e.g. with
+    /// [`crate::lock::ArchiveLock::check_unchanged`].
+    pub fn write_to_file_checked(
+        &self,
+        path: &Path,
+        check: impl FnOnce() -> Result<(), String>,
+    ) -> Result<(), String> {
+        self.write_file(path, check, false).map(drop)
+    }
+    /// Like [`GrpBuilder::write_to_file_checked`], but also returns digests
+    /// like [`GrpBuilder::write_to_digested`].
+    pub(crate) fn write_to_file_digested(
+        &self,
+        path: &Path,
+        check: impl FnOnce() -> Result<(), String>,
+    ) -> Result<Vec<(EntryName, [u8; 32])>, String> {
+        self.write_file(path, check, true)
+    }
+    fn write_file(
+        &self,
+        path: &Path,
+        check: impl FnOnce() -> Result<(), String>,
+        digest: bool,
+    ) -> Result<Vec<(EntryName, [u8; 32])>, String> {
+        let mut temp_file = TempFile::new(path)?;
+        let mut writer = BufWriter::new(temp_file.file());
+        let digests = self.write_entries(&mut writer, digest)?;
+        writer.flush().map_err(|_| "Failed to write .grp file.")?;
+        drop(writer);
+        check()?;
+        temp_file.persist()?;
+        Ok(digests)
+    }
+}
+
+#[test]
+fn should_round_trip_through_reader() {
+    let file_path = std::env::temp_dir().join("grp_should_round_trip_through_reader.grp");
+    let mut file = std::fs::File::create(&file_path).unwrap();
+    GrpBuilder::new()
+        .duplicate_policy(DuplicatePolicy::Replace)
+        .add_file("defs.con", b"define".to_vec())
+        .unwrap()
+        .add_file("E1L1.MAP", vec![7, 0, 0, 0])
+        .unwrap()
+        .add_file("DEFS.CON", b"define TILE 1".to_vec())
+        .unwrap()
+        .write_to(&mut file)
+        .unwrap();
+    drop(file);
+
+    let file = std::fs::File::open(&file_path).unwrap();
+    let mut grp_reader = crate::GrpFileReader::new(&file).unwrap();
+    let entries = grp_reader.get_file_entries().unwrap();
+    assert_eq!(
+        entries
+            .iter()
+            .map(|e| e.name().to_string())
+            .collect::<Vec<_>>(),
+        ["DEFS.CON", "E1L1.MAP"]
+    );
+    assert_eq!(grp_reader.read_file(&entries[0]).unwrap(), b"define TILE 1");
+
+    assert!(GrpBuilder::new()
+        .add_file("GAME.CON", Vec::new())
+        .unwrap()
+        .add_file("GAME.CON", Vec::new())
+        .is_err());
+    assert!(GrpBuilder::new()
+        .add_file("TOO_LONG_NAME.CON", Vec::new())
+        .is_err());
+}
+
+#[test]
+fn should_stream_files_from_disk() {
+    let dir = std::env::temp_dir().join("grp_should_stream_files_from_disk");
+    std::fs::create_dir_all(&dir).unwrap();
+    let map_path = dir.join("E1L1.MAP");
+    std::fs::write(&map_path, vec![7u8; 100_000]).unwrap();
+
+    let mut bytes = Vec::new();
+    GrpBuilder::new()
+        .add_from_path_streamed(&map_path)
+        .unwrap()
+        .write_to(&mut bytes)
+        .unwrap();
+    assert_eq!(
+        bytes.len(),
+        crate::HEADER_SIZE + crate::TABLE_RECORD_SIZE + 100_000
+    );
+    assert_eq!(&bytes[crate::HEADER_SIZE..][..8], b"E1L1.MAP");
+
+    // A file that grows after being added must not produce a corrupt archive.
+    let builder = GrpBuilder::new().add_from_path_streamed(&map_path).unwrap();
+    std::fs::write(&map_path, vec![7u8; 100_001]).unwrap();
+    assert!(builder.write_to(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn should_pack_dir_into_file() {
+    let dir = std::env::temp_dir().join("grp_should_pack_dir_into_file");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("user.con"), b"gamestartup").unwrap();
+    std::fs::write(dir.join("E1L1.MAP"), [7, 0, 0, 0]).unwrap();
+    std::fs::write(dir.join("WHAT%3F.TXT"), b"?").unwrap();
+    std::fs::write(dir.join(".grpsync"), b"").unwrap();
+
+    let file_path = std::env::temp_dir().join("grp_should_pack_dir_into_file.grp");
+    GrpBuilder::new()
+        .add_dir(&dir)
+        .unwrap()
+        .write_to_file(&file_path)
+        .unwrap();
+
+    let file = File::open(&file_path).unwrap();
+    let mut grp_reader = crate::GrpFileReader::new(&file).unwrap();
+    let entries = grp_reader.get_file_entries().unwrap();
+    assert_eq!(
+        entries
+            .iter()
+            .map(|e| e.name().to_string())
+            .collect::<Vec<_>>(),
+        ["E1L1.MAP", "WHAT?.TXT", "USER.CON"]
+    );
+    assert_eq!(grp_reader.read_file(&entries[2]).unwrap(), b"gamestartup");
+}
+
+#[test]
+fn should_report_case_collisions_in_dir() {
+    let dir = std::env::temp_dir().join("grp_should_report_case_collisions_in_dir");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("GAME.CON"), b"define").unwrap();
+    std::fs::write(dir.join("game.con"), b"gamestartup").unwrap();
+
+    // Case-insensitive file systems cannot hold both files.
+    if std::fs::read(dir.join("GAME.CON")).unwrap() == b"define" {
+        let result = GrpBuilder::new()
+            .duplicate_policy(DuplicatePolicy::Replace)
+            .add_dir(&dir);
+        assert_eq!(
+            result.err(),
+            Some(format!(
+                "Files \"GAME.CON\" and \"game.con\" in \"{}\" would both be packed as \"GAME.CON\".",
+                dir.display()
+            ))
+        );
+    }
+}
+
+#[test]
+fn should_copy_duplicate_archive_entries() {
+    let file_path = std::env::temp_dir().join("grp_should_copy_duplicate_archive_entries.grp");
+    crate::testing::GrpFixture::new()
+        .entry("GAME.CON", b"define")
+        .entry("GAME.CON", b"gamestartup")
+        .write_to(&file_path)
+        .unwrap();
+    let file = File::open(&file_path).unwrap();
+    let entries = crate::GrpFileReader::new(&file)
+        .unwrap()
+        .get_file_entries()
+        .unwrap();
+
+    let mut builder = GrpBuilder::new();
+    for entry in &entries {
+        builder = builder
+            .add_archive_entry(entry.name(), &file_path, entry.offset, entry.size)
+            .unwrap();
+    }
+    assert_eq!(builder.len(), 2);
+    assert!(builder.add_file("GAME.CON", b"include".to_vec()).is_err());
+}
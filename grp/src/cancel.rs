@@ -0,0 +1,82 @@
+use std::{
+    io::{ErrorKind, Read, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Size of the chunks that long copies are split into, between which
+/// a [`CancellationToken`] is checked.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// A flag for aborting a long operation, such as extracting an entry or
+/// rewriting an archive, from another thread.
+///
+/// Clones share the same flag. Operations check it between chunks and fail
+/// with an error once it is set; an archive that was being rewritten is
+/// left as it was.
+///
+/// ```
+/// use grp::{CancellationToken, GrpBuilder};
+///
+/// let token = CancellationToken::new();
+/// let builder = GrpBuilder::new()
+///     .cancellation(token.clone())
+///     .add_file("E1L1.MAP", vec![7; 1024])
+///     .unwrap();
+///
+/// token.cancel();
+/// assert!(builder.write_to(&mut Vec::new()).is_err());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+    pub(crate) fn check(token: Option<&Self>) -> Result<(), String> {
+        match token {
+            Some(token) if token.is_cancelled() => Err("Operation was cancelled.".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Copies everything from `reader` to `writer` in chunks, checking the
+/// token before each chunk. `describe` names the copied data in errors.
+pub(crate) fn copy_chunked<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    token: Option<&CancellationToken>,
+    describe: &dyn Fn() -> String,
+) -> Result<u64, String> {
+    let mut buf = vec![0u8; CHUNK_BYTES];
+    let mut copied = 0;
+    loop {
+        CancellationToken::check(token)?;
+        let read = match reader.read(&mut buf) {
+            Ok(0) => return Ok(copied),
+            Ok(read) => read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(format!("Failed to copy {}: {}", describe(), err)),
+        };
+        // A misbehaving reader may claim to have read more than the buffer holds.
+        let chunk = buf.get(..read).ok_or_else(|| {
+            format!(
+                "Failed to copy {}: the reader read too many bytes",
+                describe()
+            )
+        })?;
+        writer
+            .write_all(chunk)
+            .map_err(|e| format!("Failed to copy {}: {}", describe(), e))?;
+        copied += read as u64;
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    io::{self, BufRead, Read, Seek, SeekFrom},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// Wraps any reader and counts the bytes read through it, e.g. to show the
+/// progress or throughput of a parser that takes a reader.
+///
+/// The count is shared, so another thread can observe it while the reader
+/// is in use. Seeking does not change the count.
+///
+/// ```
+/// use grp::{testing::GrpFixture, CountingReader, GrpFileReader};
+/// use std::io::Cursor;
+///
+/// let bytes = GrpFixture::new().entry("GAME.CON", b"define").build();
+/// let len = bytes.len() as u64;
+/// let reader = CountingReader::new(Cursor::new(bytes));
+/// let counter = reader.counter();
+///
+/// let mut grp_reader = GrpFileReader::new(reader).unwrap();
+/// let entry = grp_reader.find_file_entry("GAME.CON").unwrap().unwrap();
+/// grp_reader.read_file(&entry).unwrap();
+/// // The reader buffers ahead and reads again after seeking, so the count
+/// // is what was read from the underlying reader, not the size of the data.
+/// assert!(counter.load(std::sync::atomic::Ordering::Relaxed) >= len);
+/// ```
+#[derive(Debug)]
+pub struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+impl<R> CountingReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::with_counter(inner, Arc::default())
+    }
+    /// Adds the read bytes to an existing counter, e.g. one shared by the
+    /// readers of several files.
+    pub fn with_counter(inner: R, count: Arc<AtomicU64>) -> Self {
+        Self { inner, count }
+    }
+    /// The number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+    /// The shared counter of read bytes.
+    pub fn counter(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.count)
+    }
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+    fn consume(&mut self, amount: usize) {
+        self.count.fetch_add(amount as u64, Ordering::Relaxed);
+        self.inner.consume(amount);
+    }
+}
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
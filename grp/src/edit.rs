@@ -0,0 +1,529 @@
+use crate::{
+    lock::ArchiveLock,
+    temp_file,
+    undo::{self, UndoRecord},
+    CancellationToken, EntryName, GrpBuilder, GrpFileEntry, GrpFileReader,
+};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+/// Contents for an entry that is added or replaced in an [`EditSession`].
+pub enum EntryData {
+    Bytes(Vec<u8>),
+    /// A file that is copied through in chunks when the session is applied.
+    Path(PathBuf),
+}
+
+enum Edit {
+    Add(String, EntryData),
+    Replace(String, EntryData),
+    Remove(String),
+    Rename(String, String),
+    Rearrange(Vec<(String, String)>),
+}
+
+/// A batch of edits to an existing .grp file.
+///
+/// Edits are only recorded until [`EditSession::apply`] is called, which
+/// rewrites the archive once: into a temporary file next to it, which is
+/// synced to disk and then renamed over the original. Entries that are not
+/// touched are copied over from the original archive in chunks.
+///
+/// Before anything is read, [`EditSession::preflight`] checks that the
+/// archive and its undo journal can be written, so that a read-only
+/// archive is reported up front rather than after the rewrite.
+///
+/// While applying, the original archive is locked with an [`ArchiveLock`],
+/// so that another session on it fails, and it is only replaced if no other
+/// program changed it in the meantime.
+///
+/// ```
+/// use grp::{edit::{EditSession, EntryData}, testing::GrpFixture, GrpFileReader};
+/// use std::fs::File;
+///
+/// let path = std::env::temp_dir().join("grp_doc_edit_session.grp");
+/// GrpFixture::new()
+///     .entry("GAME.CON", b"include defs.con")
+///     .entry("DEMO1.DMO", &[0; 64])
+///     .write_to(&path)
+///     .unwrap();
+///
+/// let mut session = EditSession::new(&path);
+/// session
+///     .remove("DEMO1.DMO")
+///     .replace("GAME.CON", EntryData::Bytes(b"include user.con".to_vec()))
+///     .add("E1L1.MAP", EntryData::Bytes(vec![7, 0, 0, 0]));
+/// session.apply().unwrap();
+///
+/// let file = File::open(&path).unwrap();
+/// let mut grp_reader = GrpFileReader::new(&file).unwrap();
+/// let names = grp_reader
+///     .get_file_entries()
+///     .unwrap()
+///     .iter()
+///     .map(|e| e.name().to_string())
+///     .collect::<Vec<_>>();
+/// assert_eq!(names, ["GAME.CON", "E1L1.MAP"]);
+/// ```
+pub struct EditSession {
+    cancellation: Option<CancellationToken>,
+    edits: Vec<Edit>,
+    output: Option<PathBuf>,
+    path: PathBuf,
+    undo_description: Option<String>,
+}
+impl EditSession {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            cancellation: None,
+            edits: Vec::new(),
+            output: None,
+            path: path.into(),
+            undo_description: None,
+        }
+    }
+    /// Makes [`EditSession::apply`] stop with an error once the token is
+    /// cancelled, leaving the archive untouched.
+    pub fn cancellation(&mut self, token: CancellationToken) -> &mut Self {
+        self.cancellation = Some(token);
+        self
+    }
+    /// Writes the edited archive to `path` instead of replacing the
+    /// original, which is left untouched.
+    pub fn output(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.output = Some(path.into());
+        self
+    }
+    /// Records the inverse of the edits in the undo journal of the archive
+    /// when applying, so that [`crate::undo::undo_last`] can revert them.
+    pub fn record_undo(&mut self, description: &str) -> &mut Self {
+        self.undo_description = Some(description.to_string());
+        self
+    }
+    /// Adds a new entry to the end of the archive.
+    /// Applying fails if an entry with the same name exists.
+    pub fn add(&mut self, name: &str, data: EntryData) -> &mut Self {
+        self.edits.push(Edit::Add(name.to_string(), data));
+        self
+    }
+    /// Replaces the contents of an existing entry, keeping its position.
+    pub fn replace(&mut self, name: &str, data: EntryData) -> &mut Self {
+        self.edits.push(Edit::Replace(name.to_string(), data));
+        self
+    }
+    /// Removes an existing entry.
+    pub fn remove(&mut self, name: &str) -> &mut Self {
+        self.edits.push(Edit::Remove(name.to_string()));
+        self
+    }
+    /// Renames an existing entry, keeping its position and contents.
+    pub fn rename(&mut self, name: &str, new_name: &str) -> &mut Self {
+        self.edits
+            .push(Edit::Rename(name.to_string(), new_name.to_string()));
+        self
+    }
+    /// Puts all entries into the given order, renaming them at the same
+    /// time. Every entry is listed once by its current name, with its new
+    /// name, so names can also be swapped.
+    pub fn rearrange(&mut self, entries: Vec<(String, String)>) -> &mut Self {
+        self.edits.push(Edit::Rearrange(entries));
+        self
+    }
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+    /// The file that applying writes, the original archive unless
+    /// [`EditSession::output`] was called.
+    pub fn target(&self) -> &Path {
+        self.output.as_deref().unwrap_or(&self.path)
+    }
+    /// Checks that applying can write the target archive and, when undo
+    /// is recorded, its journal. [`EditSession::apply`] runs this first,
+    /// but it can be called earlier to fail before expensive preparation.
+    pub fn preflight(&self) -> Result<(), String> {
+        temp_file::check_writable(self.target())?;
+        if self.undo_description.is_some() {
+            temp_file::check_writable(&undo::journal_path_for(self.target()))?;
+        }
+        Ok(())
+    }
+    /// Applies all edits in the order they were recorded with a single rewrite.
+    /// If any edit is invalid, the archive is left untouched.
+    pub fn apply(self) -> Result<(), String> {
+        self.apply_entries(false).map(drop)
+    }
+    /// Like [`EditSession::apply`], but also returns the SHA-256 of every
+    /// added or replaced entry, computed while the entries are written.
+    pub fn apply_with_digests(self) -> Result<Vec<(EntryName, [u8; 32])>, String> {
+        self.apply_entries(true)
+    }
+    fn apply_entries(self, digest: bool) -> Result<Vec<(EntryName, [u8; 32])>, String> {
+        enum Slot {
+            Original(GrpFileEntry),
+            New(EntryData),
+        }
+
+        self.preflight()?;
+        let lock = ArchiveLock::acquire(&self.path)?;
+        let target = self.target().to_path_buf();
+        let file = File::open(&self.path)
+            .map_err(|e| format!("Failed to open \"{}\": {}", self.path.display(), e))?;
+        let mut grp_reader = GrpFileReader::new(&file)?;
+        let original_entries = grp_reader.get_file_entries()?;
+        let mut slots = original_entries
+            .iter()
+            .map(|e| (e.name(), Slot::Original(e.clone())))
+            .collect::<Vec<_>>();
+        // The original entries that are removed, replaced or renamed.
+        let mut touched = Vec::new();
+
+        let position =
+            |slots: &[(EntryName, Slot)], name: &str| slots.iter().position(|(n, _)| *n == name);
+        for edit in self.edits {
+            match edit {
+                Edit::Add(name, data) => {
+                    if position(&slots, &name).is_some() {
+                        return Err(format!("Entry \"{}\" already exists.", name));
+                    }
+                    slots.push((EntryName::new(&name)?, Slot::New(data)));
+                }
+                Edit::Replace(name, data) => {
+                    let Some(slot) = position(&slots, &name).and_then(|i| slots.get_mut(i)) else {
+                        return Err(format!("Entry \"{}\" does not exist.", name));
+                    };
+                    if let Slot::Original(entry) = std::mem::replace(&mut slot.1, Slot::New(data)) {
+                        touched.push(entry);
+                    }
+                }
+                Edit::Remove(name) => {
+                    let Some(i) = position(&slots, &name) else {
+                        return Err(format!("Entry \"{}\" does not exist.", name));
+                    };
+                    if let Slot::Original(entry) = slots.remove(i).1 {
+                        touched.push(entry);
+                    }
+                }
+                Edit::Rename(name, new_name) => {
+                    let Some(i) = position(&slots, &name) else {
+                        return Err(format!("Entry \"{}\" does not exist.", name));
+                    };
+                    if position(&slots, &new_name).is_some_and(|j| j != i) {
+                        return Err(format!("Entry \"{}\" already exists.", new_name));
+                    }
+                    // The previous contents are saved under the old name for undoing.
+                    let new_name = EntryName::new(&new_name)?;
+                    if let Some(slot) = slots.get_mut(i) {
+                        if let Slot::Original(entry) = &slot.1 {
+                            touched.push(entry.clone());
+                        }
+                        slot.0 = new_name;
+                    }
+                }
+                Edit::Rearrange(entries) => {
+                    let mut unlisted = std::mem::take(&mut slots);
+                    for (name, new_name) in entries {
+                        // Listing an entry twice finds it missing the second time.
+                        let Some(i) = position(&unlisted, &name) else {
+                            return Err(format!("Entry \"{}\" does not exist.", name));
+                        };
+                        let (old_name, slot) = unlisted.remove(i);
+                        // Kept names are not validated again, as archives
+                        // written by other tools may have names that
+                        // EntryName::new rejects.
+                        let new_name = if new_name == name {
+                            old_name
+                        } else {
+                            EntryName::new(&new_name)?
+                        };
+                        if slots.iter().any(|(n, _)| *n == new_name) {
+                            return Err(format!("Entry \"{}\" already exists.", new_name));
+                        }
+                        if let Slot::Original(entry) = &slot {
+                            if entry.name() != new_name {
+                                touched.push(entry.clone());
+                            }
+                        }
+                        slots.push((new_name, slot));
+                    }
+                    if let Some((name, _)) = unlisted.first() {
+                        return Err(format!("Entry \"{}\" is not listed.", name));
+                    }
+                }
+            }
+        }
+
+        let mut builder = GrpBuilder::new();
+        if let Some(token) = self.cancellation {
+            builder = builder.cancellation(token);
+        }
+        for (name, slot) in slots {
+            builder = match slot {
+                Slot::Original(entry) => {
+                    builder.add_archive_entry(name, &self.path, entry.offset, entry.size)?
+                }
+                Slot::New(EntryData::Bytes(data)) => builder.add_file(&name.to_string(), data)?,
+                Slot::New(EntryData::Path(path)) => {
+                    builder.add_from_path_streamed_as(name, &path)?
+                }
+            };
+        }
+
+        let undo_record = match self.undo_description {
+            Some(description) => {
+                let mut saved = Vec::with_capacity(touched.len());
+                for entry in touched {
+                    saved.push((entry.name(), grp_reader.read_file(&entry)?));
+                }
+                Some(UndoRecord {
+                    description,
+                    order: original_entries.iter().map(|e| e.name()).collect(),
+                    saved,
+                    after: builder.table(),
+                })
+            }
+            None => None,
+        };
+        drop(grp_reader);
+        drop(file);
+
+        // The undo record is written first, so that an edit is never
+        // applied without a way back.
+        let records_undo = undo_record.is_some();
+        if let Some(undo_record) = undo_record {
+            undo::push_record(&target, undo_record)?;
+        }
+
+        let check = || lock.check_unchanged();
+        let written = if digest {
+            builder.write_to_file_digested(&target, check)
+        } else {
+            builder
+                .write_to_file_checked(&target, check)
+                .map(|()| Vec::new())
+        };
+        written.map_err(|err| {
+            if records_undo {
+                let _ = undo::pop_record(&target);
+            }
+            format!("Failed to rewrite \"{}\": {}", target.display(), err)
+        })
+    }
+}
+
+#[test]
+fn should_leave_archive_untouched_on_invalid_edit() {
+    use std::fs;
+
+    let path = std::env::temp_dir().join("grp_should_leave_archive_untouched_on_invalid_edit.grp");
+    let original = crate::testing::GrpFixture::new()
+        .entry("GAME.CON", b"include defs.con")
+        .build();
+    fs::write(&path, &original).unwrap();
+
+    let mut session = EditSession::new(&path);
+    session
+        .add("USER.CON", EntryData::Bytes(Vec::new()))
+        .remove("MISSING.MAP");
+    assert!(session.apply().is_err());
+    assert_eq!(fs::read(&path).unwrap(), original);
+
+    let mut session = EditSession::new(&path);
+    session.add("game.con", EntryData::Bytes(Vec::new()));
+    assert!(session.apply().is_err());
+
+    let mut session = EditSession::new(&path);
+    session
+        .add("USER.CON", EntryData::Bytes(Vec::new()))
+        .rename("USER.CON", "GAME.CON");
+    assert!(session.apply().is_err());
+    assert_eq!(fs::read(&path).unwrap(), original);
+}
+
+#[test]
+fn should_leave_archive_untouched_when_cancelled() {
+    use std::fs;
+
+    let path = std::env::temp_dir().join("grp_should_leave_archive_untouched_when_cancelled.grp");
+    let original = crate::testing::GrpFixture::new()
+        .entry("E1L1.MAP", &[7; 1024])
+        .build();
+    fs::write(&path, &original).unwrap();
+
+    let token = CancellationToken::new();
+    let mut session = EditSession::new(&path);
+    session
+        .cancellation(token.clone())
+        .add("E1L2.MAP", EntryData::Bytes(vec![7; 1024]));
+    token.cancel();
+    assert!(session.apply().is_err());
+    assert_eq!(fs::read(&path).unwrap(), original);
+    let temp_files = fs::read_dir(std::env::temp_dir())
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            name.starts_with(".grp_should_leave_archive_untouched_when_cancelled.grp.")
+        })
+        .count();
+    assert_eq!(temp_files, 0);
+}
+
+#[test]
+fn should_refuse_read_only_archive_before_editing() {
+    use std::fs;
+
+    let path = std::env::temp_dir().join("grp_should_refuse_read_only_archive_before_editing.grp");
+    let output_path =
+        std::env::temp_dir().join("grp_should_refuse_read_only_archive_before_editing_out.grp");
+    let _ = fs::remove_file(undo::journal_path_for(&output_path));
+    // A read-only file left by the previous run can still be removed.
+    let _ = fs::remove_file(&path);
+    let original = crate::testing::GrpFixture::new()
+        .entry("GAME.CON", b"include defs.con")
+        .build();
+    fs::write(&path, &original).unwrap();
+    let mut permissions = fs::metadata(&path).unwrap().permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(&path, permissions).unwrap();
+
+    let mut session = EditSession::new(&path);
+    session
+        .record_undo("grp-remove --entry GAME.CON")
+        .remove("GAME.CON");
+    assert_eq!(
+        session.preflight(),
+        Err(format!("\"{}\" is read-only.", path.display()))
+    );
+    assert!(session.apply().is_err());
+    assert!(!undo::journal_path_for(&path).exists());
+
+    let mut session = EditSession::new(&path);
+    session
+        .output(&output_path)
+        .record_undo("grp-remove --entry GAME.CON")
+        .remove("GAME.CON");
+    session.apply().unwrap();
+    assert_eq!(fs::read(&path).unwrap(), original);
+    let file = File::open(&output_path).unwrap();
+    assert!(GrpFileReader::new(&file)
+        .unwrap()
+        .get_file_entries()
+        .unwrap()
+        .is_empty());
+    assert!(undo::journal_path_for(&output_path).exists());
+}
+
+#[test]
+fn should_rearrange_and_swap_names() {
+    use std::fs;
+
+    let path = std::env::temp_dir().join("grp_should_rearrange_and_swap_names.grp");
+    crate::testing::GrpFixture::new()
+        .entry("A.CON", b"a")
+        .entry("B.CON", b"b")
+        .entry("C.CON", b"c")
+        .write_to(&path)
+        .unwrap();
+    let original = fs::read(&path).unwrap();
+
+    let mut session = EditSession::new(&path);
+    session.rearrange(vec![("A.CON".into(), "B.CON".into())]);
+    assert_eq!(
+        session.apply(),
+        Err("Entry \"B.CON\" is not listed.".to_string())
+    );
+    let mut session = EditSession::new(&path);
+    session.rearrange(vec![
+        ("A.CON".into(), "A.CON".into()),
+        ("a.con".into(), "D.CON".into()),
+    ]);
+    assert!(session.apply().is_err());
+    assert_eq!(fs::read(&path).unwrap(), original);
+
+    let mut session = EditSession::new(&path);
+    session.rearrange(vec![
+        ("C.CON".into(), "C.CON".into()),
+        ("A.CON".into(), "B.CON".into()),
+        ("B.CON".into(), "A.CON".into()),
+    ]);
+    session.apply().unwrap();
+    let file = File::open(&path).unwrap();
+    let mut grp_reader = GrpFileReader::new(&file).unwrap();
+    let entries = grp_reader
+        .get_file_entries()
+        .unwrap()
+        .iter()
+        .map(|e| (e.name().to_string(), grp_reader.read_file(e).unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        [
+            ("C.CON".to_string(), b"c".to_vec()),
+            ("B.CON".to_string(), b"a".to_vec()),
+            ("A.CON".to_string(), b"b".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn should_refuse_archive_locked_by_another_session() {
+    use std::fs;
+
+    let path = std::env::temp_dir().join("grp_should_refuse_archive_locked_by_another_session.grp");
+    let original = crate::testing::GrpFixture::new()
+        .entry("GAME.CON", b"include defs.con")
+        .build();
+    fs::write(&path, &original).unwrap();
+
+    let lock = ArchiveLock::acquire(&path).unwrap();
+    let mut session = EditSession::new(&path);
+    session.remove("GAME.CON");
+    assert_eq!(
+        session.apply(),
+        Err(format!(
+            "\"{}\" is being edited by another process.",
+            path.display()
+        ))
+    );
+    drop(lock);
+    assert_eq!(fs::read(&path).unwrap(), original);
+
+    let mut session = EditSession::new(&path);
+    session.remove("GAME.CON");
+    session.apply().unwrap();
+}
+
+#[test]
+fn should_digest_written_entries() {
+    let dir = std::env::temp_dir().join("grp_should_digest_written_entries");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("MOD.GRP");
+    crate::testing::GrpFixture::new()
+        .entry("GAME.CON", b"include defs.con")
+        .entry("DEFS.CON", b"define")
+        .write_to(&path)
+        .unwrap();
+    let user_con = dir.join("USER.CON");
+    std::fs::write(&user_con, b"gamestartup").unwrap();
+
+    let mut session = EditSession::new(&path);
+    session
+        .replace("DEFS.CON", EntryData::Bytes(b"define TILE 1".to_vec()))
+        .add("USER.CON", EntryData::Path(user_con));
+    let digests = session.apply_with_digests().unwrap();
+    assert_eq!(
+        digests,
+        [
+            (
+                EntryName::new("DEFS.CON").unwrap(),
+                crate::sha256(b"define TILE 1")
+            ),
+            (
+                EntryName::new("USER.CON").unwrap(),
+                crate::sha256(b"gamestartup")
+            ),
+        ]
+    );
+}
@@ -0,0 +1,156 @@
+use std::fmt;
+
+/// Name of an archive entry, stored the way archives store it:
+/// at most 12 bytes, padded with NUL bytes. A name that is exactly
+/// 12 bytes long has no terminator.
+///
+/// Names created with [`EntryName::new`] are validated and uppercased,
+/// while names read from an archive are kept as they are, so that
+/// malformed archives can still be inspected. Names are compared, hashed
+/// and ordered case-insensitively, like the Build engine looks up files,
+/// whether against another name or a string; [`EntryName::raw`] tells
+/// names apart byte for byte.
+///
+/// ```
+/// use grp::EntryName;
+///
+/// let name = EntryName::new("e1l1.map").unwrap();
+/// assert_eq!(name.to_string(), "E1L1.MAP");
+/// assert_eq!(name, "E1L1.MAP");
+/// assert_eq!(name, EntryName::from_raw(*b"e1l1.map\0\0\0\0"));
+/// assert!(EntryName::new("LONGER_THAN_12.CON").is_err());
+/// ```
+#[derive(Clone, Copy)]
+pub struct EntryName([u8; EntryName::MAX_LEN]);
+impl EntryName {
+    pub const MAX_LEN: usize = 12;
+
+    /// Creates a name for writing into an archive. The name must be 1 to 12
+    /// bytes of printable ASCII without path separators, and is uppercased.
+    pub fn new(name: &str) -> Result<Self, String> {
+        if name.is_empty() {
+            return Err("Entry name must not be empty.".to_string());
+        }
+        if name.len() > Self::MAX_LEN {
+            return Err(format!(
+                "Entry name \"{}\" is {} bytes long (max {}).",
+                name,
+                name.len(),
+                Self::MAX_LEN
+            ));
+        }
+        if let Some(c) = name
+            .chars()
+            .find(|c| !c.is_ascii_graphic() || *c == '/' || *c == '\\')
+        {
+            return Err(format!(
+                "Entry name \"{}\" contains an invalid character {:?}.",
+                name, c
+            ));
+        }
+
+        let mut raw = [0u8; Self::MAX_LEN];
+        for (raw, byte) in raw.iter_mut().zip(name.bytes()) {
+            *raw = byte;
+        }
+        raw.make_ascii_uppercase();
+        Ok(Self(raw))
+    }
+    /// Wraps a name as read from an archive, without validation.
+    pub fn from_raw(raw: [u8; Self::MAX_LEN]) -> Self {
+        Self(raw)
+    }
+    /// The name bytes up to the first NUL byte.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.split(|b| *b == 0).next().unwrap_or_default()
+    }
+    /// The name as stored in an archive, including padding.
+    pub fn raw(&self) -> &[u8; Self::MAX_LEN] {
+        &self.0
+    }
+}
+impl fmt::Display for EntryName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.as_bytes() {
+            write!(f, "{}", *byte as char)?;
+        }
+        Ok(())
+    }
+}
+impl fmt::Debug for EntryName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+impl EntryName {
+    /// The name bytes uppercased, which is what comparisons go by.
+    fn folded(&self) -> impl Iterator<Item = u8> + '_ {
+        self.as_bytes().iter().map(u8::to_ascii_uppercase)
+    }
+}
+impl PartialEq for EntryName {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes().eq_ignore_ascii_case(other.as_bytes())
+    }
+}
+impl Eq for EntryName {}
+impl std::hash::Hash for EntryName {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        for byte in self.folded() {
+            state.write_u8(byte);
+        }
+        state.write_usize(self.as_bytes().len());
+    }
+}
+impl PartialOrd for EntryName {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for EntryName {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.folded().cmp(other.folded())
+    }
+}
+impl PartialEq<str> for EntryName {
+    fn eq(&self, other: &str) -> bool {
+        self.as_bytes().eq_ignore_ascii_case(other.as_bytes())
+    }
+}
+impl PartialEq<&str> for EntryName {
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+#[test]
+fn should_handle_names_without_terminator() {
+    let name = EntryName::new("tiles000.art").unwrap();
+    assert_eq!(name.raw(), b"TILES000.ART");
+    assert_eq!(name.as_bytes(), b"TILES000.ART");
+    assert_eq!(name, "TILES000.ART");
+    assert_ne!(name, "TILES000.AR");
+    assert_ne!(name, "TILES000.ARTX");
+
+    let name = EntryName::from_raw(*b"GAME.CON\0\0\0\0");
+    assert_eq!(name.to_string(), "GAME.CON");
+    assert_ne!(name, "GAME.CON\0");
+}
+
+#[test]
+fn should_compare_names_like_strings() {
+    use std::collections::{BTreeSet, HashSet};
+
+    let upper = EntryName::from_raw(*b"GAME.CON\0\0\0\0");
+    let lower = EntryName::from_raw(*b"game.con\0\0\0\0");
+    // Bytes after the terminator are not part of the name.
+    let padded = EntryName::from_raw(*b"GAME.CON\0XYZ");
+    assert_eq!(upper == "game.con", upper == lower);
+    assert_eq!(upper, lower);
+    assert_eq!(upper, padded);
+    assert_ne!(upper.raw(), lower.raw());
+    assert_eq!(HashSet::from([upper, lower, padded]).len(), 1);
+    assert_eq!(BTreeSet::from([upper, lower, padded]).len(), 1);
+    assert!(EntryName::new("a.con").unwrap() < lower);
+    assert!(lower < EntryName::new("GAME.CONX").unwrap());
+}
@@ -0,0 +1,102 @@
+use crate::metrics;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+
+/// Reads one entry of an archive in place, see [`crate::GrpFileReader::open_file`].
+///
+/// Positions are relative to the start of the entry and reads stop at its end.
+///
+/// ```
+/// use grp::{testing::GrpFixture, GrpFileReader};
+/// use std::io::{Cursor, Read, Seek, SeekFrom};
+///
+/// let bytes = GrpFixture::new()
+///     .entry("DEFS.CON", b"define")
+///     .entry("GAME.CON", b"include defs.con")
+///     .build();
+/// let mut grp_reader = GrpFileReader::new(Cursor::new(bytes)).unwrap();
+/// let mut entry_reader = grp_reader.open_entry("GAME.CON").unwrap().unwrap();
+///
+/// entry_reader.seek(SeekFrom::Start(8)).unwrap();
+/// let mut contents = String::new();
+/// entry_reader.read_to_string(&mut contents).unwrap();
+/// assert_eq!(contents, "defs.con");
+/// ```
+pub struct EntryReader<'a, R> {
+    reader: &'a mut BufReader<R>,
+    size: u64,
+    position: u64,
+}
+impl<'a, R: Read + Seek> EntryReader<'a, R> {
+    pub(crate) fn new(reader: &'a mut BufReader<R>, start: u64, size: u64) -> Result<Self, String> {
+        reader
+            .seek(SeekFrom::Start(start))
+            .map_err(|_| "Failed to seek to file offset.")?;
+        Ok(Self {
+            reader,
+            size,
+            position: 0,
+        })
+    }
+    /// The size of the entry in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+impl<R: Read + Seek> Read for EntryReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.size.saturating_sub(self.position);
+        let len = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        let read = self.reader.read(buf.get_mut(..len).unwrap_or_default())?;
+        self.position += read as u64;
+        metrics::record(|m| m.bytes_read(read as u64));
+        Ok(read)
+    }
+}
+impl<R: Read + Seek> Seek for EntryReader<'_, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+        };
+        let Some(position) = position else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seek before the start of the entry.",
+            ));
+        };
+        // Seeking relative to the current position keeps the buffered bytes when possible.
+        self.reader
+            .seek_relative(position as i64 - self.position as i64)?;
+        self.position = position;
+        Ok(position)
+    }
+}
+
+#[test]
+fn should_stay_within_entry() {
+    use crate::{testing::GrpFixture, GrpFileReader};
+    use std::io::Cursor;
+
+    let bytes = GrpFixture::new()
+        .entry("A.TXT", b"first")
+        .entry("B.TXT", b"second")
+        .entry("C.TXT", b"third")
+        .build();
+    let mut grp_reader = GrpFileReader::new(Cursor::new(bytes)).unwrap();
+    let mut entry_reader = grp_reader.open_entry("B.TXT").unwrap().unwrap();
+
+    let mut contents = Vec::new();
+    entry_reader.read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, b"second");
+
+    assert_eq!(entry_reader.seek(SeekFrom::End(-3)).unwrap(), 3);
+    let mut buf = [0u8; 8];
+    assert_eq!(entry_reader.read(&mut buf).unwrap(), 3);
+    assert_eq!(&buf[..3], b"ond");
+    assert_eq!(entry_reader.seek(SeekFrom::Current(-5)).unwrap(), 1);
+    entry_reader.read_exact(&mut buf[..2]).unwrap();
+    assert_eq!(&buf[..2], b"ec");
+    assert!(entry_reader.seek(SeekFrom::Current(-4)).is_err());
+    assert!(grp_reader.open_entry("D.TXT").unwrap().is_none());
+}
@@ -0,0 +1,126 @@
+//! Reversible mapping between entry names and file names that are valid on
+//! every operating system, shared by everything that extracts entries into
+//! a directory or packs a directory into an archive.
+//!
+//! Entry names of malformed archives can contain characters that Windows
+//! does not allow in file names, such as `?`, or be reserved device names
+//! such as `AUX.DAT`. Such characters are written as `%` and two hex digits,
+//! as is `%` itself, so `WHAT?.TXT` becomes `WHAT%3F.TXT`.
+//!
+//! File names are uppercased when they are packed, so that files named by
+//! hand get the names the game looks for. Lowercase letters of entry names
+//! are therefore escaped as well, which keeps them apart from uppercase ones.
+//!
+//! ```
+//! use grp::{fs_name, EntryName};
+//!
+//! let name = EntryName::from_raw(*b"WHAT?.TXT\0\0\0");
+//! assert_eq!(fs_name::to_fs_name(&name), "WHAT%3F.TXT");
+//! assert_eq!(fs_name::from_fs_name("WHAT%3F.TXT").unwrap(), name);
+//! assert_eq!(fs_name::from_fs_name("game.con").unwrap(), "GAME.CON");
+//!
+//! let name = EntryName::from_raw(*b"Game.con\0\0\0\0");
+//! assert_eq!(fs_name::to_fs_name(&name), "G%61%6D%65.%63%6F%6E");
+//! assert_eq!(fs_name::from_fs_name("G%61%6D%65.%63%6F%6E").unwrap().raw(), name.raw());
+//! ```
+
+use crate::EntryName;
+
+/// Characters that are not allowed in file names on Windows, plus the escape character.
+const ESCAPED_CHARS: &[u8] = b"<>:\"/\\|?*%";
+/// File names that Windows reserves for devices, regardless of their extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// The file name to use for an entry.
+pub fn to_fs_name(name: &EntryName) -> String {
+    let bytes = name.as_bytes();
+    let base = bytes.split(|b| *b == b'.').next().unwrap_or_default();
+    let is_reserved = RESERVED_NAMES
+        .iter()
+        .any(|r| r.as_bytes().eq_ignore_ascii_case(base));
+
+    let mut fs_name = String::with_capacity(bytes.len());
+    for (i, byte) in bytes.iter().enumerate() {
+        let escape = !byte.is_ascii_graphic()
+            || byte.is_ascii_lowercase()
+            || ESCAPED_CHARS.contains(byte)
+            // Windows drops trailing dots from file names.
+            || (*byte == b'.' && i == bytes.len() - 1)
+            || (is_reserved && i == 0);
+        if escape {
+            fs_name.push_str(&format!("%{:02X}", byte));
+        } else {
+            fs_name.push(*byte as char);
+        }
+    }
+    fs_name
+}
+
+/// The entry name for a file name, undoing [`to_fs_name`]. Letters are
+/// uppercased like [`EntryName::new`] does, while escaped bytes are kept as
+/// they are so that malformed archives round-trip.
+pub fn from_fs_name(file_name: &str) -> Result<EntryName, String> {
+    let invalid = || format!("File name \"{}\" is not a valid entry name.", file_name);
+
+    let mut bytes = Vec::with_capacity(file_name.len());
+    let mut rest = file_name.as_bytes();
+    while let Some((byte, tail)) = rest.split_first() {
+        if *byte == b'%' {
+            let (hex, tail) = tail.split_at_checked(2).ok_or_else(invalid)?;
+            let hex = std::str::from_utf8(hex).map_err(|_| invalid())?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            rest = tail;
+        } else {
+            bytes.push(byte.to_ascii_uppercase());
+            rest = tail;
+        }
+    }
+
+    if bytes.is_empty() || bytes.len() > EntryName::MAX_LEN || bytes.contains(&0) {
+        return Err(invalid());
+    }
+    let mut raw = [0u8; EntryName::MAX_LEN];
+    for (raw, byte) in raw.iter_mut().zip(bytes) {
+        *raw = byte;
+    }
+    Ok(EntryName::from_raw(raw))
+}
+
+#[test]
+fn should_round_trip_names_through_file_names() {
+    let names = [
+        &b"GAME.CON\0\0\0\0"[..],
+        b"AUX.DAT\0\0\0\0\0",
+        b"100%.TXT\0\0\0\0",
+        b"A<B>C:D|E*F.",
+        b"SLASH/\\.ART\0",
+        b"LOW CASE\xE9\0\0\0",
+        b"lower.con\0\0\0",
+        b"MiXeD.ArT\0\0\0",
+    ];
+    for raw in names {
+        let name = EntryName::from_raw(raw.try_into().unwrap());
+        let fs_name = to_fs_name(&name);
+        assert!(fs_name
+            .bytes()
+            .all(|b| b.is_ascii_graphic() && !b"<>:\"/\\|?*".contains(&b)));
+        assert_eq!(
+            from_fs_name(&fs_name).unwrap().raw(),
+            name.raw(),
+            "{}",
+            fs_name
+        );
+    }
+
+    assert_eq!(to_fs_name(&EntryName::new("AUX.DAT").unwrap()), "%41UX.DAT");
+    assert_eq!(
+        to_fs_name(&EntryName::new("AUXILIAR.DAT").unwrap()),
+        "AUXILIAR.DAT"
+    );
+    assert_eq!(from_fs_name("user.con").unwrap().raw(), b"USER.CON\0\0\0\0");
+    assert!(from_fs_name("BAD%4.TXT").is_err());
+    assert!(from_fs_name("LONGER_THAN_12.CON").is_err());
+}
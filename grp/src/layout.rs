@@ -0,0 +1,109 @@
+//! The raw structures at the start of a .grp file, for tools that work on
+//! the bytes themselves, such as hex editors or forensic scripts.
+//!
+//! A .grp file starts with a [`GrpHeader`], followed by one
+//! [`GrpTableRecord`] per entry and then the contents of the entries in the
+//! same order. [`crate::GrpFileReader`] and [`crate::GrpBuilder`] read and
+//! write the same structures, but these types do not validate anything
+//! beyond the length of their input.
+//!
+//! ```
+//! use grp::layout::{GrpHeader, GrpTableRecord};
+//!
+//! let bytes = grp::testing::GrpFixture::new()
+//!     .entry("GAME.CON", b"define")
+//!     .build();
+//! let header = GrpHeader::parse(&bytes).unwrap();
+//! assert!(header.has_valid_signature());
+//! assert_eq!(header.file_count, 1);
+//!
+//! let record = GrpTableRecord::parse(&bytes[GrpHeader::SIZE..]).unwrap();
+//! assert_eq!((record.name.to_string().as_str(), record.size), ("GAME.CON", 6));
+//! assert_eq!(record.to_bytes(), bytes[16..32]);
+//! ```
+
+use crate::EntryName;
+
+/// The signature and the number of entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GrpHeader {
+    pub signature: [u8; 12],
+    pub file_count: u32,
+}
+impl GrpHeader {
+    /// The size of the header in bytes.
+    pub const SIZE: usize = 16;
+    /// The signature of every .grp file, named after the designer of the format.
+    pub const SIGNATURE: &'static [u8; 12] = b"KenSilverman";
+
+    /// A header with the standard signature.
+    pub fn new(file_count: u32) -> Self {
+        Self {
+            signature: *Self::SIGNATURE,
+            file_count,
+        }
+    }
+    /// Parses the header from the first 16 bytes of `bytes`.
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let Some(&[ref signature @ .., c0, c1, c2, c3]) = bytes.first_chunk::<{ Self::SIZE }>()
+        else {
+            return Err(format!(
+                "A .grp header is {} bytes, got {}.",
+                Self::SIZE,
+                bytes.len()
+            ));
+        };
+        Ok(Self {
+            signature: *signature,
+            file_count: u32::from_le_bytes([c0, c1, c2, c3]),
+        })
+    }
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        let (signature, file_count) = bytes.split_at_mut(12);
+        signature.copy_from_slice(&self.signature);
+        file_count.copy_from_slice(&self.file_count.to_le_bytes());
+        bytes
+    }
+    pub fn has_valid_signature(&self) -> bool {
+        self.signature == *Self::SIGNATURE
+    }
+}
+
+/// A record of the entry table: the name of an entry, padded with NUL
+/// bytes to 12 bytes, and its size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GrpTableRecord {
+    pub name: EntryName,
+    pub size: u32,
+}
+impl GrpTableRecord {
+    /// The size of a record in bytes.
+    pub const SIZE: usize = 16;
+
+    /// Parses a record from the first 16 bytes of `bytes`.
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        match bytes.first_chunk::<{ Self::SIZE }>() {
+            Some(record) => Ok(Self::from_bytes(record)),
+            None => Err(format!(
+                "A .grp table record is {} bytes, got {}.",
+                Self::SIZE,
+                bytes.len()
+            )),
+        }
+    }
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let [name @ .., s0, s1, s2, s3] = *bytes;
+        Self {
+            name: EntryName::from_raw(name),
+            size: u32::from_le_bytes([s0, s1, s2, s3]),
+        }
+    }
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        let (name, size) = bytes.split_at_mut(EntryName::MAX_LEN);
+        name.copy_from_slice(self.name.raw());
+        size.copy_from_slice(&self.size.to_le_bytes());
+        bytes
+    }
+}
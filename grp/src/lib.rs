@@ -1,7 +1,53 @@
-use std::{
-    fs::File,
-    io::{BufReader, Read, Seek},
-};
+// Malformed files are reported as errors and never panic, as the
+// processes that embed these crates cannot recover from a panic.
+// clippy::arithmetic_side_effects is not denied, as it flags every operator
+// including those on bounded values. Arithmetic on values read from files
+// is checked or saturating instead, which tests/error_injection.rs covers.
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::expect_used,
+        clippy::indexing_slicing,
+        clippy::panic,
+        clippy::unwrap_used
+    )
+)]
+
+mod builder;
+mod cancel;
+mod counting_reader;
+pub mod edit;
+mod entry_name;
+mod entry_reader;
+pub mod fs_name;
+pub mod layout;
+pub mod lock;
+pub mod metrics;
+pub mod query;
+mod sha256;
+pub mod temp_file;
+pub mod testing;
+pub mod undo;
+
+pub use builder::{DuplicatePolicy, GrpBuilder};
+pub use cancel::CancellationToken;
+pub use counting_reader::CountingReader;
+pub use checksum::crc32;
+pub use entry_name::EntryName;
+pub use entry_reader::EntryReader;
+pub use sha256::{sha256, Sha256};
+
+use layout::{GrpHeader, GrpTableRecord};
+use std::io::{BufReader, Read, Seek, Write};
+
+/// The signature that every .grp file starts with, named after the designer of the format.
+pub const SIGNATURE: &[u8; 12] = GrpHeader::SIGNATURE;
+/// The size of the header: the signature and a 4-byte file count. The entry
+/// table starts right after it.
+pub const HEADER_SIZE: usize = GrpHeader::SIZE;
+/// Each entry in the table consists of a 12-byte name and a 4-byte size.
+pub const TABLE_RECORD_SIZE: usize = GrpTableRecord::SIZE;
+const FILE_COUNT_BYTES: usize = HEADER_SIZE - SIGNATURE.len();
 
 /// File reader for the GRP file format (.grp). GRP files are used by the Build engine.
 /// 
@@ -9,17 +55,43 @@ use std::{
 /// and the file offsets are calculated based on the those file sizes.
 ///
 /// See https://moddingwiki.shikadi.net/wiki/GRP_Format
-pub struct GrpFileReader<'a> {
+///
+/// The reader accepts anything that can be read and seeked, such as a file
+/// or a `std::io::Cursor`. The entry table is read once when the reader is
+/// created and kept in memory.
+///
+/// ```
+/// use grp::{testing::GrpFixture, GrpFileReader};
+/// use std::fs::File;
+///
+/// let path = std::env::temp_dir().join("grp_doc_reader.grp");
+/// GrpFixture::new()
+///     .entry("GAME.CON", b"include defs.con")
+///     .entry("E1L1.MAP", &[7, 0, 0, 0])
+///     .write_to(&path)
+///     .unwrap();
+///
+/// let file = File::open(&path).unwrap();
+/// let mut grp_reader = GrpFileReader::new(&file).unwrap();
+/// assert_eq!(grp_reader.file_count, 2);
+///
+/// let entry = grp_reader.find_file_entry("GAME.CON").unwrap().unwrap();
+/// assert_eq!(entry.size(), 16);
+/// assert_eq!(grp_reader.read_file(&entry).unwrap(), b"include defs.con");
+/// ```
+pub struct GrpFileReader<R> {
     pub file_count: u32,
-    reader: BufReader<&'a File>,
+    entries: Vec<GrpFileEntry>,
+    reader: BufReader<R>,
 }
-impl<'a> GrpFileReader<'a> {
-    /// Represents a signature for a GRP file.
-    const FORMAT_DESIGNER_NAME: &[u8; 12] = b"KenSilverman";
-    const FILE_COUNT_BYTES: usize = 4;
-
-    pub fn new(file: &'a File) -> Result<Self, String> {
-        let mut reader = BufReader::new(file);
+impl<R: Read + Seek> GrpFileReader<R> {
+    /// Opens the archive: the header and the whole entry table are read up
+    /// front with one read per part, and the parsed entries are kept in
+    /// memory. Subsequent calls to [`Self::get_file_entries`] and
+    /// [`Self::find_file_entry`] do not touch the file at all, which matters
+    /// on slow (e.g. network) file systems.
+    pub fn new(reader: R) -> Result<Self, String> {
+        let mut reader = BufReader::new(reader);
 
         // Ensure that the file is at least 12 bytes long
         // (the length of the signature) and that the
@@ -27,109 +99,305 @@ impl<'a> GrpFileReader<'a> {
         let mut format_designer_name_buf = [0u8; 12];
         reader
             .read_exact(&mut format_designer_name_buf)
-            .map_err(|_| "Failed to read a signature from .grp file.")?;
+            .map_err(|_| truncated("Signature", 0))?;
 
-        if format_designer_name_buf != *Self::FORMAT_DESIGNER_NAME {
+        if format_designer_name_buf != *SIGNATURE {
             return Err(format!(
                 "Signature \"{}\" does not match the expected signature \"{}\" read from the .grp file.",
                 String::from_utf8_lossy(&format_designer_name_buf),
-                String::from_utf8_lossy(Self::FORMAT_DESIGNER_NAME)
+                String::from_utf8_lossy(SIGNATURE)
             ));
         }
 
         // Read the file count. The file count is stored
         // as a little-endian unsigned 32-bit integer.
         let file_count = {
-            let mut file_count_buf = [0u8; Self::FILE_COUNT_BYTES];
-            reader.read_exact(&mut file_count_buf).map_err(|_| {
-                concat!(
-                    "Failed to read file count from .grp file. ",
-                    "There are not enough bytes in the file for reading."
-                )
-            })?;
+            let mut file_count_buf = [0u8; FILE_COUNT_BYTES];
+            reader
+                .read_exact(&mut file_count_buf)
+                .map_err(|_| truncated("File count", SIGNATURE.len() as u64))?;
             u32::from_le_bytes(file_count_buf)
         };
 
-        Ok(Self { file_count, reader })
+        metrics::record(|m| m.bytes_read(format_designer_name_buf.len() as u64 + 4));
+
+        let mut grp_reader = Self {
+            file_count,
+            entries: Vec::new(),
+            reader,
+        };
+        grp_reader.entries = grp_reader.read_file_entries()?;
+        Ok(grp_reader)
     }
     /// A shorthand for getting the file entries and finding a specific file entry among them.
+    /// The name is compared case-insensitively, like the Build engine does,
+    /// and without allocating per entry.
     pub fn find_file_entry(&mut self, file_name: &str) -> Result<Option<GrpFileEntry>, String> {
-        let file_entries = self.get_file_entries()?;
-        Ok(file_entries.into_iter().find(|f| f.name() == file_name))
+        metrics::record(|m| m.table_cache_hit());
+        Ok(self.entries.iter().find(|f| f.name == file_name).cloned())
     }
     pub fn get_file_entries(&mut self) -> Result<Vec<GrpFileEntry>, String> {
+        metrics::record(|m| m.table_cache_hit());
+        Ok(self.entries.clone())
+    }
+    /// The entries of the archive in the order of the entry table.
+    pub fn entries(&self) -> &[GrpFileEntry] {
+        &self.entries
+    }
+    fn read_file_entries(&mut self) -> Result<Vec<GrpFileEntry>, String> {
         // Ensure the file reader is set after the format designer name and the file count.
         self.reader
             .seek(std::io::SeekFrom::Start(
-                (Self::FORMAT_DESIGNER_NAME.len() + Self::FILE_COUNT_BYTES) as u64,
+                (HEADER_SIZE) as u64,
             ))
             .map_err(|_| {
                 "Failed to set the file reader after the format designer name and the file count."
             })?;
 
-        let mut current_offset = (Self::FORMAT_DESIGNER_NAME.len()
-            + Self::FILE_COUNT_BYTES
-            + self.file_count as usize * 16) as u64;
-        let mut files = Vec::with_capacity(self.file_count as usize);
-
-        // Read the file entries based on the file count.
-        for _ in 0..self.file_count {
-            // Read the file name. The max length of the file name is 12 bytes.
-            // If the file name is shorter than 12 bytes, the remaining bytes
-            // are filled with null bytes.
-            let mut file_name_buf = [0u8; 12];
-            self.reader
-                .read_exact(&mut file_name_buf)
-                .map_err(|_| "Failed to read file name from .grp file.")?;
-
-            // Read the file size. The file size is stored
-            // as a little-endian unsigned 32-bit integer.
-            let file_size = {
-                let mut size_buf = [0u8; 4];
-                self.reader
-                    .read_exact(&mut size_buf)
-                    .map_err(|_| "Failed to read file size from .grp file.")?;
-                u32::from_le_bytes(size_buf)
-            };
+        // Read the whole entry table at once and parse the entries from memory.
+        let started = std::time::Instant::now();
+        let table_offset = HEADER_SIZE;
+        // The buffer grows as the data arrives, so a bogus file count in a short
+        // file does not allocate gigabytes up front.
+        let table_bytes = self.file_count as u64 * TABLE_RECORD_SIZE as u64;
+        let mut table_buf = Vec::new();
+        (&mut self.reader)
+            .take(table_bytes)
+            .read_to_end(&mut table_buf)
+            .map_err(|e| format!("Failed to read file entry table from .grp file: {}", e))?;
+        if (table_buf.len() as u64) < table_bytes {
+            let record = table_buf.len() / TABLE_RECORD_SIZE;
+            return Err(truncated(
+                &format!("Entry table record {}", record),
+                (table_offset + record * TABLE_RECORD_SIZE) as u64,
+            ));
+        }
+
+        // Entries are checked against the length of the file up front, so a
+        // truncated archive fails here rather than with short reads later.
+        let file_len = self
+            .reader
+            .seek(std::io::SeekFrom::End(0))
+            .map_err(|e| format!("Failed to get the length of the .grp file: {}", e))?;
+        let mut current_offset = (table_offset + table_buf.len()) as u64;
+        let (records, _) = table_buf.as_chunks::<TABLE_RECORD_SIZE>();
+        let mut files = Vec::with_capacity(records.len());
 
+        for record in records {
+            let record = GrpTableRecord::from_bytes(record);
+            let expected_end = current_offset + record.size as u64;
+            if expected_end > file_len {
+                return Err(EntryOutOfBounds {
+                    entry: record.name,
+                    expected_end,
+                    file_len,
+                }
+                .into());
+            }
             files.push(GrpFileEntry {
-                name: file_name_buf,
+                name: record.name,
                 offset: current_offset,
-                size: file_size,
+                size: record.size,
             });
 
-            current_offset += file_size as u64;
+            current_offset += record.size as u64;
         }
 
+        metrics::record(|m| {
+            m.bytes_read(table_buf.len() as u64);
+            m.table_decoded(files.len(), started.elapsed());
+        });
+
         Ok(files)
     }
     pub fn read_file(&mut self, entry: &GrpFileEntry) -> Result<Vec<u8>, String> {
-        let mut buf = vec![0u8; entry.size as usize];
         self.reader
             .seek(std::io::SeekFrom::Start(entry.offset))
             .map_err(|_| "Failed to seek to file offset.")?;
-        self.reader
-            .read_exact(&mut buf)
-            .map_err(|_| "Failed to read file from .grp file.")?;
+        // Sizes come from the entry table, so the buffer only grows as data arrives.
+        let mut buf = Vec::new();
+        (&mut self.reader)
+            .take(entry.size as u64)
+            .read_to_end(&mut buf)
+            .map_err(|e| format!("Failed to read \"{}\" from .grp file: {}", entry.name, e))?;
+        if buf.len() < entry.size as usize {
+            return Err(truncated(
+                &format!("Entry \"{}\"", entry.name),
+                entry.offset,
+            ));
+        }
+        metrics::record(|m| {
+            m.bytes_read(buf.len() as u64);
+            m.entry_read(entry.name);
+        });
         Ok(buf)
     }
+    /// Copies an entry to `writer` in chunks instead of reading it into memory.
+    /// Stops with an error between chunks once `cancellation` is cancelled.
+    pub fn copy_file_to<W: Write>(
+        &mut self,
+        entry: &GrpFileEntry,
+        writer: &mut W,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<(), String> {
+        self.reader
+            .seek(std::io::SeekFrom::Start(entry.offset))
+            .map_err(|_| "Failed to seek to file offset.")?;
+        let copied = cancel::copy_chunked(
+            &mut (&mut self.reader).take(entry.size as u64),
+            writer,
+            cancellation,
+            &|| format!("\"{}\"", entry.name),
+        )?;
+        metrics::record(|m| m.bytes_read(copied));
+        if copied != entry.size as u64 {
+            return Err(truncated(
+                &format!("Entry \"{}\"", entry.name),
+                entry.offset,
+            ));
+        }
+        metrics::record(|m| m.entry_read(entry.name));
+        Ok(())
+    }
+    /// Opens an entry for reading in place, without reading it into memory.
+    /// The returned reader only sees the bytes of the entry, so e.g. an .art
+    /// file can be read straight out of the archive.
+    pub fn open_file(&mut self, entry: &GrpFileEntry) -> Result<EntryReader<'_, R>, String> {
+        metrics::record(|m| m.entry_read(entry.name));
+        EntryReader::new(&mut self.reader, entry.offset, entry.size as u64)
+    }
+    /// A shorthand for finding an entry by name and opening it with [`Self::open_file`].
+    pub fn open_entry(&mut self, file_name: &str) -> Result<Option<EntryReader<'_, R>>, String> {
+        match self.find_file_entry(file_name)? {
+            Some(entry) => self.open_file(&entry).map(Some),
+            None => Ok(None),
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct GrpFileEntry {
-    name: [u8; 12],
+    name: EntryName,
     offset: u64,
     size: u32,
 }
 impl GrpFileEntry {
-    pub fn name(&self) -> String {
-        let mut name = String::with_capacity(12);
-        for byte in &self.name {
-            if *byte == 0 {
-                break;
-            }
-            name.push(*byte as char);
-        }
-        name
+    pub fn name(&self) -> EntryName {
+        self.name
+    }
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+}
+
+/// The error for an entry that the entry table places past the end of the
+/// file, e.g. "Entry \"E1L1.MAP\" ends at offset 0x1F40, but the file is
+/// only 0x1000 bytes long.", which is what a download cut short looks like.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntryOutOfBounds {
+    pub entry: EntryName,
+    /// The offset that the entry ends at according to the entry table.
+    pub expected_end: u64,
+    pub file_len: u64,
+}
+impl std::fmt::Display for EntryOutOfBounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Entry \"{}\" ends at offset 0x{:X}, but the file is only 0x{:X} bytes long.",
+            self.entry, self.expected_end, self.file_len
+        )
+    }
+}
+impl std::error::Error for EntryOutOfBounds {}
+impl From<EntryOutOfBounds> for String {
+    fn from(error: EntryOutOfBounds) -> Self {
+        error.to_string()
     }
 }
+
+/// The error for a part of the file that the file ends within,
+/// e.g. "Entry table record 3 at offset 0x40 is truncated."
+fn truncated(part: &str, offset: u64) -> String {
+    format!("{} at offset 0x{:X} is truncated.", part, offset)
+}
+
+#[test]
+fn should_find_entries_by_offset_in_the_table() {
+    let file_path = std::env::temp_dir().join("grp_should_find_entries_by_offset_in_the_table.grp");
+    testing::GrpFixture::new()
+        .entry("DEFS.CON", b"define")
+        .entry("USER.CON", b"gamestartup")
+        .entry("E1L1.MAP", &[7, 0, 0, 0])
+        .write_to(&file_path)
+        .unwrap();
+
+    let file = std::fs::File::open(&file_path).unwrap();
+    let mut grp_reader = GrpFileReader::new(&file).unwrap();
+    let entry = grp_reader.find_file_entry("USER.CON").unwrap().unwrap();
+    assert_eq!(entry.name(), "USER.CON");
+    assert_eq!((entry.offset, entry.size), (HEADER_SIZE as u64 + 3 * TABLE_RECORD_SIZE as u64 + 6, 11));
+    assert_eq!(grp_reader.read_file(&entry).unwrap(), b"gamestartup");
+    assert!(grp_reader.find_file_entry("e1l1.map").unwrap().is_some());
+    assert!(grp_reader.find_file_entry("E1L1.MAP.BAK").unwrap().is_none());
+
+    let bytes = std::fs::read(&file_path).unwrap();
+    let truncated = GrpFileReader::new(std::io::Cursor::new(&bytes[..HEADER_SIZE + 2 * TABLE_RECORD_SIZE + 8]));
+    assert_eq!(
+        truncated.err().as_deref(),
+        Some("Entry table record 2 at offset 0x30 is truncated.")
+    );
+}
+
+#[test]
+fn should_reject_entries_past_end_of_file() {
+    let bytes = testing::GrpFixture::new()
+        .entry("GAME.CON", b"include defs.con")
+        .entry("E1L1.MAP", &[7, 0, 0, 0])
+        .build();
+    assert!(GrpFileReader::new(std::io::Cursor::new(&bytes)).is_ok());
+
+    let truncated = GrpFileReader::new(std::io::Cursor::new(&bytes[..bytes.len() - 1]));
+    let expected = EntryOutOfBounds {
+        entry: EntryName::new("E1L1.MAP").unwrap(),
+        expected_end: bytes.len() as u64,
+        file_len: bytes.len() as u64 - 1,
+    };
+    assert_eq!(truncated.err(), Some(expected.to_string()));
+    assert_eq!(
+        expected.to_string(),
+        "Entry \"E1L1.MAP\" ends at offset 0x44, but the file is only 0x43 bytes long."
+    );
+}
+
+#[test]
+fn should_round_trip_names_without_terminator() {
+    // Adjacent 12-byte names leave no NUL byte between a name and its size.
+    let builder = GrpBuilder::new()
+        .add_file("TILES000.ART", vec![1, 2, 3])
+        .unwrap()
+        .add_file("TILES001.ART", vec![4])
+        .unwrap()
+        .add_file("GAME.CON", vec![5, 6])
+        .unwrap();
+    let mut buf = Vec::new();
+    builder.write_to(&mut buf).unwrap();
+    assert_eq!(&buf[16..28], b"TILES000.ART");
+    assert_eq!(&buf[28..32], &3u32.to_le_bytes());
+
+    let file_path = std::env::temp_dir().join("grp_should_round_trip_names_without_terminator.grp");
+    std::fs::write(&file_path, &buf).unwrap();
+    let file = std::fs::File::open(file_path).unwrap();
+    let mut grp_reader = GrpFileReader::new(&file).unwrap();
+    let names = grp_reader
+        .get_file_entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["TILES000.ART", "TILES001.ART", "GAME.CON"]);
+
+    let entry = grp_reader.find_file_entry("tiles001.art").unwrap().unwrap();
+    assert_eq!(grp_reader.read_file(&entry).unwrap(), [4]);
+    assert!(grp_reader.find_file_entry("TILES001.AR").unwrap().is_none());
+}
@@ -0,0 +1,176 @@
+//! Advisory locks of archives, so that two tools editing the same archive
+//! fail instead of one silently discarding the edits of the other.
+//!
+//! The lock is taken on a file next to the archive, e.g. `MOD.GRP.lock`,
+//! rather than on the archive itself: the archive is replaced by renaming
+//! when an edit is applied, and some platforms do not let a locked file be
+//! read through another handle. The lock file is left in place when the
+//! lock is released, as removing it would let another process lock a file
+//! that is about to disappear.
+//!
+//! Programs that do not take the lock, such as a running game, are caught
+//! by checking that the archive is unchanged before it is replaced.
+
+use crate::{sha256, HEADER_SIZE, SIGNATURE, TABLE_RECORD_SIZE};
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    io::{ErrorKind, Read},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The lock file used for an archive, e.g. `MOD.GRP.lock` for `MOD.GRP`.
+pub fn lock_path_for(archive_path: &Path) -> PathBuf {
+    let mut path = archive_path.as_os_str().to_owned();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+/// What is compared to tell whether an archive has changed: its size and
+/// modification time, and the hash of its header and entry table, which
+/// catches rewrites within the resolution of the modification time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Stamp {
+    size: u64,
+    modified: Option<SystemTime>,
+    table_hash: [u8; 32],
+}
+impl Stamp {
+    /// The stamp of an archive, or `None` if it does not exist.
+    fn of(archive_path: &Path) -> Result<Option<Self>, String> {
+        let describe =
+            |e: std::io::Error| format!("Failed to read \"{}\": {}", archive_path.display(), e);
+        let mut file = match File::open(archive_path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(describe(err)),
+        };
+        let metadata = file.metadata().map_err(describe)?;
+        let mut table = Vec::new();
+        (&mut file)
+            .take(HEADER_SIZE as u64)
+            .read_to_end(&mut table)
+            .map_err(describe)?;
+        let entry_count = table
+            .get(SIGNATURE.len()..HEADER_SIZE)
+            .and_then(|b| b.try_into().ok())
+            .map_or(0, u32::from_le_bytes);
+        file.take(entry_count as u64 * TABLE_RECORD_SIZE as u64)
+            .read_to_end(&mut table)
+            .map_err(describe)?;
+        Ok(Some(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            table_hash: sha256(&table),
+        }))
+    }
+}
+
+/// An exclusive advisory lock of an archive, released when dropped.
+///
+/// ```
+/// use grp::lock::ArchiveLock;
+///
+/// let path = std::env::temp_dir().join("grp_doc_archive_lock.grp");
+/// std::fs::write(&path, b"KenSilverman\0\0\0\0").unwrap();
+///
+/// let lock = ArchiveLock::acquire(&path).unwrap();
+/// assert!(ArchiveLock::acquire(&path).is_err());
+/// lock.check_unchanged().unwrap();
+/// drop(lock);
+/// assert!(ArchiveLock::acquire(&path).is_ok());
+/// ```
+pub struct ArchiveLock {
+    _file: File,
+    archive_path: PathBuf,
+    stamp: Option<Stamp>,
+}
+impl ArchiveLock {
+    /// Takes the lock of an archive, failing right away if another process
+    /// holds it. File systems without locks, e.g. some network shares, are
+    /// only protected by [`ArchiveLock::check_unchanged`].
+    pub fn acquire(archive_path: &Path) -> Result<Self, String> {
+        let lock_path = lock_path_for(archive_path);
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .map_err(|e| format!("Failed to lock \"{}\": {}", archive_path.display(), e))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(format!(
+                    "\"{}\" is being edited by another process.",
+                    archive_path.display()
+                ))
+            }
+            Err(TryLockError::Error(err)) if err.kind() == ErrorKind::Unsupported => {}
+            Err(TryLockError::Error(err)) => {
+                return Err(format!(
+                    "Failed to lock \"{}\": {}",
+                    archive_path.display(),
+                    err
+                ))
+            }
+        }
+        Ok(Self {
+            _file: file,
+            archive_path: archive_path.to_path_buf(),
+            stamp: Stamp::of(archive_path)?,
+        })
+    }
+    /// Checks that the archive is as it was when the lock was taken, to be
+    /// called right before it is replaced.
+    pub fn check_unchanged(&self) -> Result<(), String> {
+        if Stamp::of(&self.archive_path)? != self.stamp {
+            return Err(format!(
+                "\"{}\" was changed by another program during the edit, so the edit was not applied.",
+                self.archive_path.display()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn should_detect_concurrent_modification() {
+    use std::fs;
+
+    let path = std::env::temp_dir().join("grp_should_detect_concurrent_modification.grp");
+    let original = crate::testing::GrpFixture::new()
+        .entry("GAME.CON", b"include defs.con")
+        .build();
+    fs::write(&path, &original).unwrap();
+
+    let lock = ArchiveLock::acquire(&path).unwrap();
+    assert_eq!(
+        ArchiveLock::acquire(&path).err(),
+        Some(format!(
+            "\"{}\" is being edited by another process.",
+            path.display()
+        ))
+    );
+    // A rewrite of the same size within the same modification time.
+    let mut rewritten = original.clone();
+    if let Some(b) = rewritten.get_mut(16) {
+        *b = b'A';
+    }
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    fs::write(&path, &rewritten).unwrap();
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    assert!(lock.check_unchanged().is_err());
+    drop(lock);
+
+    let missing = std::env::temp_dir().join("grp_should_detect_concurrent_modification_new.grp");
+    let _ = fs::remove_file(&missing);
+    let lock = ArchiveLock::acquire(&missing).unwrap();
+    lock.check_unchanged().unwrap();
+    fs::write(&missing, &original).unwrap();
+    assert!(lock.check_unchanged().is_err());
+}
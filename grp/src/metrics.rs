@@ -0,0 +1,107 @@
+//! Hooks for applications that want to export metrics about archive access.
+//!
+//! An application installs one [`Metrics`] implementation for the whole
+//! process, and every reader, builder and edit session reports to it.
+//! Without an installed implementation nothing is recorded.
+//!
+//! ```
+//! use grp::{metrics::{self, Counters}, testing::GrpFixture, GrpFileReader};
+//! use std::{fs::File, sync::atomic::Ordering};
+//!
+//! static COUNTERS: Counters = Counters::new();
+//! metrics::install(&COUNTERS).unwrap();
+//!
+//! let path = std::env::temp_dir().join("grp_doc_metrics.grp");
+//! GrpFixture::new().entry("GAME.CON", b"include defs.con").write_to(&path).unwrap();
+//! let file = File::open(&path).unwrap();
+//! let mut grp_reader = GrpFileReader::new(&file).unwrap();
+//! let entry = grp_reader.find_file_entry("GAME.CON").unwrap().unwrap();
+//! grp_reader.read_file(&entry).unwrap();
+//!
+//! assert_eq!(COUNTERS.entries_read.load(Ordering::Relaxed), 1);
+//! assert_eq!(COUNTERS.table_cache_hits.load(Ordering::Relaxed), 1);
+//! assert_eq!(COUNTERS.bytes_read.load(Ordering::Relaxed), 16 + 16 + 16);
+//! ```
+
+use crate::EntryName;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::Duration,
+};
+
+/// Receives events about archive access. All methods do nothing by default,
+/// so implementations only override what they export.
+pub trait Metrics: Sync {
+    /// Bytes were read from an archive: its header, its entry table or entries.
+    fn bytes_read(&self, _bytes: u64) {}
+    /// An entry was read or copied out of an archive.
+    fn entry_read(&self, _name: EntryName) {}
+    /// An entry was written into an archive.
+    fn entry_written(&self, _name: EntryName) {}
+    /// A lookup or listing of entries was answered from the cached entry table.
+    fn table_cache_hit(&self) {}
+    /// The entry table of an archive was read and parsed.
+    fn table_decoded(&self, _entry_count: usize, _duration: Duration) {}
+}
+
+/// A [`Metrics`] implementation that sums up all events in atomic counters.
+#[derive(Debug, Default)]
+pub struct Counters {
+    pub bytes_read: AtomicU64,
+    pub entries_read: AtomicU64,
+    pub entries_written: AtomicU64,
+    pub table_cache_hits: AtomicU64,
+    pub tables_decoded: AtomicU64,
+    pub table_decode_nanos: AtomicU64,
+}
+impl Counters {
+    pub const fn new() -> Self {
+        Self {
+            bytes_read: AtomicU64::new(0),
+            entries_read: AtomicU64::new(0),
+            entries_written: AtomicU64::new(0),
+            table_cache_hits: AtomicU64::new(0),
+            tables_decoded: AtomicU64::new(0),
+            table_decode_nanos: AtomicU64::new(0),
+        }
+    }
+}
+impl Metrics for Counters {
+    fn bytes_read(&self, bytes: u64) {
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+    }
+    fn entry_read(&self, _name: EntryName) {
+        self.entries_read.fetch_add(1, Ordering::Relaxed);
+    }
+    fn entry_written(&self, _name: EntryName) {
+        self.entries_written.fetch_add(1, Ordering::Relaxed);
+    }
+    fn table_cache_hit(&self) {
+        self.table_cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+    fn table_decoded(&self, _entry_count: usize, duration: Duration) {
+        self.tables_decoded.fetch_add(1, Ordering::Relaxed);
+        self.table_decode_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+static INSTALLED: OnceLock<&'static dyn Metrics> = OnceLock::new();
+
+/// Installs the implementation that receives all events of the process.
+/// Fails if one has already been installed.
+pub fn install(metrics: &'static dyn Metrics) -> Result<(), String> {
+    INSTALLED
+        .set(metrics)
+        .map_err(|_| "Metrics have already been installed.".to_string())
+}
+
+/// Reports an event to the installed implementation, if any.
+pub(crate) fn record(event: impl FnOnce(&dyn Metrics)) {
+    if let Some(metrics) = INSTALLED.get() {
+        event(*metrics);
+    }
+}
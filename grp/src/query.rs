@@ -0,0 +1,464 @@
+//! A tiny expression language for selecting archive entries by their metadata.
+//!
+//! ```text
+//! size > 1MB and name like "*.ANM"
+//! not (type = "MAP" or crc = 0x1B2C3D4E)
+//! ```
+//!
+//! Fields are `name`, `size`, `type` (the extension of the name) and `crc`
+//! (the CRC-32 of the contents). Names and types are compared case-insensitively,
+//! and `like` accepts `*` and `?` wildcards. Sizes accept `B`, `KB`, `MB`
+//! and `GB` suffixes (powers of 1024).
+
+use crate::{crc32, GrpFileEntry, GrpFileReader};
+use std::io::{Read, Seek};
+
+/// A parsed query that can be matched against archive entries.
+///
+/// ```
+/// use grp::query::{EntryMetadata, Query};
+///
+/// let query = Query::parse(r#"size > 1MB and name like "*.ANM""#).unwrap();
+/// let entry = EntryMetadata { name: "LOGO.ANM", size: 2 * 1024 * 1024, crc: None };
+/// assert!(query.matches(&entry));
+/// ```
+#[derive(Debug)]
+pub struct Query {
+    expr: Expr,
+}
+
+/// The metadata of an entry a query is evaluated against.
+/// The checksum is only needed when [`Query::needs_crc`] returns true.
+pub struct EntryMetadata<'a> {
+    pub name: &'a str,
+    pub size: u64,
+    pub crc: Option<u32>,
+}
+impl<'a> EntryMetadata<'a> {
+    pub fn of(entry: &GrpFileEntry, name: &'a str, crc: Option<u32>) -> Self {
+        Self {
+            name,
+            size: entry.size() as u64,
+            crc,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Text(TextField, TextOp, String),
+    Number(NumberField, CompareOp, u64),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum TextField {
+    Name,
+    Type,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum TextOp {
+    Eq,
+    Ne,
+    Like,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum NumberField {
+    Size,
+    Crc,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(u64),
+    Text(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+impl Query {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            return Err(format!("Unexpected {:?} in query.", token));
+        }
+        Ok(Self { expr })
+    }
+    /// Whether evaluating the query requires the checksum of the entry contents.
+    pub fn needs_crc(&self) -> bool {
+        fn needs_crc(expr: &Expr) -> bool {
+            match expr {
+                Expr::And(a, b) | Expr::Or(a, b) => needs_crc(a) || needs_crc(b),
+                Expr::Not(a) => needs_crc(a),
+                Expr::Text(..) => false,
+                Expr::Number(field, _, _) => matches!(field, NumberField::Crc),
+            }
+        }
+        needs_crc(&self.expr)
+    }
+    /// Evaluates the query. A comparison on a missing checksum is unknown
+    /// rather than false, so that it does not match even when negated, and
+    /// a query that is unknown as a whole does not match.
+    pub fn matches(&self, entry: &EntryMetadata) -> bool {
+        eval(&self.expr, entry) == Some(true)
+    }
+    /// Evaluates the query on an entry of an archive, reading the contents
+    /// of the entry only when the query needs their checksum.
+    pub fn matches_entry<R: Read + Seek>(
+        &self,
+        grp_reader: &mut GrpFileReader<R>,
+        entry: &GrpFileEntry,
+    ) -> Result<bool, String> {
+        Ok(self.matches_entry_with_crc(grp_reader, entry)?.0)
+    }
+    /// Like [`Query::matches_entry`], but also returns the checksum of the
+    /// contents when it had to be read.
+    pub fn matches_entry_with_crc<R: Read + Seek>(
+        &self,
+        grp_reader: &mut GrpFileReader<R>,
+        entry: &GrpFileEntry,
+    ) -> Result<(bool, Option<u32>), String> {
+        let crc = if self.needs_crc() {
+            Some(crc32(&grp_reader.read_file(entry)?))
+        } else {
+            None
+        };
+        let name = entry.name().to_string();
+        Ok((self.matches(&EntryMetadata::of(entry, &name, crc)), crc))
+    }
+}
+
+/// Evaluates an expression, `None` meaning unknown because a field is missing.
+fn eval(expr: &Expr, entry: &EntryMetadata) -> Option<bool> {
+    match expr {
+        Expr::And(a, b) => match (eval(a, entry), eval(b, entry)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+        Expr::Or(a, b) => match (eval(a, entry), eval(b, entry)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        },
+        Expr::Not(a) => eval(a, entry).map(|matches| !matches),
+        Expr::Text(field, op, value) => {
+            let actual = match field {
+                TextField::Name => entry.name,
+                TextField::Type => entry.name.rsplit_once('.').map_or("", |(_, ext)| ext),
+            };
+            Some(match op {
+                TextOp::Eq => actual.eq_ignore_ascii_case(value),
+                TextOp::Ne => !actual.eq_ignore_ascii_case(value),
+                TextOp::Like => glob_matches(value.as_bytes(), actual.as_bytes()),
+            })
+        }
+        Expr::Number(field, op, value) => {
+            let actual = match field {
+                NumberField::Size => entry.size,
+                NumberField::Crc => entry.crc? as u64,
+            };
+            Some(match op {
+                CompareOp::Eq => actual == *value,
+                CompareOp::Ne => actual != *value,
+                CompareOp::Lt => actual < *value,
+                CompareOp::Le => actual <= *value,
+                CompareOp::Gt => actual > *value,
+                CompareOp::Ge => actual >= *value,
+            })
+        }
+    }
+}
+
+/// Matches `text` against a pattern where `*` matches any run of
+/// characters and `?` any single character, ignoring ASCII case.
+pub fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and the text position it was tried at.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while let Some(text_char) = text.get(t) {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == b'?' || c.eq_ignore_ascii_case(text_char) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern.iter().skip(p).all(|c| *c == b'*')
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    // The characters from `from` up to `to` as a string.
+    let chars_between =
+        |from: usize, to: usize| chars.iter().take(to).skip(from).collect::<String>();
+
+    while let Some(&c) = chars.get(i) {
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let end = chars
+                .iter()
+                .skip(i + 1)
+                .position(|x| *x == c)
+                .ok_or("Unterminated string in query.")?;
+            tokens.push(Token::Text(chars_between(i + 1, i + 1 + end)));
+            i += end + 2;
+        } else if "=!<>".contains(c) {
+            let two = chars.get(i + 1) == Some(&'=');
+            let op = match (c, two) {
+                ('=', true) | ('=', false) => "=",
+                ('!', true) => "!=",
+                ('<', true) => "<=",
+                ('<', false) => "<",
+                ('>', true) => ">=",
+                ('>', false) => ">",
+                _ => return Err(format!("Unexpected character '{}' in query.", c)),
+            };
+            tokens.push(Token::Op(op));
+            i += if two { 2 } else { 1 };
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while chars.get(i).is_some_and(|c| c.is_ascii_alphanumeric()) {
+                i += 1;
+            }
+            let word = chars_between(start, i);
+            tokens.push(Token::Number(parse_number(&word)?));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while chars
+                .get(i)
+                .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_')
+            {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars_between(start, i).to_lowercase()));
+        } else {
+            return Err(format!("Unexpected character '{}' in query.", c));
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_number(word: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid number \"{}\" in query.", word);
+
+    if let Some(hex) = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+        return u64::from_str_radix(hex, 16).map_err(|_| invalid());
+    }
+
+    let digits_end = word
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(word.len());
+    let (digits, suffix) = word.split_at(digits_end);
+    let multiplier = match suffix.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "KB" => 1024,
+        "MB" => 1024 * 1024,
+        "GB" => 1024 * 1024 * 1024,
+        _ => return Err(invalid()),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(invalid)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Ident(i)) if i == keyword)
+    }
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_unary()?;
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Ident(i)) if i == "not" => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err("Missing closing parenthesis in query.".to_string()),
+                }
+            }
+            Some(Token::Ident(field)) => self.parse_comparison(&field),
+            Some(token) => Err(format!("Expected a field name, found {:?}.", token)),
+            None => Err("Unexpected end of query.".to_string()),
+        }
+    }
+    fn parse_comparison(&mut self, field: &str) -> Result<Expr, String> {
+        let op = self.next();
+        let value = self.next();
+
+        let text_field = match field {
+            "name" => Some(TextField::Name),
+            "type" => Some(TextField::Type),
+            _ => None,
+        };
+        if let Some(text_field) = text_field {
+            let op = match op {
+                Some(Token::Op("=")) => TextOp::Eq,
+                Some(Token::Op("!=")) => TextOp::Ne,
+                Some(Token::Ident(i)) if i == "like" => TextOp::Like,
+                _ => return Err(format!("Expected =, != or like after {}.", field)),
+            };
+            let Some(Token::Text(value)) = value else {
+                return Err(format!(
+                    "Expected a quoted string to compare {} with.",
+                    field
+                ));
+            };
+            return Ok(Expr::Text(text_field, op, value));
+        }
+
+        let number_field = match field {
+            "size" => NumberField::Size,
+            "crc" => NumberField::Crc,
+            _ => {
+                return Err(format!(
+                    "Unknown field \"{}\" (should be name, size, type or crc).",
+                    field
+                ))
+            }
+        };
+        let op = match op {
+            Some(Token::Op("=")) => CompareOp::Eq,
+            Some(Token::Op("!=")) => CompareOp::Ne,
+            Some(Token::Op("<")) => CompareOp::Lt,
+            Some(Token::Op("<=")) => CompareOp::Le,
+            Some(Token::Op(">")) => CompareOp::Gt,
+            Some(Token::Op(">=")) => CompareOp::Ge,
+            _ => return Err(format!("Expected a comparison operator after {}.", field)),
+        };
+        let Some(Token::Number(value)) = value else {
+            return Err(format!("Expected a number to compare {} with.", field));
+        };
+        Ok(Expr::Number(number_field, op, value))
+    }
+}
+
+#[test]
+fn should_evaluate_queries() {
+    let map = EntryMetadata {
+        name: "E1L1.MAP",
+        size: 40_000,
+        crc: Some(0xDEADBEEF),
+    };
+    let anm = EntryMetadata {
+        name: "LOGO.ANM",
+        size: 3 * 1024 * 1024,
+        crc: None,
+    };
+    let query = |source: &str| Query::parse(source).unwrap();
+
+    assert!(query(r#"size > 1MB and name like "*.anm""#).matches(&anm));
+    assert!(!query(r#"size > 1MB and name like "*.anm""#).matches(&map));
+    assert!(query(r#"type = "MAP" or size >= 3MB"#).matches(&map));
+    assert!(query(r#"not (type = "MAP") and name like 'L?GO*'"#).matches(&anm));
+    assert!(query("crc = 0xDEADBEEF").matches(&map));
+    assert!(!query("crc != 0").matches(&anm));
+    // Negating a comparison on a missing checksum does not match either.
+    assert!(!query("not crc = 0x1234").matches(&anm));
+    assert!(!query(r#"not (crc = 1 and type = "ANM")"#).matches(&anm));
+    assert!(query(r#"not (crc = 1 and type = "MAP")"#).matches(&anm));
+    assert!(query(r#"crc = 1 or type = "ANM""#).matches(&anm));
+    assert!(query("crc = 1").needs_crc() && !query("size < 10KB").needs_crc());
+
+    assert!(Query::parse("size > big").is_err());
+    assert!(Query::parse(r#"name > "A""#).is_err());
+    assert!(Query::parse("(size > 1").is_err());
+    assert!(Query::parse("colour = 1").is_err());
+}
+
+#[test]
+fn should_match_archive_entries() {
+    let bytes = crate::testing::GrpFixture::new()
+        .entry("GAME.CON", b"define")
+        .entry("E1L1.MAP", b"map")
+        .build();
+    let mut grp_reader = GrpFileReader::new(std::io::Cursor::new(bytes)).unwrap();
+    let entries = grp_reader.get_file_entries().unwrap();
+    let crc = crc32(b"define");
+
+    let by_type = Query::parse(r#"type = "con""#).unwrap();
+    assert_eq!(
+        by_type
+            .matches_entry_with_crc(&mut grp_reader, &entries[0])
+            .unwrap(),
+        (true, None)
+    );
+    assert!(!by_type.matches_entry(&mut grp_reader, &entries[1]).unwrap());
+
+    let by_crc = Query::parse(&format!("crc = {}", crc)).unwrap();
+    assert_eq!(
+        by_crc
+            .matches_entry_with_crc(&mut grp_reader, &entries[0])
+            .unwrap(),
+        (true, Some(crc))
+    );
+    assert!(!by_crc.matches_entry(&mut grp_reader, &entries[1]).unwrap());
+}
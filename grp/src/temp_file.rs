@@ -0,0 +1,153 @@
+//! Temporary files for writing outputs without clobbering them on failure.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+/// A temporary file next to the file it is going to replace.
+///
+/// The temporary file gets a unique name in the directory of the target,
+/// so that renaming it over the target stays on one file system. It is
+/// removed when the guard is dropped without [`TempFile::persist`] having
+/// been called, which covers error paths as well as panics.
+///
+/// ```
+/// use grp::temp_file::TempFile;
+/// use std::io::Write;
+///
+/// let target = std::env::temp_dir().join("grp_doc_temp_file.txt");
+/// let mut temp_file = TempFile::new(&target).unwrap();
+/// temp_file.file().write_all(b"written").unwrap();
+/// temp_file.persist().unwrap();
+/// assert_eq!(std::fs::read(&target).unwrap(), b"written");
+///
+/// let temp_file = TempFile::new(&target).unwrap();
+/// let temp_path = temp_file.path().to_path_buf();
+/// drop(temp_file);
+/// assert!(!temp_path.exists());
+/// assert_eq!(std::fs::read(&target).unwrap(), b"written");
+/// ```
+pub struct TempFile {
+    // Declared before the path, so that the file is closed before it is removed.
+    file: File,
+    path: TempPath,
+    target: PathBuf,
+}
+impl TempFile {
+    /// Creates a new temporary file for replacing `target`.
+    pub fn new(target: &Path) -> Result<Self, String> {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+        let file_name = target.file_name().unwrap_or_default().to_string_lossy();
+        loop {
+            let path = target.with_file_name(format!(
+                ".{}.{}-{}.tmp",
+                file_name,
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            // Never reuse an existing file, which may belong to another process.
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => {
+                    return Ok(Self {
+                        file,
+                        path: TempPath(path),
+                        target: target.to_path_buf(),
+                    })
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+                Err(err) => {
+                    return Err(format!(
+                        "Failed to create a temporary file for \"{}\": {}",
+                        target.display(),
+                        err
+                    ))
+                }
+            }
+        }
+    }
+    pub fn path(&self) -> &Path {
+        &self.path.0
+    }
+    /// The temporary file for writing the contents of the target.
+    pub fn file(&mut self) -> &mut File {
+        &mut self.file
+    }
+    /// Syncs the temporary file to disk and renames it over the target.
+    pub fn persist(self) -> Result<(), String> {
+        let Self {
+            file,
+            mut path,
+            target,
+        } = self;
+        let describe =
+            |e: std::io::Error| format!("Failed to write \"{}\": {}", target.display(), e);
+        Self::sync_and_close(file).map_err(describe)?;
+        fs::rename(&path.0, &target).map_err(describe)?;
+        // The temporary file no longer exists, so there is nothing to clean up.
+        path.0 = PathBuf::new();
+        Ok(())
+    }
+    /// Like [`TempFile::persist`], but fails instead of replacing an existing
+    /// target, even one created by another process in the meantime.
+    pub fn persist_new(self) -> Result<(), String> {
+        let Self {
+            file,
+            mut path,
+            target,
+        } = self;
+        let describe =
+            |e: std::io::Error| format!("Failed to write \"{}\": {}", target.display(), e);
+        let exists = || format!("\"{}\" already exists.", target.display());
+        Self::sync_and_close(file).map_err(describe)?;
+        // Unlike renaming, linking never replaces the target. The temporary
+        // name is then removed when the guard is dropped. File systems
+        // without hard links, such as FAT, fall back to checking first.
+        match fs::hard_link(&path.0, &target) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => Err(exists()),
+            Err(_) if target.exists() => Err(exists()),
+            Err(_) => {
+                fs::rename(&path.0, &target).map_err(describe)?;
+                path.0 = PathBuf::new();
+                Ok(())
+            }
+        }
+    }
+    /// Closes the file even if syncing fails, as an open file cannot be
+    /// removed on Windows.
+    fn sync_and_close(file: File) -> std::io::Result<()> {
+        let result = file.sync_all();
+        drop(file);
+        result
+    }
+}
+
+/// Removes the temporary file when dropped, unless the path has been
+/// cleared because the file was renamed.
+struct TempPath(PathBuf);
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        if !self.0.as_os_str().is_empty() {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+}
+
+/// Checks that `target` can be replaced through a [`TempFile`], so that
+/// read-only files and directories are reported before any work is done.
+pub fn check_writable(target: &Path) -> Result<(), String> {
+    if fs::metadata(target).is_ok_and(|m| m.permissions().readonly()) {
+        return Err(format!("\"{}\" is read-only.", target.display()));
+    }
+    let dir = target.parent().unwrap_or(Path::new("."));
+    if fs::metadata(dir).is_ok_and(|m| m.permissions().readonly()) {
+        return Err(format!("Directory \"{}\" is read-only.", dir.display()));
+    }
+    // Permissions do not tell everything, e.g. about ownership or read-only
+    // mounts, so a temporary file is created and removed again.
+    TempFile::new(target).map(drop)
+}
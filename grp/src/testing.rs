@@ -0,0 +1,57 @@
+//! Synthetic fixture generation for tests and examples.
+//!
+//! The real Duke Nukem 3D data files are copyrighted and cannot be shipped
+//! with the crate, so tests and documentation examples build small archives
+//! in memory instead.
+
+use std::path::Path;
+
+/// Builder for a synthetic, well-formed .grp file.
+///
+/// ```
+/// use grp::{testing::GrpFixture, HEADER_SIZE, TABLE_RECORD_SIZE};
+///
+/// let bytes = GrpFixture::new()
+///     .entry("GAME.CON", b"include defs.con")
+///     .entry("E1L1.MAP", &[7, 0, 0, 0])
+///     .build();
+///
+/// // The header, two records of the entry table and the entries.
+/// assert_eq!(bytes.len(), HEADER_SIZE + 2 * TABLE_RECORD_SIZE + 16 + 4);
+/// ```
+#[derive(Default)]
+pub struct GrpFixture {
+    entries: Vec<([u8; 12], Vec<u8>)>,
+}
+impl GrpFixture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds an entry to the end of the archive.
+    /// Names are truncated to 12 bytes, which is the maximum the format can store.
+    pub fn entry(mut self, name: &str, data: &[u8]) -> Self {
+        let mut name_buf = [0u8; 12];
+        for (dst, src) in name_buf.iter_mut().zip(name.bytes()) {
+            *dst = src;
+        }
+        self.entries.push((name_buf, data.to_vec()));
+        self
+    }
+    pub fn build(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(crate::SIGNATURE);
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for (name, data) in &self.entries {
+            bytes.extend_from_slice(name);
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        }
+        for (_, data) in &self.entries {
+            bytes.extend_from_slice(data);
+        }
+        bytes
+    }
+    /// Builds the archive and writes it to the given path.
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.build())
+    }
+}
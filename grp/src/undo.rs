@@ -0,0 +1,253 @@
+//! Journal of inverse operations for archive edits.
+//!
+//! Every record stores what is needed to restore the archive as it was before
+//! an edit: the previous entry order and the previous contents of each entry
+//! that was removed, replaced or renamed. Entries that were not touched are
+//! taken from the current archive when undoing, so records stay small. The
+//! table of the archive after the edit is stored too, so that undoing refuses
+//! to run when the archive has been changed by something else in the meantime.
+
+use crate::{lock::ArchiveLock, temp_file::TempFile, EntryName, GrpBuilder, GrpFileReader};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const RECORD_MAGIC: &[u8; 8] = b"GRPUNDO1";
+
+pub(crate) struct UndoRecord {
+    pub(crate) description: String,
+    /// Entry names before the edit, in archive order.
+    pub(crate) order: Vec<EntryName>,
+    /// Contents of the entries that the edit removed, replaced or renamed.
+    pub(crate) saved: Vec<(EntryName, Vec<u8>)>,
+    /// Entry names and sizes after the edit, in archive order.
+    pub(crate) after: Vec<(EntryName, u32)>,
+}
+
+/// The journal file used for an archive, e.g. `MOD.grpundo` for `MOD.GRP`.
+pub fn journal_path_for(archive_path: &Path) -> PathBuf {
+    archive_path.with_extension("grpundo")
+}
+
+/// Reverts the most recent recorded edit of the archive and removes it from
+/// the journal. Returns the description of the reverted edit, or `None`
+/// when there is nothing to undo.
+pub fn undo_last(archive_path: &Path) -> Result<Option<String>, String> {
+    let journal_path = journal_path_for(archive_path);
+    let mut records = read_journal(&journal_path)?;
+    let Some(record) = records.pop() else {
+        return Ok(None);
+    };
+    let lock = ArchiveLock::acquire(archive_path)?;
+
+    let file = File::open(archive_path)
+        .map_err(|e| format!("Failed to open \"{}\": {}", archive_path.display(), e))?;
+    let current_entries = GrpFileReader::new(&file)?.get_file_entries()?;
+    drop(file);
+
+    // Names are compared byte for byte, so that renaming an entry to
+    // another case also counts as a change.
+    let current_table = current_entries.iter().map(|e| (*e.name().raw(), e.size()));
+    let recorded_table = record.after.iter().map(|(name, size)| (*name.raw(), *size));
+    if !current_table.eq(recorded_table) {
+        return Err(format!(
+            "\"{}\" has changed since \"{}\" was recorded, so it cannot be undone.",
+            archive_path.display(),
+            record.description
+        ));
+    }
+
+    let mut builder = GrpBuilder::new();
+    let mut saved = record.saved;
+    for name in &record.order {
+        builder = match saved.iter().position(|(n, _)| n == name) {
+            Some(i) => builder.add_named_file(*name, saved.swap_remove(i).1)?,
+            None => {
+                let Some(entry) = current_entries.iter().find(|e| e.name() == *name) else {
+                    return Err(format!(
+                        "Entry \"{}\" needed for undoing is missing from the archive.",
+                        name
+                    ));
+                };
+                builder.add_archive_entry(*name, archive_path, entry.offset, entry.size)?
+            }
+        };
+    }
+
+    if let Err(err) = builder.write_to_file_checked(archive_path, || lock.check_unchanged()) {
+        return Err(format!(
+            "Failed to rewrite \"{}\": {}",
+            archive_path.display(),
+            err
+        ));
+    }
+
+    write_journal(&journal_path, &records)?;
+    Ok(Some(record.description))
+}
+
+/// Appends a record to the journal of an archive.
+pub(crate) fn push_record(archive_path: &Path, record: UndoRecord) -> Result<(), String> {
+    let journal_path = journal_path_for(archive_path);
+    let mut records = read_journal(&journal_path)?;
+    records.push(record);
+    write_journal(&journal_path, &records)
+}
+
+/// Removes the most recent record again, used when an edit fails after
+/// its record has already been written.
+pub(crate) fn pop_record(archive_path: &Path) -> Result<(), String> {
+    let journal_path = journal_path_for(archive_path);
+    let mut records = read_journal(&journal_path)?;
+    records.pop();
+    write_journal(&journal_path, &records)
+}
+
+fn read_journal(path: &Path) -> Result<Vec<UndoRecord>, String> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("Failed to read \"{}\": {}", path.display(), err)),
+    };
+
+    let invalid = || format!("Undo journal \"{}\" is corrupt.", path.display());
+    let mut reader = ByteReader { bytes: &bytes };
+    let mut records = Vec::new();
+    while !reader.bytes.is_empty() {
+        if reader.take(RECORD_MAGIC.len()).ok_or_else(invalid)? != RECORD_MAGIC {
+            return Err(invalid());
+        }
+        let description_len = reader.u32().ok_or_else(invalid)? as usize;
+        let description = reader.take(description_len).ok_or_else(invalid)?;
+        let description = String::from_utf8_lossy(description).into_owned();
+
+        let order_count = reader.u32().ok_or_else(invalid)?;
+        let order = (0..order_count)
+            .map(|_| reader.name())
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+
+        let saved_count = reader.u32().ok_or_else(invalid)?;
+        let saved = (0..saved_count)
+            .map(|_| {
+                let name = reader.name()?;
+                let size = reader.u32()? as usize;
+                Some((name, reader.take(size)?.to_vec()))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+
+        let after_count = reader.u32().ok_or_else(invalid)?;
+        let after = (0..after_count)
+            .map(|_| Some((reader.name()?, reader.u32()?)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+
+        records.push(UndoRecord {
+            description,
+            order,
+            saved,
+            after,
+        });
+    }
+    Ok(records)
+}
+
+fn write_journal(path: &Path, records: &[UndoRecord]) -> Result<(), String> {
+    if records.is_empty() {
+        return match fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Failed to remove \"{}\": {}", path.display(), err))
+            }
+            _ => Ok(()),
+        };
+    }
+
+    let mut bytes = Vec::new();
+    for record in records {
+        bytes.extend_from_slice(RECORD_MAGIC);
+        bytes.extend_from_slice(&(record.description.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(record.description.as_bytes());
+        bytes.extend_from_slice(&(record.order.len() as u32).to_le_bytes());
+        for name in &record.order {
+            bytes.extend_from_slice(name.raw());
+        }
+        bytes.extend_from_slice(&(record.saved.len() as u32).to_le_bytes());
+        for (name, data) in &record.saved {
+            bytes.extend_from_slice(name.raw());
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(data);
+        }
+        bytes.extend_from_slice(&(record.after.len() as u32).to_le_bytes());
+        for (name, size) in &record.after {
+            bytes.extend_from_slice(name.raw());
+            bytes.extend_from_slice(&size.to_le_bytes());
+        }
+    }
+    let mut file = TempFile::new(path)?;
+    file.file()
+        .write_all(&bytes)
+        .map_err(|e| format!("Failed to write \"{}\": {}", path.display(), e))?;
+    file.persist()
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Some(taken)
+    }
+    fn u32(&mut self) -> Option<u32> {
+        let b = self.take(4)?.first_chunk::<4>()?;
+        Some(u32::from_le_bytes(*b))
+    }
+    fn name(&mut self) -> Option<EntryName> {
+        let mut raw = [0u8; EntryName::MAX_LEN];
+        raw.copy_from_slice(self.take(EntryName::MAX_LEN)?);
+        Some(EntryName::from_raw(raw))
+    }
+}
+
+#[test]
+fn should_undo_edits_in_reverse_order() {
+    use crate::edit::{EditSession, EntryData};
+
+    let path = std::env::temp_dir().join("grp_should_undo_edits_in_reverse_order.grp");
+    let _ = fs::remove_file(journal_path_for(&path));
+    let original = crate::testing::GrpFixture::new()
+        .entry("GAME.CON", b"include defs.con")
+        .entry("DEMO1.DMO", &[1; 32])
+        .entry("E1L1.MAP", &[7, 0, 0, 0])
+        .build();
+    fs::write(&path, &original).unwrap();
+
+    let mut session = EditSession::new(&path);
+    session
+        .record_undo("first")
+        .remove("DEMO1.DMO")
+        .replace("GAME.CON", EntryData::Bytes(b"include user.con".to_vec()));
+    session.apply().unwrap();
+    let after_first = fs::read(&path).unwrap();
+
+    let mut session = EditSession::new(&path);
+    session
+        .record_undo("second")
+        .rename("E1L1.MAP", "E1L9.MAP")
+        .add("USER.CON", EntryData::Bytes(b"gamestartup".to_vec()));
+    session.apply().unwrap();
+
+    assert_eq!(undo_last(&path).unwrap().as_deref(), Some("second"));
+    assert_eq!(fs::read(&path).unwrap(), after_first);
+    assert_eq!(undo_last(&path).unwrap().as_deref(), Some("first"));
+    assert_eq!(fs::read(&path).unwrap(), original);
+    assert_eq!(undo_last(&path).unwrap(), None);
+    assert!(!journal_path_for(&path).exists());
+}
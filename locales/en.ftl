@@ -0,0 +1,149 @@
+# User-facing messages of the command-line tool, in the syntax of Fluent
+# (https://projectfluent.org). Every message has an id, and placeables like
+# { $entry } are filled in by the tool.
+#
+# Translations live next to this file named by language code, e.g. fi.ftl,
+# and may leave out messages, which are then shown in English.
+
+error = Error: { $error }
+warning = Warning: { $warning }
+no-arguments = No arguments provided.
+missing-arguments = Missing arguments.
+unknown-command = Unknown command: { $command }
+use-output-dir = Use --output-dir to write the edited archive into another directory.
+output-exists = "{ $file }" already exists, use --output-file to extract under another name or --force true to replace it.
+force-and-no-overwrite = --force and --no-overwrite cannot be used together.
+missing-value = { $option } is missing its value.
+invalid-switch = { $option } should be followed by true or false.
+
+entry-added = Added { $entry }
+entry-replaced = Replaced { $entry }
+entries-removed = Removed { $entries }
+entries-packed = Packed { $count } entries into { $file }.
+entries-extracted = { $extracted } extracted, { $skipped } skipped from { $archives } archives.
+entries-listed = Listed { $count } entries in { $file }.
+entries-rearranged = Rearranged { $count } entries.
+asset-renamed = Renamed { $from } to { $to }.
+references = { $name }: { $count } references
+no-tile-references = No references to tile { $tile } found.
+plan-not-applied = Nothing was changed, use --mode apply to apply the plan.
+no-differences = No differences.
+entries-verified = { $count } entries match the manifest.
+entries-drifted = { $count } entries differ from the manifest.
+undo-reverted = Reverted: { $description }
+undo-nothing = Nothing to undo.
+checks-passed = All { $count } checks passed.
+checks-failed = { $count } checks failed.
+text-indexed = Indexed { $count } text entries of { $archives } archives.
+colors-changed = { $count } colors differ.
+colors-remapped = { $count } colors map to another index.
+pixels-remapped = Remapped { $count } pixels.
+tiles-exported = Exported { $exported } tiles, { $unchanged } unchanged.
+version-features = Features: { $features }
+version-formats = Formats: { $formats }
+no-matching-entries = No entries match the query.
+
+open-failed = Failed to open "{ $file }": { $error }
+read-failed = Failed to read "{ $file }": { $error }
+write-failed = Failed to write "{ $file }": { $error }
+create-failed = Failed to create "{ $file }": { $error }
+entry-not-found = Entry "{ $entry }" not found.
+invalid-number = Invalid number: { $number }
+invalid-tile-number = Invalid tile number: { $tile }
+invalid-range = Invalid range: { $range }
+invalid-lotag = Invalid lotag: { $lotag }
+invalid-box = The box should be x1,y1,x2,y2.
+invalid-offset = The offset should be x,y or x,y,z.
+invalid-translation = The translation should be dx,dy or dx,dy,dz.
+invalid-rotation = Invalid rotation: { $rotation } (should be a multiple of 90 degrees)
+unknown-action = Unknown action: { $action } (should be one of { $actions })
+unknown-format = Unknown format: { $format } (should be one of { $formats })
+unknown-mode = Unknown mode: { $mode }
+unknown-axis = Unknown axis: { $axis } (should be x or y)
+unknown-direction = Unknown direction: { $direction } (should be to-dir or to-grp)
+unknown-edit = Unknown edit [{ $edit }] (should be [[add]], [[replace]] or [[remove]]).
+format-not-detected = Unknown format of "{ $file }".
+image-extension = Output file must end with .png or .bmp.
+text-index-only = Only text indexes are supported, use --text true.
+file-size = File size: { $size }
+
+status-new = new:
+status-modified = modified:
+status-missing = missing:
+status-changed = changed:
+status-unlisted = unlisted:
+status-copied = copied:
+status-removed = removed:
+status-skipped = skipped:
+sync-skipped = { $entry } (only changed on the target side)
+check-passed = pass
+check-failed = fail: { $error }
+column-index = Index
+column-type = Type
+column-count = Count
+column-bytes = Bytes
+column-surface = Surface
+column-tile = Tile
+column-problem = Problem
+column-check = Check
+column-result = Result
+
+provenance-missing = Entry "{ $entry }" has no record in "{ $file }".
+provenance-entry = Entry:
+provenance-source = Source:
+provenance-sha256 = SHA-256:
+provenance-converter = Converter:
+provenance-tool = Tool:
+define-conflict = { $name } is defined with different values:
+defines-conflicting = { $count } conflicting defines found.
+defines-checked = { $count } defines, no conflicts.
+retile-unsupported = Cannot retile "{ $entry }": only MAP and CON entries are supported.
+
+tile-size = Tile { $tile }: { $width }x{ $height }
+tile-offset = Offset: { $x },{ $y }
+tile-animation = Animation: { $type }, { $frames } frames, speed { $speed }
+tile-empty = Tile { $tile } is empty.
+tile-not-in-art = Tile { $tile } is not in any .art file.
+tile-number-too-large = Tile numbers above { $max } are not supported.
+tile-pixels = { $tile }: { $width }x{ $height } ({ $count } bytes)
+tiles-overlap = Tiles { $first }-{ $last } overlap with the tiles { $other-first }-{ $other-last } of { $file }.
+tiles-renumbered = { $file }: tiles { $first }-{ $last } -> { $base }-{ $new-last }
+tiles-count = Tiles: { $count }
+tiles-non-empty = Non-empty tiles: { $count }
+tiles-pixel-bytes = Pixel bytes: { $count }
+tiles-utilization = Non-empty tiles: { $count } of { $limit } ({ $percent }%)
+tiles-not-free = There are not { $count } free tiles below { $limit }.
+tiles-not-free-contiguous = There are not { $count } free contiguous tiles below { $limit }.
+tiles-allocated = Allocated: { $first }-{ $last }
+tiles-reserved = Reserved in "{ $file }".
+preview-palette-missing = The archive has no PALETTE.DAT for the previews, give one with --palette.
+heading-files = Files:
+heading-gaps = Gaps:
+heading-largest-tiles = Largest tiles:
+heading-types = Types:
+heading-tiles = Tiles:
+heading-sounds = Sounds:
+heading-maps = Maps:
+stats-entries = Entries: { $count }
+stats-bytes = Bytes: { $count }
+no-extension = (none)
+sounds-count = Sounds: { $count }
+sounds-seconds = Seconds: { $seconds }
+sounds-unreadable = Unreadable: { $count }
+maps-episode = Episode { $episode }: { $count }
+maps-user = User maps: { $count }
+
+map-player-start = Player start: { $x },{ $y },{ $z } angle { $angle } sector { $sector }
+map-sectors = Sectors: { $count }
+map-walls = Walls: { $count }
+map-sprites = Sprites: { $count }
+picnum-count = Picnum { $picnum }: { $count }
+map-version-target = Maps can only be converted to version { $version }.
+map-version-current = "{ $file }" is already version { $version }.
+map-version-unsupported = Unsupported map version: { $version }
+map-file-or-entry = Give either --map-file or --entry.
+no-sector-filter = Select sectors with --sectors, --lotag or --box.
+no-sectors-selected = No sectors were selected.
+region-extracted = Extracted { $sectors } sectors, { $walls } walls and { $sprites } sprites.
+sectors-merged = Merged { $count } sectors as sectors { $first }-{ $last }.
+problems-found = Problems: { $count }
@@ -0,0 +1,143 @@
+# Finnish translation of en.ftl.
+
+error = Virhe: { $error }
+warning = Varoitus: { $warning }
+no-arguments = Argumentteja ei annettu.
+missing-arguments = Argumentteja puuttuu.
+unknown-command = Tuntematon komento: { $command }
+use-output-dir = Käytä valitsinta --output-dir kirjoittaaksesi muokatun arkiston toiseen hakemistoon.
+output-exists = "{ $file }" on jo olemassa, käytä valitsinta --output-file purkaaksesi toisella nimellä tai --force true korvataksesi sen.
+force-and-no-overwrite = Valitsimia --force ja --no-overwrite ei voi käyttää yhdessä.
+missing-value = Valitsimelta { $option } puuttuu arvo.
+invalid-switch = Valitsimen { $option } jälkeen pitää olla true tai false.
+
+entry-added = Lisätty { $entry }
+entry-replaced = Korvattu { $entry }
+entries-removed = Poistettu { $entries }
+entries-packed = { $count } tiedostoa pakattu arkistoon { $file }.
+entries-extracted = { $extracted } purettu, { $skipped } ohitettu, arkistoja { $archives }.
+entries-listed = { $count } tiedostoa listattu tiedostoon { $file }.
+entries-rearranged = { $count } tiedostoa järjestetty uudelleen.
+asset-renamed = { $from } nimetty uudelleen: { $to }.
+references = { $name }: { $count } viittausta
+no-tile-references = Viittauksia tiileen { $tile } ei löytynyt.
+plan-not-applied = Mitään ei muutettu, käytä valitsinta --mode apply suunnitelman toteuttamiseksi.
+no-differences = Ei eroja.
+entries-verified = { $count } tiedostoa vastaa luetteloa.
+entries-drifted = { $count } tiedostoa poikkeaa luettelosta.
+undo-reverted = Peruttu: { $description }
+undo-nothing = Ei mitään peruttavaa.
+checks-passed = Kaikki { $count } tarkistusta onnistuivat.
+checks-failed = { $count } tarkistusta epäonnistui.
+text-indexed = Indeksoitu { $count } tekstitiedostoa { $archives } arkistosta.
+colors-changed = { $count } väriä eroaa.
+colors-remapped = { $count } väriä siirtyy toiseen indeksiin.
+pixels-remapped = Muunnettiin { $count } pikseliä.
+tiles-exported = Vietiin { $exported } kuvaa, { $unchanged } ennallaan.
+version-features = Ominaisuudet: { $features }
+version-formats = Tiedostomuodot: { $formats }
+no-matching-entries = Mikään tiedosto ei vastaa hakua.
+
+open-failed = Tiedoston "{ $file }" avaaminen epäonnistui: { $error }
+read-failed = Tiedoston "{ $file }" lukeminen epäonnistui: { $error }
+write-failed = Tiedoston "{ $file }" kirjoittaminen epäonnistui: { $error }
+create-failed = Kansion "{ $file }" luominen epäonnistui: { $error }
+entry-not-found = Tiedostoa "{ $entry }" ei löytynyt.
+invalid-number = Virheellinen luku: { $number }
+invalid-tile-number = Virheellinen kuvan numero: { $tile }
+invalid-range = Virheellinen väli: { $range }
+invalid-lotag = Virheellinen lotag: { $lotag }
+invalid-box = Alue annetaan muodossa x1,y1,x2,y2.
+invalid-offset = Siirtymä annetaan muodossa x,y tai x,y,z.
+invalid-translation = Siirto annetaan muodossa dx,dy tai dx,dy,dz.
+invalid-rotation = Virheellinen kierto: { $rotation } (pitää olla 90 asteen monikerta)
+unknown-action = Tuntematon toiminto: { $action } (pitää olla jokin näistä: { $actions })
+unknown-format = Tuntematon muoto: { $format } (pitää olla jokin näistä: { $formats })
+unknown-mode = Tuntematon tila: { $mode }
+unknown-axis = Tuntematon akseli: { $axis } (pitää olla x tai y)
+unknown-direction = Tuntematon suunta: { $direction } (pitää olla to-dir tai to-grp)
+unknown-edit = Tuntematon muokkaus [{ $edit }] (pitää olla [[add]], [[replace]] tai [[remove]]).
+format-not-detected = Tiedoston "{ $file }" muoto on tuntematon.
+image-extension = Tulostiedoston päätteen pitää olla .png tai .bmp.
+text-index-only = Vain tekstihakemistoja tuetaan, käytä --text true.
+file-size = Tiedoston koko: { $size }
+
+status-new = uusi:
+status-modified = muuttunut:
+status-missing = puuttuu:
+status-changed = muuttunut:
+status-unlisted = listaamaton:
+status-copied = kopioitu:
+status-removed = poistettu:
+status-skipped = ohitettu:
+sync-skipped = { $entry } (muuttunut vain kohteessa)
+check-passed = ok
+check-failed = virhe: { $error }
+column-index = Indeksi
+column-type = Tyyppi
+column-count = Määrä
+column-bytes = Tavut
+column-surface = Pinta
+column-tile = Kuva
+column-problem = Ongelma
+column-check = Tarkistus
+column-result = Tulos
+
+provenance-missing = Tiedostolle "{ $entry }" ei ole merkintää tiedostossa "{ $file }".
+provenance-entry = Tiedosto:
+provenance-source = Lähde:
+provenance-converter = Muunnin:
+provenance-tool = Työkalu:
+define-conflict = { $name } on määritelty eri arvoilla:
+defines-conflicting = Löytyi { $count } ristiriitaista määrittelyä.
+defines-checked = { $count } määrittelyä, ei ristiriitoja.
+retile-unsupported = Tiedostoa "{ $entry }" ei voi numeroida uudelleen: vain MAP- ja CON-tiedostoja tuetaan.
+
+tile-size = Kuva { $tile }: { $width }x{ $height }
+tile-offset = Siirtymä: { $x },{ $y }
+tile-animation = Animaatio: { $type }, { $frames } ruutua, nopeus { $speed }
+tile-empty = Kuva { $tile } on tyhjä.
+tile-not-in-art = Kuva { $tile } ei ole missään .art-tiedostossa.
+tile-number-too-large = Suurempia kuvanumeroita kuin { $max } ei tueta.
+tile-pixels = { $tile }: { $width }x{ $height } ({ $count } tavua)
+tiles-overlap = Kuvat { $first }-{ $last } menevät päällekkäin tiedoston { $file } kuvien { $other-first }-{ $other-last } kanssa.
+tiles-renumbered = { $file }: kuvat { $first }-{ $last } -> { $base }-{ $new-last }
+tiles-count = Kuvia: { $count }
+tiles-non-empty = Ei-tyhjiä kuvia: { $count }
+tiles-pixel-bytes = Pikselitavuja: { $count }
+tiles-utilization = Ei-tyhjiä kuvia: { $count } / { $limit } ({ $percent }%)
+tiles-not-free = Alle numeron { $limit } ei ole { $count } vapaata kuvaa.
+tiles-not-free-contiguous = Alle numeron { $limit } ei ole { $count } peräkkäistä vapaata kuvaa.
+tiles-allocated = Varattu: { $first }-{ $last }
+tiles-reserved = Varaukset tallennettu tiedostoon "{ $file }".
+preview-palette-missing = Arkistossa ei ole esikatselukuvia varten PALETTE.DAT-tiedostoa, anna se valinnalla --palette.
+heading-files = Tiedostot:
+heading-gaps = Aukot:
+heading-largest-tiles = Suurimmat kuvat:
+heading-types = Tyypit:
+heading-tiles = Kuvat:
+heading-sounds = Äänet:
+heading-maps = Kartat:
+stats-entries = Tiedostoja: { $count }
+stats-bytes = Tavuja: { $count }
+no-extension = (ei päätettä)
+sounds-count = Ääniä: { $count }
+sounds-seconds = Sekunteja: { $seconds }
+sounds-unreadable = Lukukelvottomia: { $count }
+maps-episode = Episodi { $episode }: { $count }
+maps-user = Omia karttoja: { $count }
+
+map-player-start = Pelaajan aloituspaikka: { $x },{ $y },{ $z } kulma { $angle } sektori { $sector }
+map-sectors = Sektoreita: { $count }
+map-walls = Seiniä: { $count }
+map-sprites = Spritejä: { $count }
+picnum-count = Kuva { $picnum }: { $count }
+map-version-target = Kartat voi muuntaa vain versioon { $version }.
+map-version-current = "{ $file }" on jo versiota { $version }.
+map-version-unsupported = Tukematon karttaversio: { $version }
+map-file-or-entry = Anna joko --map-file tai --entry.
+no-sector-filter = Valitse sektorit valinnalla --sectors, --lotag tai --box.
+no-sectors-selected = Yhtään sektoria ei valittu.
+region-extracted = Irrotettiin { $sectors } sektoria, { $walls } seinää ja { $sprites } spriteä.
+sectors-merged = Yhdistettiin { $count } sektoria sektoreiksi { $first }-{ $last }.
+problems-found = Ongelmia: { $count }
@@ -0,0 +1,541 @@
+// Malformed files are reported as errors and never panic, as the
+// processes that embed these crates cannot recover from a panic.
+// clippy::arithmetic_side_effects is not denied, as it flags every operator
+// including those on bounded values. Arithmetic on values read from files
+// is checked or saturating instead, which tests/error_injection.rs covers.
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::expect_used,
+        clippy::indexing_slicing,
+        clippy::panic,
+        clippy::unwrap_used
+    )
+)]
+
+mod region;
+pub mod testing;
+mod transform;
+mod v6;
+
+pub use region::{MAX_SECTORS, MAX_SPRITES, MAX_WALLS};
+pub use transform::Axis;
+pub use v6::{read_version, MAP_VERSION_6};
+
+use std::io::{BufReader, Read};
+
+/// File reader for .map files, which store the levels of Build engine games
+/// as sectors, the walls around them and the sprites inside them.
+///
+/// Only map version 7, the version used by Duke Nukem 3D, is supported.
+///
+/// See https://moddingwiki.shikadi.net/wiki/MAP_Format_(Build)
+///
+/// Besides files, the reader accepts any reader, such as an entry opened
+/// with `GrpFileReader::open_entry`.
+///
+/// ```
+/// use map::{Map, MapFileReader, Sprite};
+/// use std::fs::File;
+///
+/// let mut map = Map::default();
+/// map.sprites.push(Sprite {
+///     picnum: 1405,
+///     ..Sprite::default()
+/// });
+/// let path = std::env::temp_dir().join("map_doc_reader.map");
+/// std::fs::write(&path, map.to_bytes()).unwrap();
+///
+/// let file = File::open(&path).unwrap();
+/// let map = MapFileReader::new(&file).read_map().unwrap();
+/// assert_eq!(map.sprites[0].picnum, 1405);
+/// ```
+pub struct MapFileReader<R> {
+    reader: BufReader<R>,
+}
+impl<R: Read> MapFileReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+        }
+    }
+    pub fn read_map(&mut self) -> Result<Map, String> {
+        let mut data = Vec::new();
+        self.reader
+            .read_to_end(&mut data)
+            .map_err(|_| "Failed to read .map file.")?;
+        Map::parse(&data)
+    }
+}
+
+/// The map version written by Duke Nukem 3D and understood by [`Map::parse`].
+pub const MAP_VERSION: i32 = 7;
+/// Bytes before the sector count: version, player start position, angle and sector.
+pub const HEADER_BYTES: usize = 4 + 3 * 4 + 2 + 2;
+pub const SECTOR_BYTES: usize = 40;
+pub const WALL_BYTES: usize = 32;
+pub const SPRITE_BYTES: usize = 44;
+
+/// A whole level.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Map {
+    pub player_start: PlayerStart,
+    pub sectors: Vec<Sector>,
+    pub walls: Vec<Wall>,
+    pub sprites: Vec<Sprite>,
+}
+impl Map {
+    /// Parses the contents of a .map file, e.g. an entry read from a .grp file.
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut fields = FieldReader::new(data);
+
+        fields.start("Header");
+        let version = fields.i32()?;
+        if version != MAP_VERSION {
+            return Err(format!(
+                "Unsupported map version {} (supported: {})",
+                version, MAP_VERSION
+            ));
+        }
+        let player_start = PlayerStart {
+            x: fields.i32()?,
+            y: fields.i32()?,
+            z: fields.i32()?,
+            angle: fields.i16()?,
+            sector: fields.i16()?,
+        };
+
+        fields.start("Sector count");
+        let sector_count = fields.u16()? as usize;
+        let sectors = (0..sector_count)
+            .map(|i| {
+                fields.start(format!("Sector {}", i));
+                Sector::read(&mut fields)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        fields.start("Wall count");
+        let wall_count = fields.u16()? as usize;
+        let walls = (0..wall_count)
+            .map(|i| {
+                fields.start(format!("Wall {}", i));
+                Wall::read(&mut fields)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        fields.start("Sprite count");
+        let sprite_count = fields.u16()? as usize;
+        let sprites = (0..sprite_count)
+            .map(|i| {
+                fields.start(format!("Sprite {}", i));
+                Sprite::read(&mut fields)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            player_start,
+            sectors,
+            walls,
+            sprites,
+        })
+    }
+    /// Writes the map in the format read by [`Map::parse`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            HEADER_BYTES
+                + 3 * 2
+                + self.sectors.len() * SECTOR_BYTES
+                + self.walls.len() * WALL_BYTES
+                + self.sprites.len() * SPRITE_BYTES,
+        );
+        bytes.extend_from_slice(&MAP_VERSION.to_le_bytes());
+        let start = &self.player_start;
+        bytes.extend_from_slice(&start.x.to_le_bytes());
+        bytes.extend_from_slice(&start.y.to_le_bytes());
+        bytes.extend_from_slice(&start.z.to_le_bytes());
+        bytes.extend_from_slice(&start.angle.to_le_bytes());
+        bytes.extend_from_slice(&start.sector.to_le_bytes());
+
+        bytes.extend_from_slice(&(self.sectors.len() as u16).to_le_bytes());
+        for sector in &self.sectors {
+            sector.write(&mut bytes);
+        }
+        bytes.extend_from_slice(&(self.walls.len() as u16).to_le_bytes());
+        for wall in &self.walls {
+            wall.write(&mut bytes);
+        }
+        bytes.extend_from_slice(&(self.sprites.len() as u16).to_le_bytes());
+        for sprite in &self.sprites {
+            sprite.write(&mut bytes);
+        }
+        bytes
+    }
+}
+
+/// Where the player starts the level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlayerStart {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    /// Build angle, where 2048 is a full turn.
+    pub angle: i16,
+    pub sector: i16,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sector {
+    /// Index of the first wall of the sector.
+    pub wall_ptr: i16,
+    pub wall_count: i16,
+    pub ceiling_z: i32,
+    pub floor_z: i32,
+    pub ceiling_stat: u16,
+    pub floor_stat: u16,
+    pub ceiling_picnum: i16,
+    pub ceiling_heinum: i16,
+    pub ceiling_shade: i8,
+    pub ceiling_pal: u8,
+    pub ceiling_x_panning: u8,
+    pub ceiling_y_panning: u8,
+    pub floor_picnum: i16,
+    pub floor_heinum: i16,
+    pub floor_shade: i8,
+    pub floor_pal: u8,
+    pub floor_x_panning: u8,
+    pub floor_y_panning: u8,
+    pub visibility: u8,
+    pub filler: u8,
+    pub lotag: i16,
+    pub hitag: i16,
+    pub extra: i16,
+}
+impl Sector {
+    fn read(fields: &mut FieldReader) -> Result<Self, String> {
+        Ok(Self {
+            wall_ptr: fields.i16()?,
+            wall_count: fields.i16()?,
+            ceiling_z: fields.i32()?,
+            floor_z: fields.i32()?,
+            ceiling_stat: fields.u16()?,
+            floor_stat: fields.u16()?,
+            ceiling_picnum: fields.i16()?,
+            ceiling_heinum: fields.i16()?,
+            ceiling_shade: fields.i8()?,
+            ceiling_pal: fields.u8()?,
+            ceiling_x_panning: fields.u8()?,
+            ceiling_y_panning: fields.u8()?,
+            floor_picnum: fields.i16()?,
+            floor_heinum: fields.i16()?,
+            floor_shade: fields.i8()?,
+            floor_pal: fields.u8()?,
+            floor_x_panning: fields.u8()?,
+            floor_y_panning: fields.u8()?,
+            visibility: fields.u8()?,
+            filler: fields.u8()?,
+            lotag: fields.i16()?,
+            hitag: fields.i16()?,
+            extra: fields.i16()?,
+        })
+    }
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.wall_ptr.to_le_bytes());
+        bytes.extend_from_slice(&self.wall_count.to_le_bytes());
+        bytes.extend_from_slice(&self.ceiling_z.to_le_bytes());
+        bytes.extend_from_slice(&self.floor_z.to_le_bytes());
+        bytes.extend_from_slice(&self.ceiling_stat.to_le_bytes());
+        bytes.extend_from_slice(&self.floor_stat.to_le_bytes());
+        bytes.extend_from_slice(&self.ceiling_picnum.to_le_bytes());
+        bytes.extend_from_slice(&self.ceiling_heinum.to_le_bytes());
+        bytes.extend_from_slice(&self.ceiling_shade.to_le_bytes());
+        bytes.extend_from_slice(&[
+            self.ceiling_pal,
+            self.ceiling_x_panning,
+            self.ceiling_y_panning,
+        ]);
+        bytes.extend_from_slice(&self.floor_picnum.to_le_bytes());
+        bytes.extend_from_slice(&self.floor_heinum.to_le_bytes());
+        bytes.extend_from_slice(&self.floor_shade.to_le_bytes());
+        bytes.extend_from_slice(&[
+            self.floor_pal,
+            self.floor_x_panning,
+            self.floor_y_panning,
+            self.visibility,
+            self.filler,
+        ]);
+        bytes.extend_from_slice(&self.lotag.to_le_bytes());
+        bytes.extend_from_slice(&self.hitag.to_le_bytes());
+        bytes.extend_from_slice(&self.extra.to_le_bytes());
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Wall {
+    pub x: i32,
+    pub y: i32,
+    /// Index of the wall whose start is the end of this wall.
+    pub point2: i16,
+    /// The wall on the other side of a portal, or -1.
+    pub next_wall: i16,
+    /// The sector on the other side of a portal, or -1.
+    pub next_sector: i16,
+    pub cstat: u16,
+    pub picnum: i16,
+    pub over_picnum: i16,
+    pub shade: i8,
+    pub pal: u8,
+    pub x_repeat: u8,
+    pub y_repeat: u8,
+    pub x_panning: u8,
+    pub y_panning: u8,
+    pub lotag: i16,
+    pub hitag: i16,
+    pub extra: i16,
+}
+impl Wall {
+    fn read(fields: &mut FieldReader) -> Result<Self, String> {
+        Ok(Self {
+            x: fields.i32()?,
+            y: fields.i32()?,
+            point2: fields.i16()?,
+            next_wall: fields.i16()?,
+            next_sector: fields.i16()?,
+            cstat: fields.u16()?,
+            picnum: fields.i16()?,
+            over_picnum: fields.i16()?,
+            shade: fields.i8()?,
+            pal: fields.u8()?,
+            x_repeat: fields.u8()?,
+            y_repeat: fields.u8()?,
+            x_panning: fields.u8()?,
+            y_panning: fields.u8()?,
+            lotag: fields.i16()?,
+            hitag: fields.i16()?,
+            extra: fields.i16()?,
+        })
+    }
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.x.to_le_bytes());
+        bytes.extend_from_slice(&self.y.to_le_bytes());
+        bytes.extend_from_slice(&self.point2.to_le_bytes());
+        bytes.extend_from_slice(&self.next_wall.to_le_bytes());
+        bytes.extend_from_slice(&self.next_sector.to_le_bytes());
+        bytes.extend_from_slice(&self.cstat.to_le_bytes());
+        bytes.extend_from_slice(&self.picnum.to_le_bytes());
+        bytes.extend_from_slice(&self.over_picnum.to_le_bytes());
+        bytes.extend_from_slice(&self.shade.to_le_bytes());
+        bytes.extend_from_slice(&[
+            self.pal,
+            self.x_repeat,
+            self.y_repeat,
+            self.x_panning,
+            self.y_panning,
+        ]);
+        bytes.extend_from_slice(&self.lotag.to_le_bytes());
+        bytes.extend_from_slice(&self.hitag.to_le_bytes());
+        bytes.extend_from_slice(&self.extra.to_le_bytes());
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sprite {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub cstat: u16,
+    pub picnum: i16,
+    pub shade: i8,
+    pub pal: u8,
+    pub clip_dist: u8,
+    pub filler: u8,
+    pub x_repeat: u8,
+    pub y_repeat: u8,
+    pub x_offset: i8,
+    pub y_offset: i8,
+    pub sector: i16,
+    pub stat: i16,
+    /// Build angle, where 2048 is a full turn.
+    pub angle: i16,
+    pub owner: i16,
+    pub x_vel: i16,
+    pub y_vel: i16,
+    pub z_vel: i16,
+    pub lotag: i16,
+    pub hitag: i16,
+    pub extra: i16,
+}
+impl Sprite {
+    fn read(fields: &mut FieldReader) -> Result<Self, String> {
+        Ok(Self {
+            x: fields.i32()?,
+            y: fields.i32()?,
+            z: fields.i32()?,
+            cstat: fields.u16()?,
+            picnum: fields.i16()?,
+            shade: fields.i8()?,
+            pal: fields.u8()?,
+            clip_dist: fields.u8()?,
+            filler: fields.u8()?,
+            x_repeat: fields.u8()?,
+            y_repeat: fields.u8()?,
+            x_offset: fields.i8()?,
+            y_offset: fields.i8()?,
+            sector: fields.i16()?,
+            stat: fields.i16()?,
+            angle: fields.i16()?,
+            owner: fields.i16()?,
+            x_vel: fields.i16()?,
+            y_vel: fields.i16()?,
+            z_vel: fields.i16()?,
+            lotag: fields.i16()?,
+            hitag: fields.i16()?,
+            extra: fields.i16()?,
+        })
+    }
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.x.to_le_bytes());
+        bytes.extend_from_slice(&self.y.to_le_bytes());
+        bytes.extend_from_slice(&self.z.to_le_bytes());
+        bytes.extend_from_slice(&self.cstat.to_le_bytes());
+        bytes.extend_from_slice(&self.picnum.to_le_bytes());
+        bytes.extend_from_slice(&self.shade.to_le_bytes());
+        bytes.extend_from_slice(&[
+            self.pal,
+            self.clip_dist,
+            self.filler,
+            self.x_repeat,
+            self.y_repeat,
+        ]);
+        bytes.extend_from_slice(&self.x_offset.to_le_bytes());
+        bytes.extend_from_slice(&self.y_offset.to_le_bytes());
+        bytes.extend_from_slice(&self.sector.to_le_bytes());
+        bytes.extend_from_slice(&self.stat.to_le_bytes());
+        bytes.extend_from_slice(&self.angle.to_le_bytes());
+        bytes.extend_from_slice(&self.owner.to_le_bytes());
+        bytes.extend_from_slice(&self.x_vel.to_le_bytes());
+        bytes.extend_from_slice(&self.y_vel.to_le_bytes());
+        bytes.extend_from_slice(&self.z_vel.to_le_bytes());
+        bytes.extend_from_slice(&self.lotag.to_le_bytes());
+        bytes.extend_from_slice(&self.hitag.to_le_bytes());
+        bytes.extend_from_slice(&self.extra.to_le_bytes());
+    }
+}
+
+/// Reads little-endian fields one after another. Errors name the record
+/// being read and where it starts, e.g. "Wall 12 at offset 0x1D6 is truncated."
+struct FieldReader<'a> {
+    data: &'a [u8],
+    at: usize,
+    record: String,
+    record_offset: usize,
+}
+impl<'a> FieldReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            at: 0,
+            record: "Header".to_string(),
+            record_offset: 0,
+        }
+    }
+    /// Marks the start of a record at the current offset.
+    fn start(&mut self, record: impl Into<String>) {
+        self.record = record.into();
+        self.record_offset = self.at;
+    }
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let bytes = self
+            .data
+            .get(self.at..)
+            .and_then(|d| d.first_chunk::<N>())
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "{} at offset 0x{:X} is truncated.",
+                    self.record, self.record_offset
+                )
+            })?;
+        self.at += N;
+        Ok(bytes)
+    }
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes::<1>()?[0])
+    }
+    fn i8(&mut self) -> Result<i8, String> {
+        Ok(self.u8()? as i8)
+    }
+    fn u16(&mut self) -> Result<u16, String> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+    fn i16(&mut self) -> Result<i16, String> {
+        self.bytes().map(i16::from_le_bytes)
+    }
+    fn i32(&mut self) -> Result<i32, String> {
+        self.bytes().map(i32::from_le_bytes)
+    }
+}
+
+#[test]
+fn should_round_trip_map() {
+    let map = Map {
+        player_start: PlayerStart {
+            x: 1024,
+            y: -2048,
+            z: 8192,
+            angle: 1536,
+            sector: 0,
+        },
+        sectors: vec![Sector {
+            wall_count: 2,
+            floor_z: 8192,
+            ceiling_picnum: 183,
+            floor_picnum: 898,
+            floor_shade: -8,
+            lotag: 1,
+            ..Sector::default()
+        }],
+        walls: vec![
+            Wall {
+                point2: 1,
+                next_wall: -1,
+                next_sector: -1,
+                picnum: 1097,
+                ..Wall::default()
+            },
+            Wall {
+                x: 512,
+                next_wall: -1,
+                next_sector: -1,
+                over_picnum: 1098,
+                ..Wall::default()
+            },
+        ],
+        sprites: vec![Sprite {
+            picnum: 1405,
+            x_offset: -3,
+            angle: 512,
+            hitag: 7,
+            ..Sprite::default()
+        }],
+    };
+
+    let bytes = map.to_bytes();
+    assert_eq!(
+        bytes.len(),
+        HEADER_BYTES + 6 + SECTOR_BYTES + 2 * WALL_BYTES + SPRITE_BYTES
+    );
+    // The fields are where other Build tools expect them.
+    let sprite = HEADER_BYTES + 6 + SECTOR_BYTES + 2 * WALL_BYTES;
+    assert_eq!(bytes[sprite + 14..sprite + 16], 1405i16.to_le_bytes());
+    assert_eq!(
+        bytes[HEADER_BYTES + 4 + SECTOR_BYTES + WALL_BYTES + 18..][..2],
+        1098i16.to_le_bytes()
+    );
+    assert_eq!(Map::parse(&bytes).unwrap(), map);
+
+    assert_eq!(
+        Map::parse(&bytes[..bytes.len() - 1]),
+        Err(format!("Sprite 0 at offset 0x{:X} is truncated.", sprite))
+    );
+    let mut v6 = bytes.clone();
+    v6[0] = 6;
+    assert!(Map::parse(&v6).is_err());
+}
@@ -0,0 +1,238 @@
+//! Copying parts of maps into other maps, for reusing rooms as prefabs.
+
+use crate::{Map, Wall};
+
+/// Limits of Duke Nukem 3D, which fails to load maps that exceed them.
+pub const MAX_SECTORS: usize = 1024;
+pub const MAX_WALLS: usize = 8192;
+pub const MAX_SPRITES: usize = 4096;
+
+impl Map {
+    /// The walls around a sector, or none when its wall range is invalid.
+    pub fn sector_walls(&self, sector: usize) -> &[Wall] {
+        self.sectors
+            .get(sector)
+            .and_then(|s| {
+                let first = usize::try_from(s.wall_ptr).ok()?;
+                let count = usize::try_from(s.wall_count).ok()?;
+                self.walls.get(first..first + count)
+            })
+            .unwrap_or_default()
+    }
+    /// A new map of the given sectors with their walls and sprites.
+    ///
+    /// Walls between a selected and an unselected sector become solid walls.
+    /// The player start is kept when its sector is selected and is otherwise
+    /// moved to the middle of the first selected sector.
+    pub fn extract_sectors(&self, sectors: &[usize]) -> Result<Map, String> {
+        let mut old_to_new = vec![-1i16; self.sectors.len()];
+        for (new, old) in sectors.iter().enumerate() {
+            let Some(slot) = old_to_new.get_mut(*old) else {
+                return Err(format!("Sector {} does not exist.", old));
+            };
+            *slot = new as i16;
+        }
+        let new_sector = |old: i16| {
+            usize::try_from(old)
+                .ok()
+                .and_then(|old| old_to_new.get(old).copied())
+                .unwrap_or(-1)
+        };
+
+        // The walls of every sector keep their order, so only their start changes.
+        if self.walls.len() > i16::MAX as usize {
+            return Err(format!(
+                "The map has {} walls, more than walls can refer to.",
+                self.walls.len()
+            ));
+        }
+        let mut wall_old_to_new = vec![-1i16; self.walls.len()];
+        let mut next_wall = 0i16;
+        let old_sector = |old: &usize| {
+            self.sectors
+                .get(*old)
+                .copied()
+                .ok_or_else(|| format!("Sector {} does not exist.", old))
+        };
+        for old in sectors {
+            let sector = old_sector(old)?;
+            let wall_count = self.sector_walls(*old).len();
+            if wall_count != sector.wall_count.max(0) as usize {
+                return Err(format!("Sector {} has walls beyond the last wall.", old));
+            }
+            let first_wall = sector.wall_ptr.max(0) as usize;
+            for slot in wall_old_to_new.iter_mut().skip(first_wall).take(wall_count) {
+                *slot = next_wall;
+                next_wall += 1;
+            }
+        }
+        let new_wall = |old: i16| {
+            usize::try_from(old)
+                .ok()
+                .and_then(|old| wall_old_to_new.get(old).copied())
+                .unwrap_or(-1)
+        };
+
+        let mut map = Map {
+            player_start: self.player_start,
+            ..Map::default()
+        };
+        for old in sectors {
+            let mut sector = old_sector(old)?;
+            sector.wall_ptr = map.walls.len() as i16;
+            for wall in self.sector_walls(*old) {
+                let mut wall = *wall;
+                wall.point2 = new_wall(wall.point2);
+                wall.next_sector = new_sector(wall.next_sector);
+                wall.next_wall = if wall.next_sector >= 0 {
+                    new_wall(wall.next_wall)
+                } else {
+                    -1
+                };
+                map.walls.push(wall);
+            }
+            map.sectors.push(sector);
+        }
+        for sprite in &self.sprites {
+            let sector = new_sector(sprite.sector);
+            if sector >= 0 {
+                let mut sprite = *sprite;
+                sprite.sector = sector;
+                map.sprites.push(sprite);
+            }
+        }
+
+        map.player_start.sector = new_sector(self.player_start.sector);
+        if map.player_start.sector < 0 {
+            let walls = map.sector_walls(0);
+            let count = walls.len().max(1) as i64;
+            let x = walls.iter().map(|w| w.x as i64).sum::<i64>() / count;
+            let y = walls.iter().map(|w| w.y as i64).sum::<i64>() / count;
+            (map.player_start.x, map.player_start.y) = (x as i32, y as i32);
+            map.player_start.z = map.sectors.first().map_or(0, |s| s.floor_z);
+            map.player_start.sector = 0;
+        }
+        Ok(map)
+    }
+    /// Appends the sectors, walls and sprites of `other`, moved by `offset`,
+    /// renumbering them to follow the existing ones. The player start of
+    /// this map is kept.
+    pub fn append(&mut self, other: &Map, offset: (i32, i32, i32)) -> Result<(), String> {
+        // Checked up front, as the renumbered indices must fit in 16 bits.
+        for (name, count, max) in [
+            (
+                "sectors",
+                self.sectors.len() + other.sectors.len(),
+                MAX_SECTORS,
+            ),
+            ("walls", self.walls.len() + other.walls.len(), MAX_WALLS),
+            (
+                "sprites",
+                self.sprites.len() + other.sprites.len(),
+                MAX_SPRITES,
+            ),
+        ] {
+            if count > max {
+                return Err(format!(
+                    "The map would have {} {} (at most {}).",
+                    count, name, max
+                ));
+            }
+        }
+
+        let sector_base = self.sectors.len() as i16;
+        let wall_base = self.walls.len() as i16;
+        // Indices and coordinates wrap like in Map::translate, which only
+        // happens with indices that were invalid to begin with.
+        let rebase = |index: i16, base: i16| {
+            if index >= 0 {
+                index.wrapping_add(base)
+            } else {
+                index
+            }
+        };
+
+        for sector in &other.sectors {
+            let mut sector = *sector;
+            sector.wall_ptr = sector.wall_ptr.wrapping_add(wall_base);
+            sector.ceiling_z = sector.ceiling_z.wrapping_add(offset.2);
+            sector.floor_z = sector.floor_z.wrapping_add(offset.2);
+            self.sectors.push(sector);
+        }
+        for wall in &other.walls {
+            let mut wall = *wall;
+            wall.x = wall.x.wrapping_add(offset.0);
+            wall.y = wall.y.wrapping_add(offset.1);
+            wall.point2 = rebase(wall.point2, wall_base);
+            wall.next_wall = rebase(wall.next_wall, wall_base);
+            wall.next_sector = rebase(wall.next_sector, sector_base);
+            self.walls.push(wall);
+        }
+        for sprite in &other.sprites {
+            let mut sprite = *sprite;
+            sprite.x = sprite.x.wrapping_add(offset.0);
+            sprite.y = sprite.y.wrapping_add(offset.1);
+            sprite.z = sprite.z.wrapping_add(offset.2);
+            sprite.sector = rebase(sprite.sector, sector_base);
+            self.sprites.push(sprite);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn should_extract_sectors() {
+    let map = crate::testing::two_rooms();
+    assert_eq!((map.walls[1].next_wall, map.walls[7].next_wall), (7, 1));
+
+    let region = map.extract_sectors(&[1]).unwrap();
+    assert_eq!(region.sectors.len(), 1);
+    assert_eq!(region.sectors[0].wall_ptr, 0);
+    assert_eq!(
+        region.walls.iter().map(|w| w.point2).collect::<Vec<_>>(),
+        [1, 2, 3, 0]
+    );
+    // The shared wall became solid.
+    assert!(region
+        .walls
+        .iter()
+        .all(|w| w.next_wall == -1 && w.next_sector == -1));
+    assert_eq!(region.sprites.len(), 1);
+    assert_eq!((region.sprites[0].x, region.sprites[0].sector), (1500, 0));
+    assert_eq!(
+        (
+            region.player_start.x,
+            region.player_start.y,
+            region.player_start.sector
+        ),
+        (1536, 512, 0)
+    );
+    assert_eq!(region.player_start.z, 8192);
+
+    assert_eq!(map.extract_sectors(&[0, 1]).unwrap(), map);
+    assert!(map.extract_sectors(&[2]).is_err());
+}
+
+#[test]
+fn should_append_maps() {
+    let mut map = crate::testing::two_rooms();
+    let other = crate::testing::two_rooms();
+    map.append(&other, (4096, 0, -1024)).unwrap();
+
+    assert_eq!(map.sectors.len(), 4);
+    assert_eq!(map.sectors[3].wall_ptr, 12);
+    assert_eq!(map.sectors[3].floor_z, 8192 - 1024);
+    assert_eq!(map.walls[9].next_wall, 15);
+    assert_eq!(map.walls[9].next_sector, 3);
+    assert_eq!(map.walls[10].next_sector, -1);
+    assert_eq!((map.walls[8].x, map.walls[8].point2), (4096, 9));
+    assert_eq!((map.sprites[3].x, map.sprites[3].sector), (1500 + 4096, 3));
+    // Extracting the appended sectors gives back their original numbering.
+    let links = |map: &Map| {
+        map.walls
+            .iter()
+            .map(|w| (w.point2, w.next_wall, w.next_sector))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(links(&map.extract_sectors(&[2, 3]).unwrap()), links(&other));
+}
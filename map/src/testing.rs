@@ -0,0 +1,66 @@
+//! Synthetic fixture generation for tests and examples.
+//!
+//! The real Duke Nukem 3D data files are copyrighted and cannot be shipped
+//! with the crate, so tests and documentation examples build small maps
+//! in memory instead.
+
+use crate::{Map, Sector, Sprite, Wall};
+
+/// Two square rooms of 1024 units side by side, joined by the wall at
+/// x = 1024, with one sprite in each. The second room is lower.
+///
+/// ```
+/// let map = map::testing::two_rooms();
+/// assert_eq!((map.sectors.len(), map.walls.len(), map.sprites.len()), (2, 8, 2));
+/// assert_eq!(map.walls[1].next_wall, 7);
+/// ```
+pub fn two_rooms() -> Map {
+    // Two squares side by side, sharing the wall at x = 1024.
+    let square = |x: i32, first_wall: i16, shared: i16, next_sector: i16, next_wall: i16| {
+        [(x, 0), (x + 1024, 0), (x + 1024, 1024), (x, 1024)]
+            .iter()
+            .enumerate()
+            .map(|(i, (x, y))| {
+                let is_shared = i as i16 == shared;
+                Wall {
+                    x: *x,
+                    y: *y,
+                    point2: first_wall + (i as i16 + 1) % 4,
+                    next_sector: if is_shared { next_sector } else { -1 },
+                    next_wall: if is_shared { next_wall } else { -1 },
+                    ..Wall::default()
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut walls = square(0, 0, 1, 1, 7);
+    walls.extend(square(1024, 4, 3, 0, 1));
+    Map {
+        sectors: vec![
+            Sector {
+                wall_count: 4,
+                lotag: 1,
+                ..Sector::default()
+            },
+            Sector {
+                wall_ptr: 4,
+                wall_count: 4,
+                floor_z: 8192,
+                ..Sector::default()
+            },
+        ],
+        walls,
+        sprites: vec![
+            Sprite {
+                sector: 0,
+                ..Sprite::default()
+            },
+            Sprite {
+                x: 1500,
+                sector: 1,
+                ..Sprite::default()
+            },
+        ],
+        ..Map::default()
+    }
+}
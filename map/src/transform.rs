@@ -0,0 +1,276 @@
+//! Mirroring, rotating and moving whole maps.
+//!
+//! Coordinates are those of the editor's 2D view: x grows to the right and
+//! y grows downwards. Angles are Build angles, where 0 points along the x
+//! axis, 512 along the y axis and 2048 is a full turn.
+
+use crate::{Map, Wall};
+
+/// The coordinate that [`Map::mirror`] negates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+impl Map {
+    /// Mirrors the map by negating the x or y coordinate of everything.
+    ///
+    /// Mirroring would turn every wall loop inside out, so the loops are
+    /// reversed to keep their winding. The first wall of every sector stays
+    /// first, as slopes are relative to it, and wall panning is adjusted so
+    /// that textures stay aligned across walls. Slopes keep their sign: the
+    /// first wall now runs the other way, which cancels out the reflection.
+    /// Wall and floor sprites are flipped, as their pictures are mirrored.
+    pub fn mirror(&mut self, axis: Axis) -> Result<(), String> {
+        let mirror_point = |x: i32, y: i32| match axis {
+            Axis::X => (x.wrapping_neg(), y),
+            Axis::Y => (x, y.wrapping_neg()),
+        };
+        let mirror_angle = |angle: i16| match axis {
+            Axis::X => (1024 - angle.rem_euclid(2048)).rem_euclid(2048),
+            Axis::Y => (-angle.rem_euclid(2048)).rem_euclid(2048),
+        };
+
+        // Every loop [w0, w1, ..., wn] becomes [w0', wn', ..., w1'], where w'
+        // is the wall w running the other way, and takes the slots of the
+        // loop in order.
+        let mut loops = Vec::new();
+        for sector in 0..self.sectors.len() {
+            loops.extend(self.wall_loops(sector)?);
+        }
+        fn reversed_loop(walls: &[usize]) -> impl Iterator<Item = &usize> {
+            walls.iter().take(1).chain(walls.iter().skip(1).rev())
+        }
+        let mut new_index = (0..self.walls.len()).collect::<Vec<_>>();
+        for walls in &loops {
+            for (old, reversed) in walls.iter().zip(reversed_loop(walls)) {
+                if let Some(index) = new_index.get_mut(*old) {
+                    *index = *reversed;
+                }
+            }
+        }
+
+        let old_walls = self.walls.clone();
+        for walls in &loops {
+            let next_slots = walls.iter().cycle().skip(1);
+            for ((old, reversed), next_slot) in
+                walls.iter().zip(reversed_loop(walls)).zip(next_slots)
+            {
+                // wall_loops only returns walls whose point2 is in the same loop.
+                let wall = old_walls.get(*reversed);
+                let end = wall.and_then(|w| old_walls.get(usize::try_from(w.point2).ok()?));
+                let (Some(wall), Some(end), Some(new_wall)) = (wall, end, self.walls.get_mut(*old))
+                else {
+                    return Err(format!("Wall {} leads to a missing wall.", reversed));
+                };
+                let (x, y) = mirror_point(end.x, end.y);
+                *new_wall = Wall {
+                    x,
+                    y,
+                    point2: *next_slot as i16,
+                    next_wall: usize::try_from(wall.next_wall)
+                        .ok()
+                        .and_then(|next| new_index.get(next))
+                        .map_or(-1, |next| *next as i16),
+                    // The texture ran from the start of the wall and now runs from
+                    // its end, so start where it used to end on the other side.
+                    x_panning: (wall.x_panning as u32 + wall.x_repeat as u32 * 8).wrapping_neg()
+                        as u8,
+                    ..*wall
+                };
+            }
+        }
+
+        for sprite in &mut self.sprites {
+            (sprite.x, sprite.y) = mirror_point(sprite.x, sprite.y);
+            sprite.angle = mirror_angle(sprite.angle);
+            // Bits 4 and 5 align the sprite to a wall or the floor, and bit 2
+            // flips it horizontally. Face sprites always face the viewer.
+            if sprite.cstat & 48 != 0 {
+                sprite.cstat ^= 4;
+            }
+        }
+        let start = &mut self.player_start;
+        (start.x, start.y) = mirror_point(start.x, start.y);
+        start.angle = mirror_angle(start.angle);
+        Ok(())
+    }
+    /// Rotates the map clockwise around the origin by `quarter_turns` times 90 degrees.
+    pub fn rotate(&mut self, quarter_turns: u32) {
+        let rotate_point = |x: i32, y: i32| match quarter_turns % 4 {
+            0 => (x, y),
+            1 => (y.wrapping_neg(), x),
+            2 => (x.wrapping_neg(), y.wrapping_neg()),
+            _ => (y, x.wrapping_neg()),
+        };
+        let rotate_angle = |angle: i16| {
+            (angle.rem_euclid(2048) + (quarter_turns % 4) as i16 * 512).rem_euclid(2048)
+        };
+
+        for wall in &mut self.walls {
+            (wall.x, wall.y) = rotate_point(wall.x, wall.y);
+        }
+        for sprite in &mut self.sprites {
+            (sprite.x, sprite.y) = rotate_point(sprite.x, sprite.y);
+            sprite.angle = rotate_angle(sprite.angle);
+        }
+        let start = &mut self.player_start;
+        (start.x, start.y) = rotate_point(start.x, start.y);
+        start.angle = rotate_angle(start.angle);
+    }
+    /// Moves the map, where negative z is upwards as in the engine.
+    pub fn translate(&mut self, dx: i32, dy: i32, dz: i32) {
+        for sector in &mut self.sectors {
+            sector.ceiling_z = sector.ceiling_z.wrapping_add(dz);
+            sector.floor_z = sector.floor_z.wrapping_add(dz);
+        }
+        for wall in &mut self.walls {
+            wall.x = wall.x.wrapping_add(dx);
+            wall.y = wall.y.wrapping_add(dy);
+        }
+        for sprite in &mut self.sprites {
+            sprite.x = sprite.x.wrapping_add(dx);
+            sprite.y = sprite.y.wrapping_add(dy);
+            sprite.z = sprite.z.wrapping_add(dz);
+        }
+        let start = &mut self.player_start;
+        start.x = start.x.wrapping_add(dx);
+        start.y = start.y.wrapping_add(dy);
+        start.z = start.z.wrapping_add(dz);
+    }
+    /// The walls of a sector split into loops by following point2, the outer loop first.
+    fn wall_loops(&self, sector: usize) -> Result<Vec<Vec<usize>>, String> {
+        let first_wall = self
+            .sectors
+            .get(sector)
+            .map_or(0, |s| s.wall_ptr.max(0) as usize);
+        let walls = first_wall..first_wall + self.sector_walls(sector).len();
+
+        let mut loops = Vec::new();
+        let mut visited = vec![false; walls.len()];
+        for start in walls.clone() {
+            let mut wall_loop = Vec::new();
+            let mut wall = start;
+            // Every wall followed is in `walls`, so it is at or after the first wall.
+            while let Some(seen) = visited.get_mut(wall - first_wall).filter(|seen| !**seen) {
+                *seen = true;
+                wall_loop.push(wall);
+                wall = self
+                    .walls
+                    .get(wall)
+                    .and_then(|w| usize::try_from(w.point2).ok())
+                    .filter(|next| walls.contains(next))
+                    .ok_or_else(|| {
+                        format!(
+                            "Wall {} of sector {} leads outside of the sector.",
+                            wall, sector
+                        )
+                    })?;
+            }
+            if wall_loop.is_empty() {
+                continue;
+            }
+            if wall != start {
+                return Err(format!(
+                    "The walls of sector {} do not form closed loops.",
+                    sector
+                ));
+            }
+            loops.push(wall_loop);
+        }
+        Ok(loops)
+    }
+}
+
+#[test]
+fn should_mirror_and_keep_winding() {
+    // Twice the signed area, positive for clockwise loops in the editor's view.
+    let winding = |map: &Map, walls: &[usize]| {
+        walls
+            .iter()
+            .map(|w| {
+                let (a, b) = (&map.walls[*w], &map.walls[map.walls[*w].point2 as usize]);
+                a.x as i64 * b.y as i64 - b.x as i64 * a.y as i64
+            })
+            .sum::<i64>()
+    };
+    // The height of a slope at a point relative to the first wall of the
+    // sector, up to a constant factor, as the engine computes it.
+    let slope_z = |map: &Map, sector: usize, (x, y): (i32, i32)| {
+        let first = &map.walls[map.sectors[sector].wall_ptr as usize];
+        let second = &map.walls[first.point2 as usize];
+        let (dx, dy) = ((second.x - first.x) as i64, (second.y - first.y) as i64);
+        let distance = dx * (y - first.y) as i64 - dy * (x - first.x) as i64;
+        map.sectors[sector].floor_heinum as i64 * distance / dx.abs().max(dy.abs())
+    };
+    let mut map = crate::testing::two_rooms();
+    map.walls[0].x_repeat = 8;
+    map.walls[0].x_panning = 16;
+    map.sprites[1].angle = 256;
+    map.sprites[0].cstat = 16;
+    map.sprites[1].cstat = 1;
+    map.sectors[1].floor_stat = 2;
+    map.sectors[1].floor_heinum = -4096;
+    let original = map.clone();
+    map.mirror(Axis::X).unwrap();
+
+    assert_eq!(
+        map.walls.iter().map(|w| w.point2).collect::<Vec<_>>(),
+        [1, 2, 3, 0, 5, 6, 7, 4]
+    );
+    for sector in 0..2 {
+        let walls = map.wall_loops(sector).unwrap().remove(0);
+        assert!(winding(&map, &walls) > 0);
+    }
+    // The first wall is the old first wall running the other way.
+    assert_eq!((map.walls[0].x, map.walls[0].y), (-1024, 0));
+    assert_eq!((map.walls[1].x, map.walls[1].y), (0, 0));
+    assert_eq!(map.walls[0].x_panning, (256 - 16 - 64) as u8);
+    // The shared wall still leads to the other room and back.
+    for (i, wall) in map.walls.iter().enumerate() {
+        if wall.next_wall >= 0 {
+            let other = &map.walls[wall.next_wall as usize];
+            assert_eq!(other.next_wall, i as i16);
+            assert_eq!((other.x, other.y), {
+                let end = &map.walls[wall.point2 as usize];
+                (end.x, end.y)
+            });
+        }
+    }
+    assert_eq!(map.walls.iter().filter(|w| w.next_wall >= 0).count(), 2);
+    assert_eq!((map.sprites[1].x, map.sprites[1].angle), (-1500, 768));
+    // Wall sprites are flipped, face sprites are not.
+    assert_eq!((map.sprites[0].cstat, map.sprites[1].cstat), (20, 1));
+    // Slopes still rise the same way.
+    assert_eq!(map.sectors[1].floor_heinum, -4096);
+    for (x, y) in [(1100, 100), (1500, 900), (2000, 512)] {
+        assert_eq!(slope_z(&map, 1, (-x, y)), slope_z(&original, 1, (x, y)));
+        assert_ne!(slope_z(&original, 1, (x, y)), 0);
+    }
+
+    map.mirror(Axis::X).unwrap();
+    assert_eq!(map, original);
+
+    map.mirror(Axis::Y).unwrap();
+    for (x, y) in [(1100, 100), (1500, 900), (2000, 512)] {
+        assert_eq!(slope_z(&map, 1, (x, -y)), slope_z(&original, 1, (x, y)));
+    }
+}
+
+#[test]
+fn should_rotate_and_translate() {
+    let mut map = crate::testing::two_rooms();
+    map.sprites[1].angle = 1800;
+    let original = map.clone();
+
+    map.rotate(1);
+    assert_eq!((map.sprites[1].x, map.sprites[1].y), (0, 1500));
+    assert_eq!(map.sprites[1].angle, 264);
+    map.rotate(3);
+    assert_eq!(map, original);
+
+    map.translate(100, -100, 1024);
+    assert_eq!((map.walls[1].x, map.walls[1].y), (1124, -100));
+    assert_eq!(map.sectors[1].floor_z, 8192 + 1024);
+}
@@ -0,0 +1,307 @@
+//! Reading maps of version 6, the version before Duke Nukem 3D.
+//!
+//! Version 6 stores the same fields as version 7 in a different order, with
+//! sector flags of 8 bits, unsigned wall indices of sectors and slopes in
+//! units 32 times as steep. Converting widens fields and scales slopes like
+//! Build does, so the only losses are values that version 7 cannot
+//! represent, which are reported as warnings.
+
+use crate::{
+    FieldReader, Map, PlayerStart, Sector, Sprite, Wall, MAX_SECTORS, MAX_SPRITES, MAX_WALLS,
+};
+
+pub const MAP_VERSION_6: i32 = 6;
+
+/// The version number at the start of a .map file, without reading the rest.
+pub fn read_version(data: &[u8]) -> Result<i32, String> {
+    FieldReader::new(data).i32()
+}
+
+impl Map {
+    /// Parses a version 6 map, returning it with warnings about values
+    /// that did not survive the conversion to the version 7 layout.
+    pub fn parse_v6(data: &[u8]) -> Result<(Self, Vec<String>), String> {
+        let mut fields = FieldReader::new(data);
+        let mut warnings = Vec::new();
+
+        let version = fields.i32()?;
+        if version != MAP_VERSION_6 {
+            return Err(format!(
+                "Unsupported map version {} (expected {})",
+                version, MAP_VERSION_6
+            ));
+        }
+        let player_start = PlayerStart {
+            x: fields.i32()?,
+            y: fields.i32()?,
+            z: fields.i32()?,
+            angle: fields.i16()?,
+            sector: fields.i16()?,
+        };
+
+        fields.start("Sector count");
+        let sector_count = fields.u16()? as usize;
+        let mut sectors = Vec::with_capacity(sector_count);
+        for i in 0..sector_count {
+            fields.start(format!("Sector {}", i));
+            let wall_ptr = fields.u16()?;
+            let wall_count = fields.u16()?;
+            if wall_ptr > i16::MAX as u16 || wall_count > i16::MAX as u16 {
+                warnings.push(format!(
+                    "Sector {}: walls {}+{} do not fit, they were clamped.",
+                    i, wall_ptr, wall_count
+                ));
+            }
+            let ceiling_picnum = fields.i16()?;
+            let floor_picnum = fields.i16()?;
+            let ceiling_heinum = fields.i16()?;
+            let floor_heinum = fields.i16()?;
+            let ceiling_z = fields.i32()?;
+            let floor_z = fields.i32()?;
+            let ceiling_shade = fields.i8()?;
+            let floor_shade = fields.i8()?;
+            let ceiling_x_panning = fields.u8()?;
+            let floor_x_panning = fields.u8()?;
+            let ceiling_y_panning = fields.u8()?;
+            let floor_y_panning = fields.u8()?;
+            let ceiling_stat = fields.u8()? as u16;
+            let floor_stat = fields.u8()? as u16;
+            let ceiling_heinum =
+                convert_heinum(ceiling_heinum, ceiling_stat).unwrap_or_else(|heinum| {
+                    warnings.push(format!(
+                        "Sector {}: the ceiling slope {} is too steep, it was clamped.",
+                        i, ceiling_heinum
+                    ));
+                    heinum
+                });
+            let floor_heinum = convert_heinum(floor_heinum, floor_stat).unwrap_or_else(|heinum| {
+                warnings.push(format!(
+                    "Sector {}: the floor slope {} is too steep, it was clamped.",
+                    i, floor_heinum
+                ));
+                heinum
+            });
+            let ceiling_pal = fields.u8()?;
+            let floor_pal = fields.u8()?;
+            let visibility = fields.u8()?;
+            sectors.push(Sector {
+                wall_ptr: wall_ptr.min(i16::MAX as u16) as i16,
+                wall_count: wall_count.min(i16::MAX as u16) as i16,
+                ceiling_z,
+                floor_z,
+                ceiling_stat,
+                floor_stat,
+                ceiling_picnum,
+                ceiling_heinum,
+                ceiling_shade,
+                ceiling_pal,
+                ceiling_x_panning,
+                ceiling_y_panning,
+                floor_picnum,
+                floor_heinum,
+                floor_shade,
+                floor_pal,
+                floor_x_panning,
+                floor_y_panning,
+                visibility,
+                filler: 0,
+                lotag: fields.i16()?,
+                hitag: fields.i16()?,
+                extra: fields.i16()?,
+            });
+        }
+
+        fields.start("Wall count");
+        let wall_count = fields.u16()? as usize;
+        let mut walls = Vec::with_capacity(wall_count);
+        for i in 0..wall_count {
+            fields.start(format!("Wall {}", i));
+            let x = fields.i32()?;
+            let y = fields.i32()?;
+            let point2 = fields.i16()?;
+            let next_sector = fields.i16()?;
+            let next_wall = fields.i16()?;
+            let picnum = fields.i16()?;
+            let over_picnum = fields.i16()?;
+            let shade = fields.i8()?;
+            let pal = fields.u8()?;
+            walls.push(Wall {
+                x,
+                y,
+                point2,
+                next_wall,
+                next_sector,
+                cstat: fields.u16()?,
+                picnum,
+                over_picnum,
+                shade,
+                pal,
+                x_repeat: fields.u8()?,
+                y_repeat: fields.u8()?,
+                x_panning: fields.u8()?,
+                y_panning: fields.u8()?,
+                lotag: fields.i16()?,
+                hitag: fields.i16()?,
+                extra: fields.i16()?,
+            });
+        }
+
+        fields.start("Sprite count");
+        let sprite_count = fields.u16()? as usize;
+        let mut sprites = Vec::with_capacity(sprite_count);
+        for i in 0..sprite_count {
+            fields.start(format!("Sprite {}", i));
+            let x = fields.i32()?;
+            let y = fields.i32()?;
+            let z = fields.i32()?;
+            let cstat = fields.u16()?;
+            let shade = fields.i8()?;
+            let pal = fields.u8()?;
+            let clip_dist = fields.u8()?;
+            let x_repeat = fields.u8()?;
+            let y_repeat = fields.u8()?;
+            let x_offset = fields.i8()?;
+            let y_offset = fields.i8()?;
+            let picnum = fields.i16()?;
+            let angle = fields.i16()?;
+            let x_vel = fields.i16()?;
+            let y_vel = fields.i16()?;
+            let z_vel = fields.i16()?;
+            let owner = fields.i16()?;
+            sprites.push(Sprite {
+                x,
+                y,
+                z,
+                cstat,
+                picnum,
+                shade,
+                pal,
+                clip_dist,
+                filler: 0,
+                x_repeat,
+                y_repeat,
+                x_offset,
+                y_offset,
+                sector: fields.i16()?,
+                stat: fields.i16()?,
+                angle,
+                owner,
+                x_vel,
+                y_vel,
+                z_vel,
+                lotag: fields.i16()?,
+                hitag: fields.i16()?,
+                extra: fields.i16()?,
+            });
+        }
+
+        for (name, count, max) in [
+            ("sectors", sectors.len(), MAX_SECTORS),
+            ("walls", walls.len(), MAX_WALLS),
+            ("sprites", sprites.len(), MAX_SPRITES),
+        ] {
+            if count > max {
+                warnings.push(format!(
+                    "The map has {} {}, Duke Nukem 3D supports at most {}.",
+                    count, name, max
+                ));
+            }
+        }
+
+        let map = Self {
+            player_start,
+            sectors,
+            walls,
+            sprites,
+        };
+        Ok((map, warnings))
+    }
+}
+
+/// Scales a version 6 slope to version 7 units, as Build does: slopes of
+/// surfaces without the sloped flag (2) are dropped. Slopes that do not fit
+/// are returned clamped as the error.
+fn convert_heinum(heinum: i16, stat: u16) -> Result<i16, i16> {
+    if stat & 2 == 0 {
+        return Ok(0);
+    }
+    let scaled = heinum as i32 * 32;
+    let clamped = scaled.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+    if clamped as i32 == scaled {
+        Ok(clamped)
+    } else {
+        Err(clamped)
+    }
+}
+
+#[test]
+fn should_convert_v6_map() {
+    // One sector with one wall and one sprite, field by field in version 6 order.
+    let mut data = Vec::new();
+    for value in [6i32, 100, 200, 300] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(&[0, 2, 0, 0, 1, 0]); // angle 512, sector 0, 1 sector
+    data.extend_from_slice(&[0, 0, 1, 0]); // wallptr 0, wallnum 1
+    data.extend_from_slice(&[183u16.to_le_bytes(), 898u16.to_le_bytes()].concat());
+    // Heinums, the ceiling is not sloped and the floor is.
+    data.extend_from_slice(&[100i16.to_le_bytes(), (-1000i16).to_le_bytes()].concat());
+    data.extend_from_slice(&[(-4096i32).to_le_bytes(), 4096i32.to_le_bytes()].concat());
+    data.extend_from_slice(&[0xF8, 0, 1, 2, 3, 4, 0x81, 0x02, 5, 6, 7]);
+    data.extend_from_slice(&[1, 0, 2, 0, 3, 0]); // lotag, hitag, extra
+    data.extend_from_slice(&[1, 0]); // 1 wall
+    data.extend_from_slice(&[16i32.to_le_bytes(), 32i32.to_le_bytes()].concat());
+    data.extend_from_slice(&[0, 0, 0xFF, 0xFF, 0xFF, 0xFF]); // point2, nextsector, nextwall
+    data.extend_from_slice(&[0x49, 0x04, 0, 0, 0xFF, 9, 0x01, 0x80, 8, 8, 0, 0]);
+    data.extend_from_slice(&[0; 6]);
+    data.extend_from_slice(&[1, 0]); // 1 sprite
+    data.extend_from_slice(&[0; 12]);
+    data.extend_from_slice(&[1, 0, 0, 0, 32, 40, 40, 0xFF, 1]);
+    data.extend_from_slice(&[0x7D, 0x05, 0, 4, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF]);
+    data.extend_from_slice(&[0, 0, 0, 0, 0, 0, 7, 0, 0, 0]);
+
+    assert_eq!(read_version(&data), Ok(6));
+    assert!(Map::parse(&data).is_err());
+    let (map, warnings) = Map::parse_v6(&data).unwrap();
+    assert!(warnings.is_empty());
+
+    assert_eq!((map.player_start.angle, map.player_start.z), (512, 300));
+    let sector = &map.sectors[0];
+    assert_eq!((sector.ceiling_picnum, sector.floor_picnum), (183, 898));
+    assert_eq!((sector.ceiling_z, sector.floor_z), (-4096, 4096));
+    assert_eq!((sector.ceiling_shade, sector.floor_shade), (-8, 0));
+    assert_eq!((sector.ceiling_stat, sector.floor_stat), (0x81, 0x02));
+    assert_eq!((sector.ceiling_heinum, sector.floor_heinum), (0, -32000));
+    assert_eq!((sector.floor_pal, sector.visibility), (6, 7));
+    assert_eq!((sector.lotag, sector.hitag, sector.extra), (1, 2, 3));
+    let wall = &map.walls[0];
+    assert_eq!(
+        (wall.x, wall.y, wall.next_sector, wall.next_wall),
+        (16, 32, -1, -1)
+    );
+    assert_eq!(
+        (wall.picnum, wall.shade, wall.pal, wall.cstat),
+        (1097, -1, 9, 0x8001)
+    );
+    let sprite = &map.sprites[0];
+    assert_eq!(
+        (sprite.cstat, sprite.clip_dist, sprite.x_offset),
+        (1, 32, -1)
+    );
+    assert_eq!(
+        (sprite.picnum, sprite.angle, sprite.owner),
+        (1405, 1024, -1)
+    );
+    assert_eq!((sprite.sector, sprite.hitag), (0, 7));
+
+    assert_eq!(Map::parse(&map.to_bytes()).unwrap(), map);
+
+    // Slopes beyond the version 7 range are clamped with a warning.
+    data[32..34].copy_from_slice(&1100i16.to_le_bytes());
+    let (map, warnings) = Map::parse_v6(&data).unwrap();
+    assert_eq!(map.sectors[0].floor_heinum, i16::MAX);
+    assert_eq!(
+        warnings,
+        ["Sector 0: the floor slope 1100 is too steep, it was clamped."]
+    );
+}
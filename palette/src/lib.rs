@@ -0,0 +1,121 @@
+//! Palettes of Build engine games, as stored in PALETTE.DAT.
+
+// Malformed files are reported as errors and never panic, as the
+// processes that embed these crates cannot recover from a panic.
+// clippy::arithmetic_side_effects is not denied, as it flags every operator
+// including those on bounded values. Arithmetic on values read from files
+// is checked or saturating instead, which tests/error_injection.rs covers.
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::expect_used,
+        clippy::indexing_slicing,
+        clippy::panic,
+        clippy::unwrap_used
+    )
+)]
+
+mod lookup;
+mod remap;
+mod translucency;
+
+pub use lookup::{LookupDat, BASE_PALETTE_NAMES};
+pub use remap::RemapTable;
+pub use translucency::{
+    translucency_table_offset, write_translucency_table, TRANSLUCENCY_TABLE_BYTES,
+};
+
+/// Number of colors in a palette.
+pub const COLOR_COUNT: usize = 256;
+/// Index that the engine draws as transparent in sprites and masked walls.
+pub const TRANSPARENT_INDEX: u8 = 255;
+
+/// A 256-color palette with 8-bit RGB components.
+///
+/// ```
+/// let mut palette_dat = vec![0u8; palette::Palette::DAT_BYTES];
+/// palette_dat[3..6].copy_from_slice(&[63, 32, 0]);
+///
+/// let palette = palette::Palette::from_palette_dat(&palette_dat).unwrap();
+/// assert_eq!(palette.color(1), [255, 130, 0]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    colors: [[u8; 3]; COLOR_COUNT],
+}
+impl Palette {
+    /// Size of the palette at the start of PALETTE.DAT: 256 RGB colors.
+    pub const DAT_BYTES: usize = COLOR_COUNT * 3;
+
+    /// Reads the palette at the start of PALETTE.DAT, whose components are
+    /// 6-bit values (0 to 63). The shade and translucency tables following
+    /// it are not needed for the palette itself.
+    pub fn from_palette_dat(bytes: &[u8]) -> Result<Self, String> {
+        let Some(colors_bytes) = bytes.get(..Self::DAT_BYTES) else {
+            return Err(format!(
+                "Palette is {} bytes long, but at least {} bytes are needed.",
+                bytes.len(),
+                Self::DAT_BYTES
+            ));
+        };
+        if let Some(component) = colors_bytes.iter().find(|c| **c > 63) {
+            return Err(format!(
+                "Palette component {} is larger than the maximum of 63.",
+                component
+            ));
+        }
+
+        let mut colors = [[0u8; 3]; COLOR_COUNT];
+        for (color, rgb) in colors.iter_mut().zip(colors_bytes.as_chunks::<3>().0) {
+            // Scale to 8 bits so that 63 becomes 255.
+            *color = rgb.map(|c| (c << 2) | (c >> 4));
+        }
+        Ok(Self { colors })
+    }
+    pub fn from_colors(colors: [[u8; 3]; COLOR_COUNT]) -> Self {
+        Self { colors }
+    }
+    pub fn color(&self, index: u8) -> [u8; 3] {
+        // Every u8 is a valid index, so the default is never used.
+        self.colors.get(index as usize).copied().unwrap_or_default()
+    }
+    pub fn colors(&self) -> &[[u8; 3]; COLOR_COUNT] {
+        &self.colors
+    }
+    /// Converts palette indices to RGBA pixels. [`TRANSPARENT_INDEX`] becomes
+    /// fully transparent when `transparent` is set.
+    pub fn to_rgba(&self, indices: &[u8], transparent: bool) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(indices.len() * 4);
+        for index in indices {
+            let [r, g, b] = self.color(*index);
+            let alpha = if transparent && *index == TRANSPARENT_INDEX {
+                0
+            } else {
+                255
+            };
+            rgba.extend_from_slice(&[r, g, b, alpha]);
+        }
+        rgba
+    }
+}
+
+#[test]
+fn should_read_palette_dat() {
+    let mut palette_dat = (0..Palette::DAT_BYTES)
+        .map(|i| (i % 64) as u8)
+        .collect::<Vec<_>>();
+    // Shade tables follow the palette in PALETTE.DAT.
+    palette_dat.extend_from_slice(&[0xFF; 32]);
+
+    let palette = Palette::from_palette_dat(&palette_dat).unwrap();
+    assert_eq!(palette.color(0), [0, 4, 8]);
+    assert_eq!(palette.color(21), [255, 0, 4]);
+    assert_eq!(
+        palette.to_rgba(&[0, TRANSPARENT_INDEX], true),
+        [0, 4, 8, 255, 247, 251, 255, 0]
+    );
+
+    assert!(Palette::from_palette_dat(&palette_dat[..100]).is_err());
+    palette_dat[0] = 64;
+    assert!(Palette::from_palette_dat(&palette_dat).is_err());
+}
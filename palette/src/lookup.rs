@@ -0,0 +1,135 @@
+//! LOOKUP.DAT, which holds the palette lookups (palswaps) of sprites and
+//! the alternate base palettes that tint the whole screen.
+//!
+//! The file starts with the number of lookups as a byte, followed by each
+//! lookup as its palette number and 256 indices. Duke Nukem 3D then stores
+//! five base palettes in the format of PALETTE.DAT.
+
+use crate::{Palette, COLOR_COUNT};
+
+/// The names of the base palettes of LOOKUP.DAT, in the order of the file.
+/// The game also shows the slime palette while night vision is on.
+pub const BASE_PALETTE_NAMES: [&str; 5] = ["water", "slime", "title", "3drealms", "ending"];
+
+/// The contents of LOOKUP.DAT.
+///
+/// ```
+/// let mut lookup_dat = vec![1, 21];
+/// lookup_dat.extend((0..=255).rev());
+/// for component in [0, 63, 63, 0] {
+///     lookup_dat.extend(std::iter::repeat_n(component, palette::Palette::DAT_BYTES / 2));
+/// }
+///
+/// let lookup = palette::LookupDat::parse(&lookup_dat).unwrap();
+/// assert_eq!(lookup.lookup(21).unwrap()[0], 255);
+/// let water = lookup.base_palette("water").unwrap();
+/// assert_eq!(water.color(0), [0, 0, 0]);
+/// assert_eq!(lookup.base_palettes().len(), 2);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupDat {
+    lookups: Vec<(u8, [u8; COLOR_COUNT])>,
+    base_palettes: Vec<Palette>,
+}
+impl LookupDat {
+    /// Reads the lookups and the base palettes that follow them. Files of
+    /// other games may have fewer base palettes than Duke Nukem 3D, or none.
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let Some((&lookup_count, mut rest)) = bytes.split_first() else {
+            return Err("LOOKUP.DAT is empty.".to_string());
+        };
+        let mut lookups = Vec::with_capacity(lookup_count as usize);
+        for i in 0..lookup_count {
+            let Some((&number, after_number)) = rest.split_first() else {
+                return Err(format!("Lookup {} of LOOKUP.DAT is truncated.", i));
+            };
+            let Some((table, after_table)) = after_number.split_first_chunk::<COLOR_COUNT>() else {
+                return Err(format!("Lookup {} of LOOKUP.DAT is truncated.", i));
+            };
+            lookups.push((number, *table));
+            rest = after_table;
+        }
+
+        let (palettes, _) = rest.as_chunks::<{ Palette::DAT_BYTES }>();
+        let base_palettes = palettes
+            .iter()
+            .take(BASE_PALETTE_NAMES.len())
+            .enumerate()
+            .map(|(i, bytes)| {
+                Palette::from_palette_dat(bytes)
+                    .map_err(|e| format!("Base palette {} of LOOKUP.DAT: {}", i, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            lookups,
+            base_palettes,
+        })
+    }
+    /// The lookup of a palette number, e.g. 21 for the red of some enemies.
+    pub fn lookup(&self, number: u8) -> Option<&[u8; COLOR_COUNT]> {
+        self.lookups
+            .iter()
+            .find(|(n, _)| *n == number)
+            .map(|(_, table)| table)
+    }
+    pub fn lookups(&self) -> &[(u8, [u8; COLOR_COUNT])] {
+        &self.lookups
+    }
+    /// The base palettes in the order of the file, see [`BASE_PALETTE_NAMES`].
+    pub fn base_palettes(&self) -> &[Palette] {
+        &self.base_palettes
+    }
+    /// A base palette by its name in [`BASE_PALETTE_NAMES`], `nightvision`
+    /// for the slime palette, or its index.
+    pub fn base_palette(&self, name: &str) -> Result<&Palette, String> {
+        let index = BASE_PALETTE_NAMES
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(name))
+            .or_else(|| name.eq_ignore_ascii_case("nightvision").then_some(1))
+            .or_else(|| name.parse().ok());
+        index
+            .and_then(|i| self.base_palettes.get(i))
+            .ok_or_else(|| {
+                format!(
+                    "Unknown base palette: {} (should be one of {} or an index below {})",
+                    name,
+                    BASE_PALETTE_NAMES
+                        .get(..self.base_palettes.len())
+                        .unwrap_or_default()
+                        .join(", "),
+                    self.base_palettes.len()
+                )
+            })
+    }
+}
+
+#[test]
+fn should_read_lookup_dat() {
+    let mut lookup_dat = vec![2];
+    for number in [1, 21] {
+        lookup_dat.push(number);
+        lookup_dat.extend(std::iter::repeat_n(number, COLOR_COUNT));
+    }
+    for component in 0..BASE_PALETTE_NAMES.len() as u8 {
+        lookup_dat.extend(std::iter::repeat_n(component * 10, Palette::DAT_BYTES));
+    }
+
+    let lookup = LookupDat::parse(&lookup_dat).unwrap();
+    assert_eq!(lookup.lookups().len(), 2);
+    assert_eq!(lookup.lookup(21), Some(&[21; COLOR_COUNT]));
+    assert_eq!(lookup.lookup(2), None);
+    assert_eq!(lookup.base_palettes().len(), 5);
+    assert_eq!(lookup.base_palette("slime").unwrap().color(0), [40; 3]);
+    assert_eq!(lookup.base_palette("4").unwrap().color(255), [162; 3]);
+    assert!(lookup.base_palette("5").is_err());
+    assert_eq!(
+        lookup.base_palette("NightVision"),
+        lookup.base_palette("slime")
+    );
+    assert!(lookup.base_palette("night").is_err());
+
+    assert!(LookupDat::parse(&lookup_dat[..300]).is_err());
+    assert!(LookupDat::parse(&[]).is_err());
+    lookup_dat[1 + 2 * 257] = 64;
+    assert!(LookupDat::parse(&lookup_dat).is_err());
+}
@@ -0,0 +1,82 @@
+//! Comparing palettes and remapping colors from one palette to another, as
+//! needed to port tiles between games with different palettes.
+
+use crate::{Palette, COLOR_COUNT, TRANSPARENT_INDEX};
+
+/// A table that maps each index of one palette to an index of another.
+pub type RemapTable = [u8; COLOR_COUNT];
+
+impl Palette {
+    /// The indices whose colors differ between the palettes.
+    pub fn changed_indices(&self, other: &Palette) -> Vec<u8> {
+        (0..=u8::MAX)
+            .filter(|i| self.color(*i) != other.color(*i))
+            .collect()
+    }
+    /// The index of the color closest to `color` by squared RGB distance,
+    /// preferring the lowest index among equally close colors. The
+    /// transparent index is never chosen, as it does not draw its color.
+    pub fn nearest_index(&self, color: [u8; 3]) -> u8 {
+        let distance = |other: [u8; 3]| {
+            color
+                .iter()
+                .zip(other)
+                .map(|(a, b)| (*a as i32 - b as i32).pow(2))
+                .sum::<i32>()
+        };
+        (0..TRANSPARENT_INDEX)
+            .min_by_key(|i| distance(self.color(*i)))
+            .unwrap_or_default()
+    }
+    /// The table that maps each index of this palette to the nearest color
+    /// of `target`. Indices whose color is the same in `target` and the
+    /// transparent index are kept.
+    ///
+    /// ```
+    /// let mut colors = [[0; 3]; 256];
+    /// colors[1] = [250, 0, 0];
+    /// let source = palette::Palette::from_colors(colors);
+    /// colors.swap(1, 7);
+    /// let target = palette::Palette::from_colors(colors);
+    ///
+    /// let table = source.remap_table(&target);
+    /// assert_eq!((table[0], table[1], table[255]), (0, 7, 255));
+    /// ```
+    pub fn remap_table(&self, target: &Palette) -> RemapTable {
+        let mut table = [0; COLOR_COUNT];
+        for (index, entry) in (0..=u8::MAX).zip(table.iter_mut()) {
+            *entry = if index == TRANSPARENT_INDEX || self.color(index) == target.color(index) {
+                index
+            } else {
+                target.nearest_index(self.color(index))
+            };
+        }
+        table
+    }
+}
+
+#[test]
+fn should_remap_to_nearest_colors() {
+    let mut colors = [[0; 3]; COLOR_COUNT];
+    for (i, color) in colors.iter_mut().enumerate() {
+        *color = [i as u8, 0, 0];
+    }
+    let source = Palette::from_colors(colors);
+    // Only every fourth red remains; the others become the closest one.
+    for (i, color) in colors.iter_mut().enumerate() {
+        *color = [(i / 4 * 4) as u8, 0, 0];
+    }
+    colors[254] = [0, 0, 255];
+    colors[8] = [12, 0, 0];
+    let target = Palette::from_colors(colors);
+
+    assert_eq!(source.changed_indices(&source), []);
+    assert_eq!(source.changed_indices(&target).len(), 256 / 4 * 3 + 1);
+    let table = source.remap_table(&target);
+    assert_eq!(table[0..6], [0, 0, 0, 4, 4, 4]);
+    // 12 is kept, although 8 has the same color in the target.
+    assert_eq!((table[8], table[12]), (9, 12));
+    // 253 has the same color as 252, and 255 is never a candidate.
+    assert_eq!(table[254], 252);
+    assert_eq!(table[TRANSPARENT_INDEX as usize], TRANSPARENT_INDEX);
+}
@@ -0,0 +1,131 @@
+//! The translucency table of PALETTE.DAT, which the engine looks up to draw
+//! a translucent pixel over another.
+//!
+//! PALETTE.DAT holds the palette, the number of shade tables as a 16-bit
+//! integer, the shade tables of 256 bytes each and then the translucency
+//! table: for each pair of a foreground and a background index, the index
+//! of the color closest to their blend at `foreground * 256 + background`.
+
+use crate::{Palette, COLOR_COUNT};
+use std::collections::HashMap;
+
+/// Size of the translucency table: one index per pair of indices.
+pub const TRANSLUCENCY_TABLE_BYTES: usize = COLOR_COUNT * COLOR_COUNT;
+
+impl Palette {
+    /// Builds the translucency table for drawing the foreground with
+    /// `ratio` percent of opacity over the background. The engine also
+    /// looks the table up with the indices swapped, so e.g. 33 gives both
+    /// of its usual blends of a third and two thirds.
+    ///
+    /// ```
+    /// let mut colors = [[0; 3]; 256];
+    /// colors[1] = [200, 200, 200];
+    /// colors[2] = [100, 100, 100];
+    /// let palette = palette::Palette::from_colors(colors);
+    ///
+    /// let table = palette.translucency_table(50).unwrap();
+    /// assert_eq!(table[1 * 256], 2);
+    /// assert_eq!(table[1 * 256 + 1], 1);
+    /// ```
+    pub fn translucency_table(&self, ratio: u8) -> Result<Vec<u8>, String> {
+        if ratio > 100 {
+            return Err(format!(
+                "Invalid blend ratio: {} (should be 0 to 100)",
+                ratio
+            ));
+        }
+        let (foreground_weight, background_weight) = (ratio as u32, 100 - ratio as u32);
+        // Many pairs blend to the same color, whose search is done once.
+        let mut nearest = HashMap::new();
+        let mut table = Vec::with_capacity(TRANSLUCENCY_TABLE_BYTES);
+        for foreground in (0..=u8::MAX).map(|i| self.color(i)) {
+            for background in (0..=u8::MAX).map(|i| self.color(i)) {
+                let mut blend = [0; 3];
+                for ((c, f), b) in blend.iter_mut().zip(foreground).zip(background) {
+                    let sum = f as u32 * foreground_weight + b as u32 * background_weight;
+                    *c = ((sum + 50) / 100) as u8;
+                }
+                table.push(
+                    *nearest
+                        .entry(blend)
+                        .or_insert_with(|| self.nearest_index(blend)),
+                );
+            }
+        }
+        Ok(table)
+    }
+}
+
+/// The offset of the translucency table in PALETTE.DAT, after the shade tables.
+pub fn translucency_table_offset(palette_dat: &[u8]) -> Result<usize, String> {
+    let Some(&[low, high]) = palette_dat
+        .get(Palette::DAT_BYTES..)
+        .and_then(|d| d.first_chunk::<2>())
+    else {
+        return Err(format!(
+            "PALETTE.DAT is {} bytes long, but the number of shade tables is at offset {}.",
+            palette_dat.len(),
+            Palette::DAT_BYTES
+        ));
+    };
+    let shade_count = u16::from_le_bytes([low, high]) as usize;
+    let offset = Palette::DAT_BYTES + 2 + shade_count * COLOR_COUNT;
+    if palette_dat.len() < offset {
+        return Err(format!(
+            "PALETTE.DAT is {} bytes long, but its {} shade tables end at offset {}.",
+            palette_dat.len(),
+            shade_count,
+            offset
+        ));
+    }
+    Ok(offset)
+}
+
+/// Replaces the translucency table of PALETTE.DAT, or adds it if the file
+/// ends after the shade tables. Anything after the table is kept.
+pub fn write_translucency_table(palette_dat: &mut Vec<u8>, table: &[u8]) -> Result<(), String> {
+    if table.len() != TRANSLUCENCY_TABLE_BYTES {
+        return Err(format!(
+            "A translucency table is {} bytes, got {}.",
+            TRANSLUCENCY_TABLE_BYTES,
+            table.len()
+        ));
+    }
+    let offset = translucency_table_offset(palette_dat)?;
+    let end = (offset + TRANSLUCENCY_TABLE_BYTES).min(palette_dat.len());
+    palette_dat.splice(offset..end, table.iter().copied());
+    Ok(())
+}
+
+#[test]
+fn should_write_translucency_table() {
+    let mut colors = [[0; 3]; COLOR_COUNT];
+    for (i, color) in colors.iter_mut().enumerate() {
+        *color = [i as u8; 3];
+    }
+    let palette = Palette::from_colors(colors);
+    let table = palette.translucency_table(25).unwrap();
+    assert_eq!(table.len(), TRANSLUCENCY_TABLE_BYTES);
+    // A quarter of 200 and three quarters of 100 is 125.
+    assert_eq!(table[200 * 256 + 100], 125);
+    assert_eq!(table[100 * 256 + 200], 175);
+    assert_eq!(palette.translucency_table(100).unwrap()[7 * 256 + 9], 7);
+    assert!(palette.translucency_table(101).is_err());
+
+    // The palette, two shade tables and the end of the file.
+    let mut palette_dat = vec![0; Palette::DAT_BYTES];
+    palette_dat.extend_from_slice(&2u16.to_le_bytes());
+    palette_dat.extend_from_slice(&[1; 2 * COLOR_COUNT]);
+    let offset = Palette::DAT_BYTES + 2 + 2 * COLOR_COUNT;
+    assert_eq!(translucency_table_offset(&palette_dat), Ok(offset));
+    write_translucency_table(&mut palette_dat, &table).unwrap();
+    assert_eq!(palette_dat.len(), offset + TRANSLUCENCY_TABLE_BYTES);
+    palette_dat.push(0xAA);
+    write_translucency_table(&mut palette_dat, &vec![3; TRANSLUCENCY_TABLE_BYTES]).unwrap();
+    assert_eq!(palette_dat[offset..offset + 2], [3, 3]);
+    assert_eq!(palette_dat.last(), Some(&0xAA));
+
+    assert!(write_translucency_table(&mut palette_dat, &table[1..]).is_err());
+    assert!(translucency_table_offset(&palette_dat[..offset - 1]).is_err());
+}
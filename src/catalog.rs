@@ -0,0 +1,257 @@
+//! Catalog records of archives for sites that index mods, printed by
+//! `catalog` as one JSON object:
+//!
+//! ```json
+//! {
+//!   "schema": "duke3d-file-utility/catalog/1",
+//!   "tool": "duke3d-file-util 0.1.0",
+//!   "file": {"name": "MOD.GRP", "size": 52311, "sha256": "9b1f..."},
+//!   "format": "GRP",
+//!   "entries": [{"name": "GAME.CON", "size": 151, "sha256": "6f1e..."}],
+//!   "maps": [{"name": "E1L1.MAP", "episode": 1, "level": 1, "title": "HOLLYWOOD HOLOCAUST"}],
+//!   "stats": {"entries": 1, ...},
+//!   "previews": [{"tile": 3281, "file": "TILE3281.png", "width": 128, "height": 80}]
+//! }
+//! ```
+//!
+//! Entries are named as extracted by `grp-extract-all`, so that the record
+//! is also a manifest for `grp-verify`. The episodes, levels and titles of
+//! maps come from `definelevelname` in the .con entries, counted from 1,
+//! and are `null` for maps that no script names. `stats` is the output of
+//! `stats --format json`. Previews are thumbnails written next to the
+//! record, and the list is empty unless they were asked for. `tool` is the
+//! name and version of the program that wrote the record.
+
+use crate::{json, stats::GameStats, verify};
+use grp::{fs_name, GrpFileReader};
+use std::io::{Read, Seek};
+
+/// The version of the layout of catalog records, which changes only when
+/// fields are removed or change their meaning.
+pub const SCHEMA: &str = "duke3d-file-utility/catalog/1";
+/// The largest width and height of preview thumbnails.
+pub const THUMBNAIL_SIZE: u32 = 128;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogEntry {
+    pub name: String,
+    pub size: u64,
+    pub sha256: [u8; 32],
+}
+
+/// A map of the archive and how the scripts of the archive name it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogMap {
+    pub name: String,
+    pub episode: Option<u32>,
+    pub level: Option<u32>,
+    pub title: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preview {
+    pub tile: u32,
+    pub file_name: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Catalog {
+    pub file_name: String,
+    pub size: u64,
+    pub sha256: [u8; 32],
+    pub entries: Vec<CatalogEntry>,
+    pub maps: Vec<CatalogMap>,
+    pub stats: GameStats,
+    pub previews: Vec<Preview>,
+}
+impl Catalog {
+    /// Hashes every entry and reads the scripts for the names of the maps.
+    /// The name, size and hash of the archive file are given by the caller.
+    pub fn collect<R: Read + Seek>(
+        file_name: String,
+        size: u64,
+        sha256: [u8; 32],
+        grp_reader: &mut GrpFileReader<R>,
+    ) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        let mut map_names = Vec::new();
+        let mut level_names = Vec::new();
+        let game = crate::game::active();
+        for entry in grp_reader.get_file_entries()? {
+            let name = entry.name().to_string();
+            if game.is_map(&name) {
+                map_names.push(name.clone());
+            } else if game.is_con(&name) {
+                level_names.extend(con::find_level_names(&grp_reader.read_file(&entry)?));
+            }
+            entries.push(CatalogEntry {
+                name: fs_name::to_fs_name(&entry.name()),
+                size: entry.size() as u64,
+                sha256: verify::hash_entry(grp_reader, &entry)?,
+            });
+        }
+
+        let maps = map_names
+            .into_iter()
+            .map(|name| {
+                // Scripts may name maps in lowercase or with a directory.
+                let level_name = level_names.iter().find(|l| {
+                    let file_name = l.file_name.rsplit(['/', '\\']).next().unwrap_or_default();
+                    file_name.eq_ignore_ascii_case(&name)
+                });
+                CatalogMap {
+                    episode: level_name.map(|l| l.episode + 1),
+                    level: level_name.map(|l| l.level + 1),
+                    title: level_name.map(|l| l.title.clone()),
+                    name,
+                }
+            })
+            .collect();
+        Ok(Self {
+            file_name,
+            size,
+            sha256,
+            entries,
+            maps,
+            stats: GameStats::collect(grp_reader)?,
+            previews: Vec::new(),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let optional = |value: Option<u32>| value.map_or("null".to_string(), |v| v.to_string());
+        let entries = self
+            .entries
+            .iter()
+            .map(|e| {
+                format!(
+                    "{{\"name\":{},\"size\":{},\"sha256\":\"{}\"}}",
+                    json::string(&e.name),
+                    e.size,
+                    verify::to_hex(&e.sha256)
+                )
+            })
+            .collect::<Vec<_>>();
+        let maps = self
+            .maps
+            .iter()
+            .map(|m| {
+                format!(
+                    "{{\"name\":{},\"episode\":{},\"level\":{},\"title\":{}}}",
+                    json::string(&m.name),
+                    optional(m.episode),
+                    optional(m.level),
+                    m.title.as_deref().map_or("null".to_string(), json::string)
+                )
+            })
+            .collect::<Vec<_>>();
+        let previews = self
+            .previews
+            .iter()
+            .map(|p| {
+                format!(
+                    "{{\"tile\":{},\"file\":{},\"width\":{},\"height\":{}}}",
+                    p.tile,
+                    json::string(&p.file_name),
+                    p.width,
+                    p.height
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            concat!(
+                "{{\"schema\":{},\"tool\":{},\"file\":{{\"name\":{},\"size\":{},\"sha256\":\"{}\"}},",
+                "\"format\":\"GRP\",\"entries\":[{}],\"maps\":[{}],\"stats\":{},\"previews\":[{}]}}"
+            ),
+            json::string(SCHEMA),
+            json::string(duke3d_file_utility::TOOL),
+            json::string(&self.file_name),
+            self.size,
+            verify::to_hex(&self.sha256),
+            entries.join(","),
+            maps.join(","),
+            self.stats.to_json(),
+            previews.join(",")
+        )
+    }
+}
+
+/// Scales an image down to fit in `max_size` by `max_size` pixels, picking
+/// the nearest pixel. Smaller images are returned as they are.
+pub fn thumbnail(width: u32, height: u32, rgba: &[u8], max_size: u32) -> (u32, u32, Vec<u8>) {
+    if width <= max_size && height <= max_size {
+        return (width, height, rgba.to_vec());
+    }
+    let scale = |length: u32| (length as u64 * max_size as u64 / width.max(height) as u64).max(1);
+    let (new_width, new_height) = (scale(width) as u32, scale(height) as u32);
+    let mut thumbnail = Vec::with_capacity(new_width as usize * new_height as usize * 4);
+    for y in 0..new_height {
+        let source_y = y as u64 * height as u64 / new_height as u64;
+        for x in 0..new_width {
+            let source_x = x as u64 * width as u64 / new_width as u64;
+            let at = (source_y * width as u64 + source_x) as usize * 4;
+            thumbnail.extend_from_slice(rgba.get(at..at + 4).unwrap_or(&[0; 4]));
+        }
+    }
+    (new_width, new_height, thumbnail)
+}
+
+#[test]
+fn should_catalog_archive() {
+    let game_con = b"definelevelname 0 0 e1l1.map 02:30 01:45 HOLLYWOOD HOLOCAUST\r\n";
+    let grp = grp::testing::GrpFixture::new()
+        .entry("GAME.CON", game_con)
+        .entry("E1L1.MAP", &[7, 0, 0, 0])
+        .entry("USER.MAP", &[7, 0, 0, 0])
+        .build();
+    let mut grp_reader = GrpFileReader::new(std::io::Cursor::new(grp)).unwrap();
+    let mut catalog =
+        Catalog::collect("MOD.GRP".to_string(), 1234, [0; 32], &mut grp_reader).unwrap();
+    assert_eq!(catalog.entries[0].sha256, grp::sha256(game_con));
+    assert_eq!(
+        catalog.maps,
+        [
+            CatalogMap {
+                name: "E1L1.MAP".to_string(),
+                episode: Some(1),
+                level: Some(1),
+                title: Some("HOLLYWOOD HOLOCAUST".to_string()),
+            },
+            CatalogMap {
+                name: "USER.MAP".to_string(),
+                episode: None,
+                level: None,
+                title: None,
+            },
+        ]
+    );
+    catalog.previews.push(Preview {
+        tile: 0,
+        file_name: "TILE0.png".to_string(),
+        width: 8,
+        height: 8,
+    });
+
+    let json = catalog.to_json();
+    let record = json::parse(&json).unwrap();
+    assert_eq!(record.get("schema").and_then(|s| s.as_str()), Some(SCHEMA));
+    assert_eq!(
+        record.get("tool").and_then(|t| t.as_str()),
+        Some(duke3d_file_utility::TOOL)
+    );
+    let maps = record.get("maps").and_then(|m| m.as_array()).unwrap();
+    assert_eq!(maps[1].get("title"), Some(&json::JsonValue::Null));
+    // The record can be used as a manifest for grp-verify.
+    let manifest = verify::parse_manifest(&json).unwrap();
+    assert_eq!(manifest.len(), 3);
+    assert_eq!(manifest[1].size, Some(4));
+
+    let rgba = (0..16u8 * 4).collect::<Vec<_>>();
+    let (width, height, scaled) = thumbnail(4, 4, &rgba, 2);
+    assert_eq!((width, height), (2, 2));
+    assert_eq!(scaled[4..8], rgba[8..12]);
+    assert_eq!(thumbnail(4, 1, &rgba[..16], 2).1, 1);
+    assert_eq!(thumbnail(2, 2, &rgba[..16], 2).2, rgba[..16]);
+}
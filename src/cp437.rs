@@ -0,0 +1,59 @@
+//! Conversion between code page 437, which DOS-era mod scripts are written
+//! in, and UTF-8.
+//!
+//! Bytes below 0x80 are treated as ASCII (including control characters such
+//! as tabs and line breaks), so text keeps its layout.
+
+/// Unicode characters for the bytes 0x80..=0xFF.
+const HIGH_CHARS: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+
+pub fn decode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| match b {
+            0..=0x7F => *b as char,
+            _ => HIGH_CHARS[(*b - 0x80) as usize],
+        })
+        .collect()
+}
+
+/// Encodes text back to code page 437, failing on the first character that
+/// has no representation in it.
+pub fn encode(text: &str) -> Result<Vec<u8>, String> {
+    text.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if c.is_ascii() {
+                return Ok(c as u8);
+            }
+            HIGH_CHARS
+                .iter()
+                .position(|h| *h == c)
+                .map(|p| 0x80 + p as u8)
+                .ok_or_else(|| {
+                    format!(
+                        "Character {:?} (U+{:04X}) at position {} cannot be stored in code page 437.",
+                        c, c as u32, i
+                    )
+                })
+        })
+        .collect()
+}
+
+#[test]
+fn should_round_trip_all_bytes() {
+    let bytes = (0..=255u8).collect::<Vec<_>>();
+    let text = decode(&bytes);
+    assert!(text.contains('╔') && text.contains('é'));
+    assert_eq!(encode(&text).unwrap(), bytes);
+    assert!(encode("€").is_err());
+}
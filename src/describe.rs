@@ -0,0 +1,223 @@
+//! Field-by-field dumps of the headers of GRP, ART and MAP files, for
+//! finding out where a malformed file goes wrong.
+//!
+//! Each layout is described by reading its fields in order, much like the
+//! parsers do, so counts read from the file decide how many records follow.
+
+use duke3d_file_utility::formats;
+use std::fmt::Write;
+
+/// A field of a file, with its position and how it decodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub offset: usize,
+    pub bytes: Vec<u8>,
+    pub value: String,
+}
+
+struct Describer<'a> {
+    data: &'a [u8],
+    at: usize,
+    fields: Vec<Field>,
+}
+impl<'a> Describer<'a> {
+    fn bytes<const N: usize>(&mut self, name: &str) -> Result<[u8; N], String> {
+        let bytes = self
+            .data
+            .get(self.at..self.at + N)
+            .ok_or_else(|| format!("{} at offset 0x{:X} is truncated.", name, self.at))?;
+        self.fields.push(Field {
+            name: name.to_string(),
+            offset: self.at,
+            bytes: bytes.to_vec(),
+            value: String::new(),
+        });
+        self.at += N;
+        Ok(bytes.try_into().unwrap())
+    }
+    fn decoded<T: ToString>(&mut self, value: T) -> T {
+        if let Some(field) = self.fields.last_mut() {
+            field.value = value.to_string();
+        }
+        value
+    }
+    fn text<const N: usize>(&mut self, name: &str) -> Result<(), String> {
+        let bytes = self.bytes::<N>(name)?;
+        let text = String::from_utf8_lossy(&bytes);
+        self.decoded(format!("{:?}", text.trim_end_matches('\0')));
+        Ok(())
+    }
+    fn i16(&mut self, name: &str) -> Result<i16, String> {
+        let value = i16::from_le_bytes(self.bytes(name)?);
+        Ok(self.decoded(value))
+    }
+    fn u16(&mut self, name: &str) -> Result<u16, String> {
+        let value = u16::from_le_bytes(self.bytes(name)?);
+        Ok(self.decoded(value))
+    }
+    fn i32(&mut self, name: &str) -> Result<i32, String> {
+        let value = i32::from_le_bytes(self.bytes(name)?);
+        Ok(self.decoded(value))
+    }
+    fn u32(&mut self, name: &str) -> Result<u32, String> {
+        let value = u32::from_le_bytes(self.bytes(name)?);
+        Ok(self.decoded(value))
+    }
+}
+
+/// Picks the format of a file by the extension of its name, or else by its magic bytes.
+pub fn detect_format(name: &str, data: &[u8]) -> Option<&'static str> {
+    let extension = name.rsplit_once('.').map(|(_, e)| e.to_lowercase());
+    let supported = formats::supported();
+    supported
+        .iter()
+        .find(|f| {
+            extension
+                .as_deref()
+                .is_some_and(|e| f.extensions.contains(&e))
+        })
+        .or_else(|| {
+            supported
+                .iter()
+                .find(|f| f.magic.is_some_and(|magic| data.starts_with(magic)))
+        })
+        .map(|f| f.name)
+}
+
+/// The fields of the header of a file of the given format name, and of at
+/// most `limit` of the records that follow it.
+///
+/// The fields before a truncated one are described even when the file ends early.
+pub fn describe(format: &str, data: &[u8], limit: usize) -> (Vec<Field>, Option<String>) {
+    let mut describer = Describer {
+        data,
+        at: 0,
+        fields: Vec::new(),
+    };
+    let result = match format {
+        "GRP" => describe_grp(&mut describer, limit),
+        "ART" => describe_art(&mut describer, limit),
+        "MAP" => describe_map(&mut describer),
+        _ => Err(format!("Format {} cannot be described.", format)),
+    };
+    (describer.fields, result.err())
+}
+
+fn describe_grp(describer: &mut Describer, limit: usize) -> Result<(), String> {
+    describer.text::<12>("signature")?;
+    let file_count = describer.u32("file count")?;
+    for i in 0..(file_count as usize).min(limit) {
+        describer.text::<12>(&format!("entry {} name", i))?;
+        describer.u32(&format!("entry {} size", i))?;
+    }
+    Ok(())
+}
+
+fn describe_art(describer: &mut Describer, limit: usize) -> Result<(), String> {
+    let version = describer.u32("version")?;
+    // Versions without picanm values are described without that array.
+    let has_picanm =
+        art::ArtVersion::from_number(version).is_none_or(|v| v.capabilities().has_picanm);
+    describer.u32("tile count")?;
+    let first = describer.u32("first tile")?;
+    let last = describer.u32("last tile")?;
+    // Widths, heights and picanm values are arrays of all tiles one after another.
+    let count = last.saturating_sub(first).saturating_add(1) as usize;
+    let arrays = [("width", 2), ("height", 2), ("picanm", 4)];
+    for (array, size) in arrays.into_iter().take(if has_picanm { 3 } else { 2 }) {
+        for i in 0..count.min(limit) {
+            let name = format!("tile {} {}", first as usize + i, array);
+            match array {
+                "picanm" => describer.u32(&name).map(|_| ())?,
+                _ => describer.i16(&name).map(|_| ())?,
+            }
+        }
+        describer.at += (count - count.min(limit)) * size;
+    }
+    Ok(())
+}
+
+fn describe_map(describer: &mut Describer) -> Result<(), String> {
+    describer.i32("version")?;
+    describer.i32("player x")?;
+    describer.i32("player y")?;
+    describer.i32("player z")?;
+    describer.i16("player angle")?;
+    describer.i16("player sector")?;
+    describer.u16("sector count")?;
+    Ok(())
+}
+
+/// One line per field: offset, bytes in hex, name and decoded value.
+pub fn format_fields(fields: &[Field]) -> String {
+    let mut text = String::new();
+    for field in fields {
+        let bytes = field
+            .bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = writeln!(
+            text,
+            "0x{:08X}  {:<35}  {} = {}",
+            field.offset, bytes, field.name, field.value
+        );
+    }
+    text
+}
+
+#[test]
+fn should_describe_headers() {
+    let grp = grp::testing::GrpFixture::new()
+        .entry("GAME.CON", b"define")
+        .entry("E1L1.MAP", b"")
+        .build();
+    assert_eq!(detect_format("DUKE3D.GRP", &grp), Some("GRP"));
+    assert_eq!(detect_format("UNKNOWN", &grp), Some("GRP"));
+    let (fields, error) = describe("GRP", &grp, 1);
+    assert_eq!(error, None);
+    assert_eq!(
+        fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.offset, f.value.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            ("signature", 0, "\"KenSilverman\""),
+            ("file count", 12, "2"),
+            ("entry 0 name", 16, "\"GAME.CON\""),
+            ("entry 0 size", 28, "6"),
+        ]
+    );
+
+    let art = art::testing::ArtFixture::new(10)
+        .tile(2, 3, 1)
+        .tile(4, 5, 1)
+        .build();
+    let (fields, _) = describe("ART", &art, 1);
+    assert_eq!(
+        fields
+            .iter()
+            .skip(4)
+            .map(|f| (f.name.as_str(), f.offset, f.value.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            ("tile 10 width", 16, "2"),
+            ("tile 10 height", 20, "3"),
+            ("tile 10 picanm", 24, "0"),
+        ]
+    );
+
+    let map = map::testing::two_rooms().to_bytes();
+    let (fields, error) = describe("MAP", &map[..19], 10);
+    assert_eq!(fields.len(), 5);
+    assert_eq!(
+        error.as_deref(),
+        Some("player sector at offset 0x12 is truncated.")
+    );
+    assert_eq!(
+        format_fields(&fields[..1]),
+        format!("0x00000000  {:<35}  version = 7\n", "07 00 00 00")
+    );
+}
@@ -0,0 +1,151 @@
+use crate::dir_sync::SYNC_STATE_FILE_NAME;
+use grp::{fs_name, GrpBuilder, GrpFileReader};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+};
+
+/// State of a single file when comparing an archive with a directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryStatus {
+    /// The file exists only in the directory.
+    New,
+    /// The file exists in both, but the contents differ.
+    Modified,
+    /// The file exists only in the archive.
+    Missing,
+    Unchanged,
+}
+
+/// Compares the entries of an archive with the files directly inside `dir`.
+/// File names are matched case-insensitively and the result is sorted by
+/// (uppercased) name.
+pub fn compare_with_dir<R: Read + Seek>(
+    grp_reader: &mut GrpFileReader<R>,
+    dir: &Path,
+) -> Result<Vec<(String, EntryStatus)>, String> {
+    compare_with_files(grp_reader, list_dir_files(dir)?)
+}
+
+/// Compares the entries of an archive with files listed by [`list_dir_files`].
+pub fn compare_with_files<R: Read + Seek>(
+    grp_reader: &mut GrpFileReader<R>,
+    mut dir_files: BTreeMap<String, PathBuf>,
+) -> Result<Vec<(String, EntryStatus)>, String> {
+    let mut statuses = BTreeMap::new();
+    for entry in grp_reader.get_file_entries()? {
+        let name = entry.name().to_string().to_uppercase();
+        let status = match dir_files.remove(&name) {
+            None => EntryStatus::Missing,
+            Some(path) => {
+                let file_len = fs::metadata(&path)
+                    .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?
+                    .len();
+                // Only compare contents when the sizes match.
+                if file_len != entry.size() as u64 {
+                    EntryStatus::Modified
+                } else {
+                    let file_data = fs::read(&path)
+                        .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+                    if file_data == grp_reader.read_file(&entry)? {
+                        EntryStatus::Unchanged
+                    } else {
+                        EntryStatus::Modified
+                    }
+                }
+            }
+        };
+        statuses.insert(name, status);
+    }
+    for name in dir_files.into_keys() {
+        statuses.insert(name, EntryStatus::New);
+    }
+
+    Ok(statuses.into_iter().collect())
+}
+
+/// The new and modified files of `dir` as an overlay of the archive, which
+/// the game loads after the archive. Files missing from the directory are
+/// kept from the archive, as an overlay cannot remove them. The entries are
+/// named by decoding the file names with [`fs_name::from_fs_name`], so that
+/// they replace the entries of the archive that were extracted to them.
+pub fn overlay<R: Read + Seek>(
+    grp_reader: &mut GrpFileReader<R>,
+    dir: &Path,
+) -> Result<GrpBuilder, String> {
+    let dir_files = list_dir_files(dir)?;
+    let statuses = compare_with_files(grp_reader, dir_files.clone())?;
+    let mut builder = GrpBuilder::new();
+    for (name, status) in statuses {
+        if !matches!(status, EntryStatus::New | EntryStatus::Modified) {
+            continue;
+        }
+        let Some(path) = dir_files.get(&name) else {
+            continue;
+        };
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        builder = builder.add_from_path_streamed_as(fs_name::from_fs_name(&file_name)?, path)?;
+    }
+    Ok(builder)
+}
+
+/// Lists the files directly inside `dir`, keyed by their uppercased entry
+/// names (see [`grp::fs_name`]) so that they can be matched with archive entries.
+/// The state file written by grp-sync is not part of the listing.
+pub fn list_dir_files(dir: &Path) -> Result<BTreeMap<String, PathBuf>, String> {
+    let mut dir_files = BTreeMap::new();
+    let dir_entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory \"{}\": {}", dir.display(), e))?;
+    for dir_entry in dir_entries {
+        let dir_entry = dir_entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        if !dir_entry.path().is_file() || dir_entry.file_name() == SYNC_STATE_FILE_NAME {
+            continue;
+        }
+        let file_name = dir_entry.file_name().to_string_lossy().into_owned();
+        let name = match fs_name::from_fs_name(&file_name) {
+            Ok(name) => name.to_string(),
+            // Such files fail when added to an archive, with a better message.
+            Err(_) => file_name.to_uppercase(),
+        };
+        dir_files.insert(name, dir_entry.path());
+    }
+    Ok(dir_files)
+}
+
+#[test]
+fn should_overlay_escaped_names_onto_their_entries() {
+    let dir = std::env::temp_dir().join("duke3d_should_overlay_escaped_names");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("WHAT%3F.TXT"), b"new text").unwrap();
+    fs::write(dir.join("%41UX.DAT"), b"new data").unwrap();
+    fs::write(dir.join("GAME.CON"), b"include defs.con").unwrap();
+    fs::write(dir.join("USER.CON"), b"gamestartup").unwrap();
+    let base = grp::testing::GrpFixture::new()
+        .entry("WHAT?.TXT", b"text")
+        .entry("AUX.DAT", b"data")
+        .entry("GAME.CON", b"include defs.con")
+        .build();
+
+    let mut grp_reader = GrpFileReader::new(std::io::Cursor::new(base)).unwrap();
+    let mut bytes = Vec::new();
+    overlay(&mut grp_reader, &dir)
+        .unwrap()
+        .write_to(&mut bytes)
+        .unwrap();
+    let mut overlay_reader = GrpFileReader::new(std::io::Cursor::new(bytes)).unwrap();
+    let names = overlay_reader
+        .get_file_entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["AUX.DAT", "USER.CON", "WHAT?.TXT"]);
+    let entry = overlay_reader
+        .find_file_entry("WHAT?.TXT")
+        .unwrap()
+        .unwrap();
+    assert_eq!(overlay_reader.read_file(&entry).unwrap(), b"new text");
+}
@@ -0,0 +1,245 @@
+use crate::dir_status::list_dir_files;
+use grp::{
+    crc32,
+    edit::{EditSession, EntryData},
+    fs_name,
+    temp_file::TempFile,
+    GrpFileReader,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+/// Name of the file inside the synchronized directory that records
+/// the checksum of every file as of the last successful sync.
+pub const SYNC_STATE_FILE_NAME: &str = ".grpsync";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncDirection {
+    ToDir,
+    ToGrp,
+}
+
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub copied: Vec<String>,
+    pub removed: Vec<String>,
+    /// Files that changed only on the target side and were left alone.
+    pub skipped: Vec<String>,
+}
+
+/// Copies the files that changed since the last sync from one side to the other.
+///
+/// A file only counts as changed on a side when its checksum differs from
+/// the one recorded at the last sync. When both sides changed the same file
+/// differently, nothing is modified and the conflicting names are returned
+/// as an error. Without a recorded state, as on the first sync, every file
+/// that differs is copied in the given direction and nothing is removed.
+pub fn sync(grp_path: &Path, dir: &Path, direction: SyncDirection) -> Result<SyncReport, String> {
+    let file = File::open(grp_path)
+        .map_err(|e| format!("Failed to open \"{}\": {}", grp_path.display(), e))?;
+    let mut grp_reader = GrpFileReader::new(&file)?;
+
+    let grp_entries = grp_reader.get_file_entries()?;
+    let mut grp_data = BTreeMap::new();
+    let mut grp_names = BTreeMap::new();
+    for entry in &grp_entries {
+        let data = grp_reader.read_file(entry)?;
+        let name = entry.name().to_string().to_uppercase();
+        grp_names.insert(name.clone(), entry.name());
+        grp_data.insert(name, data);
+    }
+    let grp_hashes = grp_data
+        .iter()
+        .map(|(name, data)| (name.clone(), crc32(data)))
+        .collect::<BTreeMap<_, _>>();
+
+    let dir_files = list_dir_files(dir)?;
+    let mut dir_hashes = BTreeMap::new();
+    for (name, path) in &dir_files {
+        let data =
+            fs::read(path).map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+        dir_hashes.insert(name.clone(), crc32(&data));
+    }
+
+    let state_path = dir.join(SYNC_STATE_FILE_NAME);
+    let state = read_state(&state_path)?;
+    let first_sync = state.is_none();
+    let base_hashes = state.unwrap_or_default();
+
+    let (source, target) = match direction {
+        SyncDirection::ToDir => (&grp_hashes, &dir_hashes),
+        SyncDirection::ToGrp => (&dir_hashes, &grp_hashes),
+    };
+
+    let names = source
+        .keys()
+        .chain(target.keys())
+        .chain(base_hashes.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    let mut report = SyncReport::default();
+    let mut conflicts = Vec::new();
+    for name in &names {
+        let (base, source, target) = (base_hashes.get(name), source.get(name), target.get(name));
+        if source == target {
+            continue;
+        }
+        if target == base || (first_sync && source.is_some()) {
+            match source {
+                Some(_) => report.copied.push(name.clone()),
+                None => report.removed.push(name.clone()),
+            }
+        } else if source == base {
+            report.skipped.push(name.clone());
+        } else {
+            conflicts.push(name.clone());
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(format!(
+            "Both the archive and the directory changed: {}. Nothing was synchronized.",
+            conflicts.join(", ")
+        ));
+    }
+
+    match direction {
+        SyncDirection::ToDir => {
+            for name in &report.copied {
+                let path = dir_files
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_else(|| dir.join(fs_name::to_fs_name(&grp_names[name])));
+                let mut file = TempFile::new(&path)?;
+                file.file()
+                    .write_all(&grp_data[name])
+                    .map_err(|e| format!("Failed to write \"{}\": {}", path.display(), e))?;
+                file.persist()?;
+            }
+            for name in &report.removed {
+                let path = &dir_files[name];
+                fs::remove_file(path)
+                    .map_err(|e| format!("Failed to remove \"{}\": {}", path.display(), e))?;
+            }
+        }
+        SyncDirection::ToGrp => {
+            drop(grp_reader);
+            let mut session = EditSession::new(grp_path);
+            session.record_undo(&format!(
+                "grp-sync --direction to-grp --dir {}",
+                dir.display()
+            ));
+            for name in &report.copied {
+                let data = EntryData::Path(dir_files[name].clone());
+                if grp_hashes.contains_key(name) {
+                    session.replace(name, data);
+                } else {
+                    session.add(name, data);
+                }
+            }
+            for name in &report.removed {
+                session.remove(name);
+            }
+            if !session.is_empty() {
+                session.apply()?;
+            }
+        }
+    }
+
+    // Both sides now agree on every file that was in sync or got copied;
+    // everything else keeps its previously recorded state.
+    let mut new_state = base_hashes;
+    for name in &names {
+        let synced = match direction {
+            SyncDirection::ToDir => grp_hashes.get(name),
+            SyncDirection::ToGrp => dir_hashes.get(name),
+        };
+        if report.skipped.contains(name) {
+            continue;
+        }
+        match synced {
+            Some(hash) => new_state.insert(name.clone(), *hash),
+            None => new_state.remove(name),
+        };
+    }
+    write_state(&state_path, &new_state)?;
+
+    Ok(report)
+}
+
+/// The recorded checksums, or `None` if nothing has been synchronized yet.
+fn read_state(path: &Path) -> Result<Option<BTreeMap<String, u32>>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(format!("Failed to read \"{}\": {}", path.display(), err)),
+    };
+
+    let mut state = BTreeMap::new();
+    for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+        // Names can contain spaces, so the checksum is split off at the last
+        // tab. State files written before used a space instead.
+        let parsed = line
+            .rsplit_once('\t')
+            .or_else(|| line.rsplit_once(' '))
+            .and_then(|(name, hash)| Some((name, u32::from_str_radix(hash, 16).ok()?)));
+        let Some((name, hash)) = parsed else {
+            return Err(format!(
+                "Invalid line \"{}\" in sync state file \"{}\".",
+                line,
+                path.display()
+            ));
+        };
+        state.insert(name.to_string(), hash);
+    }
+    Ok(Some(state))
+}
+
+fn write_state(path: &Path, state: &BTreeMap<String, u32>) -> Result<(), String> {
+    let contents = state
+        .iter()
+        .map(|(name, hash)| format!("{}\t{:08X}\n", name, hash))
+        .collect::<String>();
+    let mut file = TempFile::new(path)?;
+    file.file()
+        .write_all(contents.as_bytes())
+        .map_err(|e| format!("Failed to write \"{}\": {}", path.display(), e))?;
+    file.persist()
+}
+
+#[test]
+fn should_copy_in_the_given_direction_on_the_first_sync() {
+    let dir = std::env::temp_dir().join("duke3d_should_copy_on_the_first_sync");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let grp_path = dir.join("GAME.GRP");
+    let sync_dir = dir.join("files");
+    fs::create_dir_all(&sync_dir).unwrap();
+    grp::testing::GrpFixture::new()
+        .entry("GAME.CON", b"include defs.con")
+        .entry("MY MAP.MAP", b"map")
+        .write_to(&grp_path)
+        .unwrap();
+    fs::write(sync_dir.join("GAME.CON"), b"gamestartup").unwrap();
+    fs::write(sync_dir.join("USER.CON"), b"user").unwrap();
+
+    let report = sync(&grp_path, &sync_dir, SyncDirection::ToDir).unwrap();
+    assert_eq!(report.copied, ["GAME.CON", "MY MAP.MAP"]);
+    assert!(report.removed.is_empty());
+    assert_eq!(report.skipped, ["USER.CON"]);
+    assert_eq!(
+        fs::read(sync_dir.join("GAME.CON")).unwrap(),
+        b"include defs.con"
+    );
+    assert_eq!(fs::read(sync_dir.join("MY%20MAP.MAP")).unwrap(), b"map");
+
+    // The recorded state is read back, including the name with a space.
+    let report = sync(&grp_path, &sync_dir, SyncDirection::ToDir).unwrap();
+    assert!(report.copied.is_empty());
+    assert_eq!(report.skipped, ["USER.CON"]);
+}
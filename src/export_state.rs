@@ -0,0 +1,98 @@
+//! State files of `art-export --output-dir`, which record a hash of every
+//! exported tile so that the next run only exports the tiles that changed.
+//!
+//! Each line holds a tile number and the SHA-256 hash of what its image is
+//! made of: the palette, the size and the pixels of the tile and the image
+//! format. A tile whose hash is unchanged and whose image still exists is
+//! skipped.
+
+use crate::verify;
+use grp::{temp_file::TempFile, Sha256};
+use std::{collections::BTreeMap, fs, io::Write, path::Path};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportState {
+    hashes: BTreeMap<u32, String>,
+}
+impl ExportState {
+    /// Reads a state file, or starts an empty state if there is none.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(format!("Failed to read \"{}\": {}", path.display(), err)),
+        };
+        let mut hashes = BTreeMap::new();
+        for (i, line) in contents.lines().enumerate() {
+            let invalid = || {
+                format!(
+                    "Line {} of \"{}\": expected a tile number and a hash.",
+                    i + 1,
+                    path.display()
+                )
+            };
+            let (tile_number, hash) = line.split_once(' ').ok_or_else(invalid)?;
+            let tile_number = tile_number.parse().map_err(|_| invalid())?;
+            hashes.insert(tile_number, hash.to_string());
+        }
+        Ok(Self { hashes })
+    }
+    /// Whether a tile was exported with the same hash by a previous run.
+    pub fn is_unchanged(&self, tile_number: u32, hash: &str) -> bool {
+        self.hashes.get(&tile_number).is_some_and(|h| h == hash)
+    }
+    pub fn record(&mut self, tile_number: u32, hash: String) {
+        self.hashes.insert(tile_number, hash);
+    }
+    /// Writes the state, replacing the previous state file. Tiles of other
+    /// .art files exported into the same directory are kept.
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let mut contents = String::new();
+        for (tile_number, hash) in &self.hashes {
+            contents.push_str(&format!("{} {}\n", tile_number, hash));
+        }
+        let mut state_file = TempFile::new(path)?;
+        state_file
+            .file()
+            .write_all(contents.as_bytes())
+            .map_err(|e| format!("Failed to write \"{}\": {}", path.display(), e))?;
+        state_file.persist()
+    }
+}
+
+/// The hash of the image of a tile, from everything the image is made of.
+pub fn tile_hash(
+    palette: &palette::Palette,
+    tile: &art::ArtTile,
+    pixels: &[u8],
+    format: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    for index in 0..=u8::MAX {
+        hasher.update(&palette.color(index));
+    }
+    hasher.update(&tile.width().to_le_bytes());
+    hasher.update(&tile.height().to_le_bytes());
+    hasher.update(pixels);
+    hasher.update(format.as_bytes());
+    verify::to_hex(&hasher.finish())
+}
+
+#[test]
+fn should_remember_tile_hashes() {
+    let path = std::env::temp_dir().join("duke3d_should_remember_tile_hashes.state");
+    let _ = fs::remove_file(&path);
+
+    let mut state = ExportState::read(&path).unwrap();
+    assert!(!state.is_unchanged(0, "ab"));
+    state.record(0, "ab".to_string());
+    state.record(512, "cd".to_string());
+    state.write(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "0 ab\n512 cd\n");
+
+    let state = ExportState::read(&path).unwrap();
+    assert!(state.is_unchanged(512, "cd"));
+    assert!(!state.is_unchanged(512, "ab"));
+    fs::write(&path, "tile\n").unwrap();
+    assert!(ExportState::read(&path).is_err());
+}
@@ -0,0 +1,307 @@
+//! Writing the entries of an archive to files, shared by the extracting
+//! commands so that they all treat the written files the same way.
+//!
+//! Extracted files get the permissions of any new file, which is 0666
+//! minus the umask on Unix, unless a mode is given. Likewise they belong to
+//! the extracting user unless an owner is given.
+
+use grp::{temp_file::TempFile, GrpFileEntry, GrpFileReader};
+use std::{
+    fs,
+    io::{Read, Seek},
+    path::Path,
+    time::SystemTime,
+};
+
+/// The modification time given to extracted files. Archives do not store
+/// timestamps, so a fixed policy keeps downstream builds and rsync
+/// transfers reproducible.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MtimePolicy {
+    /// The modification time of the archive.
+    Archive,
+    /// The time of extraction, as for any newly written file.
+    #[default]
+    Now,
+    /// 1970-01-01 00:00:00 UTC.
+    Epoch,
+}
+impl MtimePolicy {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "archive" => Ok(Self::Archive),
+            "now" => Ok(Self::Now),
+            "epoch" => Ok(Self::Epoch),
+            _ => Err(format!(
+                "Unknown mtime policy: {} (should be archive, now or epoch)",
+                value
+            )),
+        }
+    }
+    /// The time for files from the archive, or `None` for the current time.
+    pub fn resolve(self, archive_path: &Path) -> Result<Option<SystemTime>, String> {
+        match self {
+            Self::Archive => fs::metadata(archive_path)
+                .and_then(|m| m.modified())
+                .map(Some)
+                .map_err(|e| format!("Failed to read \"{}\": {}", archive_path.display(), e)),
+            Self::Now => Ok(None),
+            Self::Epoch => Ok(Some(SystemTime::UNIX_EPOCH)),
+        }
+    }
+}
+
+/// What happens to existing files at the paths of extracted entries.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    #[default]
+    Replace,
+    /// Fail with an error, also when the file appears during extraction.
+    Refuse,
+}
+
+/// The policies for the files written by an [`Extractor`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExtractOptions {
+    pub mtime: MtimePolicy,
+    /// The permission bits of extracted files, only supported on Unix.
+    pub mode: Option<u32>,
+    /// The owner of extracted files, only supported on Unix.
+    pub owner: Option<Owner>,
+    pub overwrite: OverwritePolicy,
+}
+
+/// A user and group by their numeric ids, either of which can be left as is.
+/// Archives do not store owners, so names would only be looked up for
+/// this, and changing the user usually requires root.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Owner {
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+/// Parses a file mode given in octal, e.g. `644`.
+pub fn parse_mode(value: &str) -> Result<u32, String> {
+    if !cfg!(unix) {
+        return Err("File modes are only supported on Unix.".to_string());
+    }
+    u32::from_str_radix(value, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| format!("Invalid file mode: {} (should be octal, e.g. 644)", value))
+}
+
+/// Parses an owner given as `UID`, `UID:GID` or `:GID`, e.g. `1000:100`.
+pub fn parse_owner(value: &str) -> Result<Owner, String> {
+    if !cfg!(unix) {
+        return Err("File owners are only supported on Unix.".to_string());
+    }
+    let invalid = || format!("Invalid owner: {} (should be UID, UID:GID or :GID)", value);
+    let parse_id = |id: &str| match id {
+        "" => Ok(None),
+        _ => id.parse().map(Some).map_err(|_| invalid()),
+    };
+    let (uid, gid) = value.split_once(':').unwrap_or((value, ""));
+    let owner = Owner {
+        uid: parse_id(uid)?,
+        gid: parse_id(gid)?,
+    };
+    if owner == Owner::default() {
+        return Err(invalid());
+    }
+    Ok(owner)
+}
+
+/// Extracts entries of one archive following the policies.
+pub struct Extractor {
+    mtime: Option<SystemTime>,
+    mode: Option<u32>,
+    owner: Option<Owner>,
+    overwrite: OverwritePolicy,
+}
+impl Extractor {
+    pub fn new(archive_path: &Path, options: &ExtractOptions) -> Result<Self, String> {
+        Ok(Self {
+            mtime: options.mtime.resolve(archive_path)?,
+            mode: options.mode,
+            owner: options.owner,
+            overwrite: options.overwrite,
+        })
+    }
+    /// Writes an entry to `path`. An existing file is only replaced once the
+    /// entry has been copied completely, and only if the policy allows it.
+    pub fn extract<R: Read + Seek>(
+        &self,
+        grp_reader: &mut GrpFileReader<R>,
+        entry: &GrpFileEntry,
+        path: &Path,
+    ) -> Result<(), String> {
+        // Checked up front too, so that nothing is copied in vain.
+        if self.overwrite == OverwritePolicy::Refuse && path.exists() {
+            return Err(format!("\"{}\" already exists.", path.display()));
+        }
+        let mut output_file = TempFile::new(path)?;
+        grp_reader.copy_file_to(entry, output_file.file(), None)?;
+        if let Some(mtime) = self.mtime {
+            output_file
+                .file()
+                .set_modified(mtime)
+                .map_err(|e| format!("Failed to set the time of \"{}\": {}", path.display(), e))?;
+        }
+        #[cfg(unix)]
+        if let Some(mode) = self.mode {
+            use std::os::unix::fs::PermissionsExt;
+            output_file
+                .file()
+                .set_permissions(fs::Permissions::from_mode(mode))
+                .map_err(|e| format!("Failed to set the mode of \"{}\": {}", path.display(), e))?;
+        }
+        #[cfg(unix)]
+        if let Some(owner) = self.owner {
+            std::os::unix::fs::fchown(output_file.file(), owner.uid, owner.gid)
+                .map_err(|e| format!("Failed to set the owner of \"{}\": {}", path.display(), e))?;
+        }
+        match self.overwrite {
+            OverwritePolicy::Replace => output_file.persist(),
+            OverwritePolicy::Refuse => output_file.persist_new(),
+        }
+    }
+}
+
+#[test]
+fn should_set_mtime_of_extracted_files() {
+    let dir = std::env::temp_dir().join("duke3d_should_set_mtime_of_extracted_files");
+    fs::create_dir_all(&dir).unwrap();
+    let archive_path = dir.join("MOD.GRP");
+    grp::testing::GrpFixture::new()
+        .entry("GAME.CON", b"define")
+        .write_to(&archive_path)
+        .unwrap();
+    let archive_mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(817_776_000);
+    fs::File::options()
+        .write(true)
+        .open(&archive_path)
+        .and_then(|f| f.set_modified(archive_mtime))
+        .unwrap();
+
+    let file = fs::File::open(&archive_path).unwrap();
+    let mut grp_reader = GrpFileReader::new(&file).unwrap();
+    let entry = grp_reader.find_file_entry("GAME.CON").unwrap().unwrap();
+    let output_path = dir.join("GAME.CON");
+    let mut mtime_after = |mtime| {
+        let options = ExtractOptions {
+            mtime,
+            ..Default::default()
+        };
+        Extractor::new(&archive_path, &options)
+            .and_then(|extractor| extractor.extract(&mut grp_reader, &entry, &output_path))
+            .unwrap();
+        fs::metadata(&output_path).unwrap().modified().unwrap()
+    };
+    assert_eq!(mtime_after(MtimePolicy::Archive), archive_mtime);
+    assert_eq!(mtime_after(MtimePolicy::Epoch), SystemTime::UNIX_EPOCH);
+    assert!(mtime_after(MtimePolicy::Now) > archive_mtime);
+    assert_eq!(fs::read(&output_path).unwrap(), b"define");
+    assert!(MtimePolicy::parse("tomorrow").is_err());
+}
+
+#[test]
+fn should_apply_mode_and_overwrite_policies() {
+    let dir = std::env::temp_dir().join("duke3d_should_apply_mode_and_overwrite_policies");
+    fs::create_dir_all(&dir).unwrap();
+    let archive_path = dir.join("MOD.GRP");
+    grp::testing::GrpFixture::new()
+        .entry("GAME.CON", b"define")
+        .write_to(&archive_path)
+        .unwrap();
+    let file = fs::File::open(&archive_path).unwrap();
+    let mut grp_reader = GrpFileReader::new(&file).unwrap();
+    let entry = grp_reader.find_file_entry("GAME.CON").unwrap().unwrap();
+    let output_path = dir.join("GAME.CON");
+    fs::write(&output_path, b"edited").unwrap();
+
+    let refuse = ExtractOptions {
+        overwrite: OverwritePolicy::Refuse,
+        ..Default::default()
+    };
+    let extractor = Extractor::new(&archive_path, &refuse).unwrap();
+    assert_eq!(
+        extractor.extract(&mut grp_reader, &entry, &output_path),
+        Err(format!("\"{}\" already exists.", output_path.display()))
+    );
+    assert_eq!(fs::read(&output_path).unwrap(), b"edited");
+
+    let replace = ExtractOptions {
+        mode: cfg!(unix).then_some(0o640),
+        ..Default::default()
+    };
+    let extractor = Extractor::new(&archive_path, &replace).unwrap();
+    extractor
+        .extract(&mut grp_reader, &entry, &output_path)
+        .unwrap();
+    assert_eq!(fs::read(&output_path).unwrap(), b"define");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&output_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o640);
+        assert_eq!(parse_mode("755"), Ok(0o755));
+        assert!(parse_mode("9").is_err());
+        assert!(parse_mode("17777").is_err());
+    }
+}
+
+#[test]
+fn should_apply_owner_policy() {
+    assert!(parse_owner("").is_err());
+    assert!(parse_owner(":").is_err());
+    assert!(parse_owner("root").is_err());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        assert_eq!(
+            parse_owner("1000:100"),
+            Ok(Owner {
+                uid: Some(1000),
+                gid: Some(100)
+            })
+        );
+        assert_eq!(
+            parse_owner(":100"),
+            Ok(Owner {
+                uid: None,
+                gid: Some(100)
+            })
+        );
+
+        let dir = std::env::temp_dir().join("duke3d_should_apply_owner_policy");
+        fs::create_dir_all(&dir).unwrap();
+        let archive_path = dir.join("MOD.GRP");
+        grp::testing::GrpFixture::new()
+            .entry("GAME.CON", b"define")
+            .write_to(&archive_path)
+            .unwrap();
+        let file = fs::File::open(&archive_path).unwrap();
+        let mut grp_reader = GrpFileReader::new(&file).unwrap();
+        let entry = grp_reader.find_file_entry("GAME.CON").unwrap().unwrap();
+        let output_path = dir.join("GAME.CON");
+
+        // Without root, files can only be given to their own user and group.
+        let metadata = fs::metadata(&archive_path).unwrap();
+        let options = ExtractOptions {
+            owner: Some(Owner {
+                uid: Some(metadata.uid()),
+                gid: Some(metadata.gid()),
+            }),
+            ..Default::default()
+        };
+        Extractor::new(&archive_path, &options)
+            .and_then(|extractor| extractor.extract(&mut grp_reader, &entry, &output_path))
+            .unwrap();
+        let extracted = fs::metadata(&output_path).unwrap();
+        assert_eq!(
+            (extracted.uid(), extracted.gid()),
+            (metadata.uid(), metadata.gid())
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Descriptors of the file formats supported by the enabled format crates.
+
+/// What the library can do with a format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FormatCapabilities {
+    pub read: bool,
+    pub write: bool,
+    /// Whether files can be converted to or from other formats.
+    pub convert: bool,
+}
+
+/// A supported file format. The names and fields are stable, so that
+/// front-ends can rely on them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FormatDescriptor {
+    /// Short uppercase name of the format, e.g. `GRP`.
+    pub name: &'static str,
+    pub description: &'static str,
+    /// File name extensions, lowercase and without the dot.
+    pub extensions: &'static [&'static str],
+    /// Bytes at the start of every file of the format, if there are any.
+    pub magic: Option<&'static [u8]>,
+    pub capabilities: FormatCapabilities,
+}
+
+/// All formats supported by this build, which depends on the enabled features.
+///
+/// ```
+/// let formats = duke3d_file_utility::formats::supported();
+/// if let Some(grp) = formats.iter().find(|f| f.name == "GRP") {
+///     assert_eq!(grp.magic, Some(&b"KenSilverman"[..]));
+///     assert!(grp.capabilities.write);
+/// }
+/// assert_eq!(formats.is_empty(), duke3d_file_utility::enabled_features().is_empty());
+/// ```
+pub fn supported() -> &'static [FormatDescriptor] {
+    &[
+        #[cfg(feature = "art")]
+        FormatDescriptor {
+            name: "ART",
+            description: "Build engine tiles",
+            extensions: &["art"],
+            // Version 1 as a little-endian 32-bit integer.
+            magic: Some(&[1, 0, 0, 0]),
+            capabilities: FormatCapabilities {
+                read: true,
+                write: false,
+                // Tiles can be exported as PNG or BMP images.
+                convert: true,
+            },
+        },
+        #[cfg(feature = "con")]
+        FormatDescriptor {
+            name: "CON",
+            description: "Duke Nukem 3D game scripts",
+            extensions: &["con"],
+            magic: None,
+            capabilities: FormatCapabilities {
+                read: true,
+                write: true,
+                convert: false,
+            },
+        },
+        #[cfg(feature = "grp")]
+        FormatDescriptor {
+            name: "GRP",
+            description: "Build engine archives",
+            extensions: &["grp"],
+            magic: Some(b"KenSilverman"),
+            capabilities: FormatCapabilities {
+                read: true,
+                write: true,
+                convert: false,
+            },
+        },
+        #[cfg(feature = "map")]
+        FormatDescriptor {
+            name: "MAP",
+            description: "Build engine levels (version 7, version 6 converted)",
+            extensions: &["map"],
+            // Version 7 as a little-endian 32-bit integer.
+            magic: Some(&[7, 0, 0, 0]),
+            capabilities: FormatCapabilities {
+                read: true,
+                write: true,
+                convert: true,
+            },
+        },
+        #[cfg(feature = "palette")]
+        FormatDescriptor {
+            name: "PALETTE",
+            description: "Build engine color palettes (PALETTE.DAT)",
+            extensions: &["dat"],
+            magic: None,
+            capabilities: FormatCapabilities {
+                read: true,
+                // The translucency table can be regenerated.
+                write: true,
+                convert: false,
+            },
+        },
+        #[cfg(feature = "palette")]
+        FormatDescriptor {
+            name: "LOOKUP",
+            description: "Build engine palette lookups and base palettes (LOOKUP.DAT)",
+            extensions: &["dat"],
+            magic: None,
+            capabilities: FormatCapabilities {
+                read: true,
+                write: false,
+                convert: false,
+            },
+        },
+    ]
+}
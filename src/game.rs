@@ -0,0 +1,94 @@
+//! What commands assume about the game whose files they work on: how many
+//! tiles it has, where its palette is and how its files are named.
+//!
+//! Only the PC version of Duke Nukem 3D is known so far. Ports with other
+//! tile numbering, palettes or file names are added to [`GAMES`], and the
+//! commands pick them up through [`active`] once `--game` selects them.
+
+use std::sync::OnceLock;
+
+/// The assumptions about one game.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Game {
+    /// The name that `--game` selects the game by.
+    pub name: &'static str,
+    /// The number of tiles the engine of the game can use.
+    pub tile_limit: u32,
+    /// The entry of the base palette in the archive of the game.
+    pub palette_entry: &'static str,
+    /// The script that the game starts from.
+    pub main_script: &'static str,
+    /// The extensions of tile files, maps and scripts, without the dot.
+    pub art_extension: &'static str,
+    pub map_extension: &'static str,
+    pub con_extension: &'static str,
+}
+impl Game {
+    pub fn is_art(&self, name: &str) -> bool {
+        has_extension(name, self.art_extension)
+    }
+    pub fn is_map(&self, name: &str) -> bool {
+        has_extension(name, self.map_extension)
+    }
+    pub fn is_con(&self, name: &str) -> bool {
+        has_extension(name, self.con_extension)
+    }
+}
+
+/// Compares extensions case-insensitively, like the engine opens files.
+fn has_extension(name: &str, extension: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(_, e)| e.eq_ignore_ascii_case(extension))
+}
+
+pub const DUKE3D: Game = Game {
+    name: "duke3d",
+    tile_limit: art::DUKE3D_TILE_LIMIT,
+    palette_entry: "PALETTE.DAT",
+    main_script: "GAME.CON",
+    art_extension: "ART",
+    map_extension: "MAP",
+    con_extension: "CON",
+};
+
+/// The known games, the default first.
+pub static GAMES: &[Game] = &[DUKE3D];
+
+static ACTIVE: OnceLock<&'static Game> = OnceLock::new();
+
+/// Finds a game by its name, ignoring case.
+pub fn find(name: &str) -> Result<&'static Game, String> {
+    GAMES
+        .iter()
+        .find(|game| game.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            let names = GAMES.iter().map(|game| game.name).collect::<Vec<_>>();
+            format!(
+                "Unknown game: {} (should be one of {})",
+                name,
+                names.join(", ")
+            )
+        })
+}
+
+/// Makes `game` the game of the rest of the run, for `--game`.
+pub fn select(game: &'static Game) {
+    let _ = ACTIVE.set(game);
+}
+
+/// The selected game, Duke Nukem 3D unless another one was selected.
+pub fn active() -> &'static Game {
+    ACTIVE.get().copied().unwrap_or(&DUKE3D)
+}
+
+#[test]
+fn should_find_games_and_their_files() {
+    assert_eq!(find("DUKE3D"), Ok(&DUKE3D));
+    assert_eq!(
+        find("dn64"),
+        Err("Unknown game: dn64 (should be one of duke3d)".to_string())
+    );
+    assert!(DUKE3D.is_art("TILES000.ART") && DUKE3D.is_art("tiles000.art"));
+    assert!(DUKE3D.is_con("GAME.CON") && !DUKE3D.is_con("GAME.CON.BAK"));
+    assert!(DUKE3D.is_map("E1L1.MAP") && !DUKE3D.is_map("MAP"));
+}
@@ -0,0 +1,93 @@
+//! The order and names of the entries of an archive as an editable TOML file.
+//!
+//! ```toml
+//! [[entry]]
+//! original = "GAME.CON"
+//! name = "GAME.CON"
+//! size = 151
+//! ```
+//!
+//! Reordering the `[[entry]]` tables reorders the archive and changing
+//! `name` renames the entry. `original` identifies the entry and `size` is
+//! only informational. Names are file names as written by `grp-extract-all`,
+//! so any entry name can be written and read back.
+
+use crate::toml_subset::{self, quote};
+use grp::{fs_name, GrpFileEntry};
+
+/// The metadata file of the entries in archive order.
+pub fn export(archive_name: &str, entries: &[GrpFileEntry]) -> String {
+    let mut source = format!(
+        "# Entries of {} in archive order, to apply with grp-meta apply.\n\
+         # Reorder the [[entry]] tables to reorder the archive and edit `name`\n\
+         # to rename an entry. Do not change `original`.\n",
+        archive_name
+    );
+    for entry in entries {
+        let name = quote(&fs_name::to_fs_name(&entry.name()));
+        source.push_str(&format!(
+            "\n[[entry]]\noriginal = {}\nname = {}\nsize = {}\n",
+            name,
+            name,
+            entry.size()
+        ));
+    }
+    source
+}
+
+/// The entries of a metadata file as pairs of the original and the new
+/// name, in their new order.
+pub fn parse(source: &str) -> Result<Vec<(String, String)>, String> {
+    let mut entries = Vec::new();
+    for table in toml_subset::parse(source)? {
+        if table.name.is_empty() && table.values.is_empty() {
+            continue;
+        }
+        if table.name != "entry" || !table.is_array_item {
+            return Err(format!(
+                "Unknown table [{}] (should be [[entry]]).",
+                table.name
+            ));
+        }
+        let original = fs_name::from_fs_name(table.require_str("original")?)?;
+        let name = match table.get_str("name")? {
+            Some(name) => fs_name::from_fs_name(name)?,
+            None => original,
+        };
+        entries.push((original.to_string(), name.to_string()));
+    }
+    Ok(entries)
+}
+
+#[test]
+fn should_round_trip_entry_metadata() {
+    let grp = grp::testing::GrpFixture::new()
+        .entry("GAME.CON", b"define")
+        .entry("WHAT?.TXT", b"")
+        .build();
+    let entries = grp::GrpFileReader::new(std::io::Cursor::new(grp))
+        .unwrap()
+        .get_file_entries()
+        .unwrap();
+    let source = export("MOD.GRP", &entries);
+    assert!(source.contains("[[entry]]\noriginal = \"WHAT%3F.TXT\"\n"));
+    assert_eq!(
+        parse(&source).unwrap(),
+        [
+            ("GAME.CON".to_string(), "GAME.CON".to_string()),
+            ("WHAT?.TXT".to_string(), "WHAT?.TXT".to_string()),
+        ]
+    );
+
+    let edited = "[[entry]]\noriginal = \"WHAT%3F.TXT\"\nname = \"readme.txt\"\n\
+                  [[entry]]\noriginal = \"GAME.CON\"\n";
+    assert_eq!(
+        parse(edited).unwrap(),
+        [
+            ("WHAT?.TXT".to_string(), "README.TXT".to_string()),
+            ("GAME.CON".to_string(), "GAME.CON".to_string()),
+        ]
+    );
+    assert!(parse("[entry]\noriginal = \"GAME.CON\"\n").is_err());
+    assert!(parse("[[entry]]\nname = \"GAME.CON\"\n").is_err());
+}
@@ -0,0 +1,44 @@
+//! Hex and ASCII dumps in the layout of `hexdump -C`.
+
+use std::fmt::Write;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// Formats `data` that starts at `offset`, one line per 16 bytes with the
+/// offset, the bytes in hex and the printable ASCII characters.
+pub fn format(data: &[u8], offset: u64) -> String {
+    let mut dump = String::new();
+    for (i, line) in data.chunks(BYTES_PER_LINE).enumerate() {
+        let _ = write!(dump, "{:08x} ", offset + (i * BYTES_PER_LINE) as u64);
+        for column in 0..BYTES_PER_LINE {
+            // An extra space between the two halves of the line.
+            if column % 8 == 0 {
+                dump.push(' ');
+            }
+            match line.get(column) {
+                Some(byte) => {
+                    let _ = write!(dump, "{:02x} ", byte);
+                }
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str(" |");
+        dump.extend(line.iter().map(|b| match b {
+            0x20..=0x7E => *b as char,
+            _ => '.',
+        }));
+        dump.push_str("|\n");
+    }
+    dump
+}
+
+#[test]
+fn should_format_like_hexdump() {
+    let dump = format(b"define RPG 1\r\ndefine \x80", 0x20);
+    assert_eq!(
+        dump,
+        "00000020  64 65 66 69 6e 65 20 52  50 47 20 31 0d 0a 64 65  |define RPG 1..de|\n\
+         00000030  66 69 6e 65 20 80                                 |fine .|\n"
+    );
+    assert_eq!(format(b"", 0), "");
+}
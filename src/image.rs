@@ -0,0 +1,118 @@
+//! Encoders for writing RGBA images as PNG or BMP files.
+
+/// Encodes an RGBA image as PNG. The pixel data is stored without
+/// compression, which every PNG decoder accepts.
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    // Every row starts with the filter type, 0 meaning no filter.
+    let row_len = width as usize * 4;
+    let mut raw = Vec::with_capacity((row_len + 1) * height as usize);
+    for row in rgba.chunks(row_len.max(1)).take(height as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, truecolor with alpha, deflate, no filter, no interlace.
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_png_chunk(&mut png, b"IHDR", &ihdr);
+    write_png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = checksum::crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps data in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK_LEN: usize = u16::MAX as usize;
+
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_BLOCK_LEN).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        zlib.push(is_last as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// Encodes an RGBA image as a 32-bit BMP file with an alpha channel.
+pub fn encode_bmp(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    const HEADER_LEN: u32 = 14 + 40;
+
+    let data_len = width * height * 4;
+    let mut bmp = Vec::with_capacity((HEADER_LEN + data_len) as usize);
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&(HEADER_LEN + data_len).to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&HEADER_LEN.to_le_bytes());
+
+    bmp.extend_from_slice(&40u32.to_le_bytes());
+    bmp.extend_from_slice(&(width as i32).to_le_bytes());
+    // A negative height stores the rows from top to bottom.
+    bmp.extend_from_slice(&(-(height as i32)).to_le_bytes());
+    bmp.extend_from_slice(&1u16.to_le_bytes());
+    bmp.extend_from_slice(&32u16.to_le_bytes());
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+    bmp.extend_from_slice(&data_len.to_le_bytes());
+    // 2835 pixels per meter is 72 DPI.
+    bmp.extend_from_slice(&2835i32.to_le_bytes());
+    bmp.extend_from_slice(&2835i32.to_le_bytes());
+    bmp.extend_from_slice(&[0; 8]);
+
+    for pixel in rgba.chunks_exact(4).take((width * height) as usize) {
+        bmp.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+    }
+    bmp
+}
+
+#[test]
+fn should_encode_png() {
+    assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+
+    let png = encode_png(2, 1, &[1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..24], [0, 0, 0, 2, 0, 0, 0, 1]);
+    // IDAT: zlib header, one final stored block of 9 bytes, the row and adler32.
+    assert_eq!(&png[37..41], b"IDAT");
+    assert_eq!(&png[41..48], [0x78, 0x01, 1, 9, 0, 0xF6, 0xFF]);
+    assert_eq!(&png[48..57], [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+}
+
+#[test]
+fn should_encode_bmp() {
+    let bmp = encode_bmp(1, 2, &[1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(bmp.len(), 54 + 8);
+    assert_eq!(&bmp[..2], b"BM");
+    assert_eq!(&bmp[22..26], (-2i32).to_le_bytes());
+    assert_eq!(&bmp[54..], [3, 2, 1, 4, 7, 6, 5, 8]);
+}
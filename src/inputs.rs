@@ -0,0 +1,108 @@
+//! Collection of the archives that a batch command processes.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// An archive to process, with the relative path under which its outputs
+/// are namespaced when several archives are processed.
+pub struct Input {
+    pub path: PathBuf,
+    pub namespace: PathBuf,
+}
+
+/// Collects the archives given with `--input-file` options and the .grp
+/// files inside `dir`, including its subdirectories when `recursive`.
+///
+/// Archives from `dir` are namespaced by their path relative to it without
+/// the extension, other archives by their file name without the extension.
+/// Two archives with the same namespace are rejected.
+pub fn collect(
+    files: &[PathBuf],
+    dir: Option<&Path>,
+    recursive: bool,
+) -> Result<Vec<Input>, String> {
+    let mut inputs = files
+        .iter()
+        .map(|path| Input {
+            path: path.clone(),
+            namespace: PathBuf::from(path.file_stem().unwrap_or_default()),
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(dir) = dir {
+        let mut paths = Vec::new();
+        find_archives(dir, recursive, &mut paths)?;
+        paths.sort();
+        for path in paths {
+            let relative = path.strip_prefix(dir).unwrap_or(&path);
+            inputs.push(Input {
+                namespace: relative.with_extension(""),
+                path,
+            });
+        }
+    }
+
+    let mut namespaces = BTreeMap::new();
+    for input in &inputs {
+        if let Some(other) = namespaces.insert(input.namespace.clone(), &input.path) {
+            return Err(format!(
+                "\"{}\" and \"{}\" would share the output directory \"{}\".",
+                other.display(),
+                input.path.display(),
+                input.namespace.display()
+            ));
+        }
+    }
+
+    Ok(inputs)
+}
+
+fn find_archives(dir: &Path, recursive: bool, paths: &mut Vec<PathBuf>) -> Result<(), String> {
+    let dir_entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory \"{}\": {}", dir.display(), e))?;
+    for dir_entry in dir_entries {
+        let path = dir_entry
+            .map_err(|e| format!("Failed to read directory entry: {}", e))?
+            .path();
+        if path.is_dir() {
+            if recursive {
+                find_archives(&path, recursive, paths)?;
+            }
+        } else if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("grp"))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn should_collect_archives_from_dir() {
+    let dir = std::env::temp_dir().join("duke3d_should_collect_archives_from_dir");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("nwinter")).unwrap();
+    for path in ["DUKE3D.GRP", "readme.txt", "nwinter/DUKE3D.grp"] {
+        fs::write(dir.join(path), b"").unwrap();
+    }
+
+    let namespaces = |inputs: Vec<Input>| {
+        inputs
+            .iter()
+            .map(|i| i.namespace.to_string_lossy().replace('\\', "/"))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        namespaces(collect(&[], Some(&dir), false).unwrap()),
+        ["DUKE3D"]
+    );
+    assert_eq!(
+        namespaces(collect(&[], Some(&dir), true).unwrap()),
+        ["DUKE3D", "nwinter/DUKE3D"]
+    );
+    assert!(collect(&[dir.join("DUKE3D.GRP")], Some(&dir), false).is_err());
+}
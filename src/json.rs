@@ -0,0 +1,267 @@
+//! Reading and writing the JSON of manifests and plugin invocations.
+//!
+//! The reader accepts any JSON document, but keeps numbers as `f64`, which
+//! is exact for the sizes and counts the tool reads.
+
+/// A parsed JSON value. Objects keep their keys in document order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+impl JsonValue {
+    /// The value of a key of an object.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(n) if n.fract() == 0.0 && *n >= 0.0 => Some(*n as u64),
+            _ => None,
+        }
+    }
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a JSON document.
+pub fn parse(source: &str) -> Result<JsonValue, String> {
+    let mut parser = Parser {
+        source: source.as_bytes(),
+        at: 0,
+    };
+    let value = parser.value()?;
+    parser.whitespace();
+    if parser.at < parser.source.len() {
+        return Err(parser.error("expected the end of the document"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    source: &'a [u8],
+    at: usize,
+}
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("Invalid JSON at offset {}: {}.", self.at, message)
+    }
+    fn whitespace(&mut self) {
+        while self
+            .source
+            .get(self.at)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.at += 1;
+        }
+    }
+    fn peek(&mut self) -> Option<u8> {
+        self.whitespace();
+        self.source.get(self.at).copied()
+    }
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+        self.at += 1;
+        Ok(())
+    }
+    fn keyword(&mut self, keyword: &str, value: JsonValue) -> Result<JsonValue, String> {
+        if !self.source[self.at..].starts_with(keyword.as_bytes()) {
+            return Err(self.error("unexpected character"));
+        }
+        self.at += keyword.len();
+        Ok(value)
+    }
+    fn value(&mut self) -> Result<JsonValue, String> {
+        match self.peek() {
+            Some(b'{') => {
+                self.at += 1;
+                let mut members = Vec::new();
+                if self.peek() == Some(b'}') {
+                    self.at += 1;
+                    return Ok(JsonValue::Object(members));
+                }
+                loop {
+                    if self.peek() != Some(b'"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    members.push((key, self.value()?));
+                    match self.peek() {
+                        Some(b',') => self.at += 1,
+                        _ => break,
+                    }
+                }
+                self.expect(b'}')?;
+                Ok(JsonValue::Object(members))
+            }
+            Some(b'[') => {
+                self.at += 1;
+                let mut items = Vec::new();
+                if self.peek() == Some(b']') {
+                    self.at += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    match self.peek() {
+                        Some(b',') => self.at += 1,
+                        _ => break,
+                    }
+                }
+                self.expect(b']')?;
+                Ok(JsonValue::Array(items))
+            }
+            Some(b'"') => Ok(JsonValue::String(self.string()?)),
+            Some(b't') => self.keyword("true", JsonValue::Bool(true)),
+            Some(b'f') => self.keyword("false", JsonValue::Bool(false)),
+            Some(b'n') => self.keyword("null", JsonValue::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.at;
+                while self
+                    .source
+                    .get(self.at)
+                    .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+                {
+                    self.at += 1;
+                }
+                let text = String::from_utf8_lossy(&self.source[start..self.at]);
+                text.parse()
+                    .map(JsonValue::Number)
+                    .map_err(|_| self.error("invalid number"))
+            }
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of the document")),
+        }
+    }
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(byte) = self.source.get(self.at).copied() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.at += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = self.source.get(self.at).copied();
+                    self.at += 1;
+                    let c = match escape {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                byte => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))
+    }
+    fn hex_digits(&mut self) -> Option<u32> {
+        let digits = self.source.get(self.at..self.at + 4)?;
+        self.at += 4;
+        std::str::from_utf8(digits)
+            .ok()
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+    }
+    /// The character of a `\u` escape, which is two escapes for characters
+    /// outside the Basic Multilingual Plane.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let code = match self.hex_digits() {
+            Some(high @ 0xD800..=0xDBFF) => {
+                let low = match self.source.get(self.at..self.at + 2) {
+                    Some(b"\\u") => {
+                        self.at += 2;
+                        self.hex_digits()
+                    }
+                    _ => None,
+                };
+                low.filter(|low| (0xDC00..=0xDFFF).contains(low))
+                    .map(|low| 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+            }
+            code => code,
+        };
+        code.and_then(char::from_u32)
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+}
+
+/// `value` as a JSON string.
+pub fn string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[test]
+fn should_parse_json() {
+    let value =
+        parse(r#" {"entries": [{"name": "GAME.CON", "size": 151}], "ok": true, "x": null} "#)
+            .unwrap();
+    let entry = &value.get("entries").and_then(|e| e.as_array()).unwrap()[0];
+    assert_eq!(entry.get("name").and_then(|n| n.as_str()), Some("GAME.CON"));
+    assert_eq!(entry.get("size").and_then(|s| s.as_u64()), Some(151));
+    assert_eq!(value.get("ok"), Some(&JsonValue::Bool(true)));
+
+    let text = "A \"B\"\n\u{e4}\u{1F600}";
+    assert_eq!(
+        parse(&string(text)),
+        Ok(JsonValue::String(text.to_string()))
+    );
+    assert_eq!(
+        parse(r#""\u00e4\ud83d\ude00""#),
+        Ok(JsonValue::String("\u{e4}\u{1F600}".to_string()))
+    );
+    for invalid in [
+        "",
+        "{",
+        "[1,]",
+        "{\"a\" 1}",
+        "\"\\x\"",
+        "tru",
+        "1 2",
+        "\"\\ud83d\"",
+    ] {
+        assert!(parse(invalid).is_err(), "{}", invalid);
+    }
+}
@@ -0,0 +1,64 @@
+//! Umbrella crate for the Duke Nukem 3D file utility libraries.
+//!
+//! Each format crate is re-exported behind a cargo feature of the same name,
+//! so downstream users can depend on this one crate and pick the formats they need.
+//! The `minimal` feature enables only GRP and ART parsing:
+//!
+//! ```toml
+//! duke3d-file-util = { version = "0.1", default-features = false, features = ["minimal"] }
+//! ```
+//!
+//! None of the crates have external dependencies.
+//!
+//! ```
+//! use duke3d_file_utility::prelude::*;
+//! ```
+
+pub mod formats;
+pub mod image;
+
+#[cfg(feature = "art")]
+pub use art;
+#[cfg(feature = "con")]
+pub use con;
+#[cfg(feature = "grp")]
+pub use grp;
+#[cfg(feature = "map")]
+pub use map;
+#[cfg(feature = "palette")]
+pub use palette;
+
+/// The name and version of the tool, stamped into the files it generates
+/// so that consumers can tell which version produced them.
+pub const TOOL: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+
+/// The cargo features of this crate that are enabled in this build.
+///
+/// ```
+/// let features = duke3d_file_utility::enabled_features();
+/// assert_eq!(features.contains(&"grp"), cfg!(feature = "grp"));
+/// ```
+pub fn enabled_features() -> &'static [&'static str] {
+    &[
+        #[cfg(feature = "art")]
+        "art",
+        #[cfg(feature = "con")]
+        "con",
+        #[cfg(feature = "grp")]
+        "grp",
+        #[cfg(feature = "map")]
+        "map",
+        #[cfg(feature = "palette")]
+        "palette",
+    ]
+}
+
+/// Commonly used types from every enabled format crate.
+pub mod prelude {
+    #[cfg(feature = "art")]
+    pub use art::{ArtFileReader, ArtTile};
+    #[cfg(feature = "grp")]
+    pub use grp::{EntryName, GrpBuilder, GrpFileEntry, GrpFileReader};
+    #[cfg(feature = "map")]
+    pub use map::{Map, MapFileReader, Sector, Sprite, Wall};
+}
@@ -1,65 +1,3543 @@
-use grp::GrpFileReader;
-use std::fs::{self, File};
+use art::{ArtFileReader, TileSet};
+use dir_status::EntryStatus;
+use dir_sync::SyncDirection;
+use duke3d_file_utility::image;
+use extract::{ExtractOptions, Extractor, MtimePolicy, OverwritePolicy};
+use grp::{
+    edit::{EditSession, EntryData},
+    fs_name,
+    query::{glob_matches, Query},
+    temp_file::TempFile,
+    GrpBuilder, GrpFileReader,
+};
+use map::{Axis, Map, MAP_VERSION, MAP_VERSION_6};
+use output::{Cell, Style, Table};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+mod catalog;
+mod cp437;
+mod describe;
+mod dir_status;
+mod dir_sync;
+mod export_state;
+mod extract;
+mod game;
+mod grp_meta;
+mod hexdump;
+mod inputs;
+mod json;
+mod messages;
+mod output;
+mod plugin;
+mod profile;
+mod provenance;
+mod resume;
+mod retile;
+mod selftest;
+mod stats;
+mod tar;
+mod text_index;
+mod texture_check;
+mod tile_alloc;
+mod tile_metadata;
+mod toml_subset;
+mod verify;
+mod workers;
 
 fn main() {
     if let Err(err) = run() {
-        eprintln!("Error: {}", err);
+        output::error(&messages::get("error", &[("error", &err)]));
+        // Scripts, e.g. of grp-verify, rely on the exit code to detect failures.
+        std::process::exit(1);
     }
 }
 
 fn run() -> Result<(), String> {
-    let mut args = std::env::args();
-
-    args.next(); // Skip the executable name.
+    // Skip the executable name. --no-color and --game apply to every command.
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|a| a == "--no-color") {
+        args.retain(|a| a != "--no-color");
+        output::disable_color();
+    }
+    if let Some(i) = args.iter().position(|a| a == "--game") {
+        let Some(name) = args.get(i + 1) else {
+            return Err(messages::get("missing-value", &[("option", &"--game")]));
+        };
+        game::select(game::find(name)?);
+        args.drain(i..i + 2);
+    }
+    let mut args = args.into_iter();
 
     let Some(command) = args.next() else {
-        return Err("No arguments provided.".to_string());
+        return Err(messages::get("no-arguments", &[]));
     };
+    if command == "--version" {
+        let formats = duke3d_file_utility::formats::supported()
+            .iter()
+            .map(|f| f.name)
+            .collect::<Vec<_>>();
+        println!("{}", duke3d_file_utility::TOOL);
+        println!(
+            "{}",
+            messages::get(
+                "version-features",
+                &[(
+                    "features",
+                    &duke3d_file_utility::enabled_features().join(", ")
+                )]
+            )
+        );
+        println!(
+            "{}",
+            messages::get("version-formats", &[("formats", &formats.join(", "))])
+        );
+        return Ok(());
+    }
+    let mut args =
+        profile::apply(&command, args.collect(), &std::env::current_dir().unwrap())?.into_iter();
 
     match command.as_str() {
         "grp-extract" => {
             let mut grp_file_path: Option<String> = None;
             let mut entry_file_name: Option<String> = None;
+            let mut force = false;
+            let mut options = ExtractOptions::default();
+            let mut no_overwrite = false;
+            let mut output_dir: Option<String> = None;
             let mut output_file_path: Option<String> = None;
 
-            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+            while let Some(option) = args.next() {
+                let Some(value) = args.next() else {
+                    return Err(messages::get("missing-value", &[("option", &option)]));
+                };
                 match option.as_str() {
                     "--entry" => {
                         entry_file_name = Some(value);
                     }
+                    "--force" => {
+                        force = parse_switch(&option, &value)?;
+                    }
                     "--input-file" => {
                         grp_file_path = Some(value);
                     }
+                    "--mode" => {
+                        options.mode = Some(extract::parse_mode(&value)?);
+                    }
+                    "--mtime" => {
+                        options.mtime = MtimePolicy::parse(&value)?;
+                    }
+                    "--no-overwrite" => {
+                        no_overwrite = parse_switch(&option, &value)?;
+                    }
+                    "--output-dir" => {
+                        output_dir = Some(value);
+                    }
                     "--output-file" => {
                         output_file_path = Some(value);
                     }
+                    "--owner" => {
+                        options.owner = Some(extract::parse_owner(&value)?);
+                    }
                     _ => {}
                 }
             }
 
-            match (grp_file_path, entry_file_name, output_file_path) {
-                (Some(grp_file_path), Some(entry_file_name), Some(output_file_path)) => {
-                    let curr_dir = std::env::current_dir().unwrap();
-                    let file_path = curr_dir.join(grp_file_path);
-                    let file = File::open(file_path).unwrap();
+            let (Some(grp_file_path), Some(entry_file_name)) = (grp_file_path, entry_file_name)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if force && no_overwrite {
+                return Err(messages::get("force-and-no-overwrite", &[]));
+            }
 
-                    let mut grp_reader = GrpFileReader::new(&file)?;
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let mut grp_reader = GrpFileReader::new(&file)?;
+            let Some(file_entry) = grp_reader.find_file_entry(&entry_file_name)? else {
+                return Err(messages::get(
+                    "entry-not-found",
+                    &[("entry", &entry_file_name)],
+                ));
+            };
 
-                    if let Ok(Some(file_entry)) = grp_reader.find_file_entry(&entry_file_name) {
-                        let file = grp_reader.read_file(&file_entry)?;
-                        println!("File size: {}", file.len());
-                        fs::write(curr_dir.join(output_file_path), file).unwrap();
+            // Without --output-file, the entry is written under its own name,
+            // in the output directory if given. An existing file of that name
+            // is only replaced with --force, as the user did not name it.
+            let output_dir = output_dir.map_or(curr_dir.clone(), |d| curr_dir.join(d));
+            options.overwrite = if no_overwrite || (output_file_path.is_none() && !force) {
+                OverwritePolicy::Refuse
+            } else {
+                OverwritePolicy::Replace
+            };
+            let output_file_path = match output_file_path {
+                Some(output_file_path) => output_dir.join(output_file_path),
+                None => {
+                    let output_file_path = output_dir.join(fs_name::to_fs_name(&file_entry.name()));
+                    if !force && output_file_path.exists() {
+                        return Err(messages::get(
+                            "output-exists",
+                            &[("file", &output_file_path.display())],
+                        ));
                     }
+                    output_file_path
                 }
-                _ => {
-                    return Err("Missing arguments.".to_string());
+            };
+            fs::create_dir_all(&output_dir).map_err(|e| {
+                messages::get(
+                    "create-failed",
+                    &[("file", &output_dir.display()), ("error", &e)],
+                )
+            })?;
+
+            println!(
+                "{}",
+                messages::get("file-size", &[("size", &file_entry.size())])
+            );
+            Extractor::new(&grp_file_path, &options)?.extract(
+                &mut grp_reader,
+                &file_entry,
+                &output_file_path,
+            )?;
+        }
+        "grp-extract-all" => {
+            let mut force = false;
+            let mut grp_file_paths: Vec<String> = Vec::new();
+            let mut input_dir: Option<String> = None;
+            let mut no_overwrite = false;
+            let mut options = ExtractOptions::default();
+            let mut recursive = false;
+            let mut output_dir: Option<String> = None;
+            let mut resume_file_path: Option<String> = None;
+            let mut query: Option<Query> = None;
+
+            while let Some(option) = args.next() {
+                let Some(value) = args.next() else {
+                    return Err(messages::get("missing-value", &[("option", &option)]));
+                };
+                match option.as_str() {
+                    "--force" => {
+                        force = parse_switch(&option, &value)?;
+                    }
+                    "--input-dir" => {
+                        input_dir = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_paths.push(value);
+                    }
+                    "--mode" => {
+                        options.mode = Some(extract::parse_mode(&value)?);
+                    }
+                    "--mtime" => {
+                        options.mtime = MtimePolicy::parse(&value)?;
+                    }
+                    "--no-overwrite" => {
+                        no_overwrite = parse_switch(&option, &value)?;
+                    }
+                    "--output-dir" => {
+                        output_dir = Some(value);
+                    }
+                    "--owner" => {
+                        options.owner = Some(extract::parse_owner(&value)?);
+                    }
+                    "--recursive" => {
+                        recursive = parse_switch(&option, &value)?;
+                    }
+                    "--resume" => {
+                        resume_file_path = Some(value);
+                    }
+                    "--where" => {
+                        query = Some(Query::parse(&value)?);
+                    }
+                    _ => {}
                 }
             }
+
+            let Some(output_dir) = output_dir else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if grp_file_paths.is_empty() && input_dir.is_none() {
+                return Err(messages::get("missing-arguments", &[]));
+            }
+            if force && no_overwrite {
+                return Err(messages::get("force-and-no-overwrite", &[]));
+            }
+            // Existing files are replaced by default, --force only states it.
+            if no_overwrite {
+                options.overwrite = OverwritePolicy::Refuse;
+            }
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_paths = grp_file_paths
+                .iter()
+                .map(|p| curr_dir.join(p))
+                .collect::<Vec<_>>();
+            let input_dir = input_dir.map(|d| curr_dir.join(d));
+            let inputs = inputs::collect(&grp_file_paths, input_dir.as_deref(), recursive)?;
+            // A single archive is extracted directly into the output directory,
+            // several into a subdirectory per archive.
+            let namespaced = inputs.len() > 1 || input_dir.is_some();
+            let output_dir = curr_dir.join(output_dir);
+
+            // With --resume, entries completed by an interrupted run are skipped.
+            let mut resume_state = match resume_file_path {
+                Some(path) => Some(resume::ResumeState::open(&curr_dir.join(path))?),
+                None => None,
+            };
+            let (mut extracted, mut skipped) = (0, 0);
+            for input in &inputs {
+                let file = File::open(&input.path).map_err(|e| {
+                    messages::get(
+                        "open-failed",
+                        &[("file", &input.path.display()), ("error", &e)],
+                    )
+                })?;
+                let mut grp_reader = GrpFileReader::new(&file)?;
+                let extractor = Extractor::new(&input.path, &options)?;
+                let (archive_output_dir, resume_prefix) = if namespaced {
+                    let namespace = input.namespace.to_string_lossy().replace('\\', "/");
+                    (output_dir.join(&input.namespace), format!("{}/", namespace))
+                } else {
+                    (output_dir.clone(), String::new())
+                };
+                fs::create_dir_all(&archive_output_dir).map_err(|e| {
+                    messages::get(
+                        "create-failed",
+                        &[("file", &archive_output_dir.display()), ("error", &e)],
+                    )
+                })?;
+
+                for entry in grp_reader.get_file_entries()? {
+                    if let Some(query) = &query {
+                        if !query.matches_entry(&mut grp_reader, &entry)? {
+                            continue;
+                        }
+                    }
+                    let fs_name = fs_name::to_fs_name(&entry.name());
+                    let resume_item = format!("{}{}", resume_prefix, fs_name);
+                    if resume_state
+                        .as_ref()
+                        .is_some_and(|s| s.is_completed(&resume_item))
+                    {
+                        skipped += 1;
+                        continue;
+                    }
+                    extractor.extract(
+                        &mut grp_reader,
+                        &entry,
+                        &archive_output_dir.join(&fs_name),
+                    )?;
+                    if let Some(resume_state) = &mut resume_state {
+                        resume_state.complete(&resume_item)?;
+                    }
+                    extracted += 1;
+                }
+            }
+            if let Some(resume_state) = resume_state {
+                resume_state.finish()?;
+            }
+            println!(
+                "{}",
+                messages::get(
+                    "entries-extracted",
+                    &[
+                        ("extracted", &extracted),
+                        ("skipped", &skipped),
+                        ("archives", &inputs.len()),
+                    ],
+                )
+            );
         }
-        _ => {
-            return Err(format!("Unknown command: {}", command));
+        "grp-pack" => {
+            let mut input_dir: Option<String> = None;
+            let mut input_files: Vec<String> = Vec::new();
+            let mut output_file_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--file" => {
+                        input_files.push(value);
+                    }
+                    "--input-dir" => {
+                        input_dir = Some(value);
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(output_file_path) = output_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if input_dir.is_none() && input_files.is_empty() {
+                return Err(messages::get("missing-arguments", &[]));
+            }
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let mut builder = GrpBuilder::new();
+            if let Some(input_dir) = input_dir {
+                builder = builder.add_dir(&curr_dir.join(input_dir))?;
+            }
+            for input_file in input_files {
+                builder = builder.add_from_path_streamed(&curr_dir.join(input_file))?;
+            }
+            builder.write_to_file(&curr_dir.join(&output_file_path))?;
+            println!(
+                "{}",
+                messages::get(
+                    "entries-packed",
+                    &[("count", &builder.len()), ("file", &output_file_path)],
+                )
+            );
         }
-    }
+        "grp-add" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut input_file: Option<String> = None;
+            let mut entry_name: Option<String> = None;
+            let mut output_dir: Option<String> = None;
 
-    Ok(())
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_name = Some(value);
+                    }
+                    "--file" => {
+                        input_file = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--output-dir" => {
+                        output_dir = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(input_file)) = (grp_file_path, input_file) else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let input_file = curr_dir.join(input_file);
+            // Without --entry, the entry is named after the file.
+            let entry_name = match entry_name {
+                Some(entry_name) => entry_name,
+                None => {
+                    let file_name = input_file.file_name().unwrap_or_default();
+                    fs_name::from_fs_name(&file_name.to_string_lossy())?.to_string()
+                }
+            };
+
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let exists = GrpFileReader::new(&file)?
+                .find_file_entry(&entry_name)?
+                .is_some();
+            drop(file);
+
+            let output_dir = output_dir.map(|d| curr_dir.join(d));
+            let mut session = edit_session(
+                &grp_file_path,
+                output_dir.as_deref(),
+                &format!("grp-add --entry {}", entry_name),
+            )?;
+            let data = EntryData::Path(input_file);
+            if exists {
+                session.replace(&entry_name, data);
+            } else {
+                session.add(&entry_name, data);
+            }
+            session.apply()?;
+            let id = if exists {
+                "entry-replaced"
+            } else {
+                "entry-added"
+            };
+            println!("{}", messages::get(id, &[("entry", &entry_name)]));
+        }
+        "grp-remove" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut entry_names: Vec<String> = Vec::new();
+            let mut output_dir: Option<String> = None;
+            let mut query: Option<Query> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_names.push(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--output-dir" => {
+                        output_dir = Some(value);
+                    }
+                    "--where" => {
+                        query = Some(Query::parse(&value)?);
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(grp_file_path) = grp_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if entry_names.is_empty() && query.is_none() {
+                return Err(messages::get("missing-arguments", &[]));
+            }
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            // The entries that match --where are removed with the named ones.
+            if let Some(query) = &query {
+                let file = File::open(&grp_file_path).map_err(|e| {
+                    messages::get(
+                        "open-failed",
+                        &[("file", &grp_file_path.display()), ("error", &e)],
+                    )
+                })?;
+                let mut grp_reader = GrpFileReader::new(&file)?;
+                for entry in grp_reader.get_file_entries()? {
+                    let name = entry.name().to_string();
+                    if query.matches_entry(&mut grp_reader, &entry)?
+                        && !entry_names.iter().any(|n| n.eq_ignore_ascii_case(&name))
+                    {
+                        entry_names.push(name);
+                    }
+                }
+                if entry_names.is_empty() {
+                    println!("{}", messages::get("no-matching-entries", &[]));
+                    return Ok(());
+                }
+            }
+            let output_dir = output_dir.map(|d| curr_dir.join(d));
+            let mut session = edit_session(
+                &grp_file_path,
+                output_dir.as_deref(),
+                &format!("grp-remove --entry {}", entry_names.join(" --entry ")),
+            )?;
+            for entry_name in &entry_names {
+                session.remove(entry_name);
+            }
+            session.apply()?;
+            println!(
+                "{}",
+                messages::get("entries-removed", &[("entries", &entry_names.join(", "))])
+            );
+        }
+        "grp-status" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut dir_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--dir" => {
+                        dir_path = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(dir_path)) = (grp_file_path, dir_path) else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let file = File::open(curr_dir.join(&grp_file_path)).map_err(|e| {
+                messages::get("open-failed", &[("file", &grp_file_path), ("error", &e)])
+            })?;
+            let mut grp_reader = GrpFileReader::new(&file)?;
+
+            let statuses = dir_status::compare_with_dir(&mut grp_reader, &curr_dir.join(dir_path))?;
+            let mut table = Table::new();
+            for (name, status) in &statuses {
+                let label = match status {
+                    EntryStatus::New => {
+                        Cell::styled(messages::get("status-new", &[]), Style::Green)
+                    }
+                    EntryStatus::Modified => {
+                        Cell::styled(messages::get("status-modified", &[]), Style::Yellow)
+                    }
+                    EntryStatus::Missing => {
+                        Cell::styled(messages::get("status-missing", &[]), Style::Red)
+                    }
+                    EntryStatus::Unchanged => continue,
+                };
+                table.row([label, name.as_str().into()]);
+            }
+            if table.is_empty() {
+                println!("{}", messages::get("no-differences", &[]));
+            }
+            table.print();
+        }
+        "grp-overlay" => {
+            let mut base_file_path: Option<String> = None;
+            let mut dir_path: Option<String> = None;
+            let mut output_file_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--base" => {
+                        base_file_path = Some(value);
+                    }
+                    "--dir" => {
+                        dir_path = Some(value);
+                    }
+                    "--output" => {
+                        output_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(base_file_path), Some(dir_path), Some(output_file_path)) =
+                (base_file_path, dir_path, output_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let file = File::open(curr_dir.join(&base_file_path)).map_err(|e| {
+                messages::get("open-failed", &[("file", &base_file_path), ("error", &e)])
+            })?;
+            let mut grp_reader = GrpFileReader::new(&file)?;
+            let builder = dir_status::overlay(&mut grp_reader, &curr_dir.join(dir_path))?;
+            if builder.is_empty() {
+                println!("{}", messages::get("no-differences", &[]));
+                return Ok(());
+            }
+            builder.write_to_file(&curr_dir.join(&output_file_path))?;
+            println!(
+                "{}",
+                messages::get(
+                    "entries-packed",
+                    &[("count", &builder.len()), ("file", &output_file_path)],
+                )
+            );
+        }
+        "grp-sync" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut dir_path: Option<String> = None;
+            let mut direction: Option<SyncDirection> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--dir" => {
+                        dir_path = Some(value);
+                    }
+                    "--direction" => {
+                        direction = Some(match value.as_str() {
+                            "to-dir" => SyncDirection::ToDir,
+                            "to-grp" => SyncDirection::ToGrp,
+                            _ => {
+                                return Err(messages::get(
+                                    "unknown-direction",
+                                    &[("direction", &value)],
+                                ));
+                            }
+                        });
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(dir_path), Some(direction)) =
+                (grp_file_path, dir_path, direction)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let report = dir_sync::sync(
+                &curr_dir.join(grp_file_path),
+                &curr_dir.join(dir_path),
+                direction,
+            )?;
+            let mut table = Table::new();
+            for name in &report.copied {
+                table.row([
+                    Cell::styled(messages::get("status-copied", &[]), Style::Green),
+                    name.as_str().into(),
+                ]);
+            }
+            for name in &report.removed {
+                table.row([
+                    Cell::styled(messages::get("status-removed", &[]), Style::Red),
+                    name.as_str().into(),
+                ]);
+            }
+            for name in &report.skipped {
+                table.row([
+                    Cell::styled(messages::get("status-skipped", &[]), Style::Yellow),
+                    messages::get("sync-skipped", &[("entry", name)]).into(),
+                ]);
+            }
+            table.print();
+        }
+        "grp-query" => {
+            let mut grp_file_paths: Vec<String> = Vec::new();
+            let mut input_dir: Option<String> = None;
+            let mut recursive = false;
+            let mut query: Option<String> = None;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--input-dir" => {
+                        input_dir = args.next();
+                    }
+                    "--input-file" => {
+                        grp_file_paths.extend(args.next());
+                    }
+                    "--recursive" => {
+                        recursive = parse_switch(&arg, &args.next().unwrap_or_default())?;
+                    }
+                    _ if query.is_none() && !arg.starts_with("--") => {
+                        query = Some(arg);
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(query) = query else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if grp_file_paths.is_empty() && input_dir.is_none() {
+                return Err(messages::get("missing-arguments", &[]));
+            }
+            let query = Query::parse(&query)?;
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_paths = grp_file_paths
+                .iter()
+                .map(|p| curr_dir.join(p))
+                .collect::<Vec<_>>();
+            let input_dir = input_dir.map(|d| curr_dir.join(d));
+            let inputs = inputs::collect(&grp_file_paths, input_dir.as_deref(), recursive)?;
+            let namespaced = inputs.len() > 1 || input_dir.is_some();
+
+            for input in &inputs {
+                let file = File::open(&input.path).map_err(|e| {
+                    messages::get(
+                        "open-failed",
+                        &[("file", &input.path.display()), ("error", &e)],
+                    )
+                })?;
+                let mut grp_reader = GrpFileReader::new(&file)?;
+                if namespaced {
+                    output::heading(&format!("{}:", input.namespace.display()));
+                }
+
+                let mut table = Table::new().right_align(1);
+                for entry in grp_reader.get_file_entries()? {
+                    let (matches, crc) = query.matches_entry_with_crc(&mut grp_reader, &entry)?;
+                    if matches {
+                        let mut row = vec![entry.name().to_string(), entry.size().to_string()];
+                        row.extend(crc.map(|crc| format!("{:08X}", crc)));
+                        table.row(row);
+                    }
+                }
+                table.print();
+            }
+        }
+        "grp-index" => {
+            let mut grp_file_paths: Vec<String> = Vec::new();
+            let mut input_dir: Option<String> = None;
+            let mut recursive = false;
+            let mut text = false;
+
+            while let Some(option) = args.next() {
+                let Some(value) = args.next() else {
+                    return Err(messages::get("missing-value", &[("option", &option)]));
+                };
+                match option.as_str() {
+                    "--input-dir" => {
+                        input_dir = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_paths.push(value);
+                    }
+                    "--recursive" => {
+                        recursive = parse_switch(&option, &value)?;
+                    }
+                    "--text" => {
+                        text = parse_switch(&option, &value)?;
+                    }
+                    _ => {}
+                }
+            }
+
+            if grp_file_paths.is_empty() && input_dir.is_none() {
+                return Err(messages::get("missing-arguments", &[]));
+            }
+            // Text is the only kind of index so far, but it is named so that
+            // scripts keep working once there are others.
+            if !text {
+                return Err(messages::get("text-index-only", &[]));
+            }
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_paths = grp_file_paths
+                .iter()
+                .map(|p| curr_dir.join(p))
+                .collect::<Vec<_>>();
+            let input_dir = input_dir.map(|d| curr_dir.join(d));
+            let inputs = inputs::collect(&grp_file_paths, input_dir.as_deref(), recursive)?;
+
+            let mut entry_count = 0;
+            for input in &inputs {
+                // The stamp is taken first, so that an archive changed while it
+                // is indexed leaves a stale index.
+                let stamp = text_index::ArchiveStamp::of(&input.path)?;
+                let file = File::open(&input.path).map_err(|e| {
+                    messages::get(
+                        "open-failed",
+                        &[("file", &input.path.display()), ("error", &e)],
+                    )
+                })?;
+                let index = text_index::TextIndex::build(&mut GrpFileReader::new(&file)?, stamp)?;
+                entry_count += index.entries.len();
+
+                let index_path = text_index::sidecar_path(&input.path);
+                let mut output_file = TempFile::new(&index_path)?;
+                output_file
+                    .file()
+                    .write_all(&index.to_bytes())
+                    .map_err(|e| {
+                        messages::get(
+                            "write-failed",
+                            &[("file", &index_path.display()), ("error", &e)],
+                        )
+                    })?;
+                output_file.persist()?;
+            }
+            println!(
+                "{}",
+                messages::get(
+                    "text-indexed",
+                    &[("count", &entry_count), ("archives", &inputs.len())],
+                )
+            );
+        }
+        "grp-grep" => {
+            let mut grp_file_paths: Vec<String> = Vec::new();
+            let mut input_dir: Option<String> = None;
+            let mut recursive = false;
+            let mut pattern: Option<String> = None;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--input-dir" => {
+                        input_dir = args.next();
+                    }
+                    "--input-file" => {
+                        grp_file_paths.extend(args.next());
+                    }
+                    "--recursive" => {
+                        recursive = parse_switch(&arg, &args.next().unwrap_or_default())?;
+                    }
+                    _ if pattern.is_none() && !arg.starts_with("--") => {
+                        pattern = Some(arg);
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(pattern) = pattern else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if grp_file_paths.is_empty() && input_dir.is_none() {
+                return Err(messages::get("missing-arguments", &[]));
+            }
+            let pattern = cp437::encode(&pattern)?;
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_paths = grp_file_paths
+                .iter()
+                .map(|p| curr_dir.join(p))
+                .collect::<Vec<_>>();
+            let input_dir = input_dir.map(|d| curr_dir.join(d));
+            let inputs = inputs::collect(&grp_file_paths, input_dir.as_deref(), recursive)?;
+            let namespaced = inputs.len() > 1 || input_dir.is_some();
+
+            for input in &inputs {
+                let file = File::open(&input.path).map_err(|e| {
+                    messages::get(
+                        "open-failed",
+                        &[("file", &input.path.display()), ("error", &e)],
+                    )
+                })?;
+                let mut grp_reader = GrpFileReader::new(&file)?;
+                // With an up-to-date index of grp-index, only the entries that
+                // contain every trigram of the pattern are read.
+                let mut entries = grp_reader.get_file_entries()?;
+                if let Some(index) = text_index::read_sidecar(&input.path)? {
+                    let candidates = index.candidates(&pattern);
+                    entries.retain(|e| candidates.contains(&e.name()));
+                }
+                let prefix = if namespaced {
+                    format!("{}/", input.namespace.display())
+                } else {
+                    String::new()
+                };
+
+                for entry in entries {
+                    let data = grp_reader.read_file(&entry)?;
+                    if !text_index::is_text(&data) {
+                        continue;
+                    }
+                    for (line_number, line) in text_index::matching_lines(&data, &pattern) {
+                        println!(
+                            "{}{}:{}:{}",
+                            prefix,
+                            fs_name::to_fs_name(&entry.name()),
+                            line_number,
+                            cp437::decode(line)
+                        );
+                    }
+                }
+            }
+        }
+        "grp-apply" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut edits_file_path: Option<String> = None;
+            let mut output_dir: Option<String> = None;
+
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--input-file" => {
+                        grp_file_path = args.next();
+                    }
+                    "--output-dir" => {
+                        output_dir = args.next();
+                    }
+                    _ if edits_file_path.is_none() && !arg.starts_with("--") => {
+                        edits_file_path = Some(arg);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(edits_file_path)) = (grp_file_path, edits_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let edits_file_path = curr_dir.join(edits_file_path);
+            let edits_source = fs::read_to_string(&edits_file_path).map_err(|e| {
+                messages::get(
+                    "read-failed",
+                    &[("file", &edits_file_path.display()), ("error", &e)],
+                )
+            })?;
+            // Paths in the edits file are relative to the edits file itself.
+            let edits_dir = edits_file_path.parent().unwrap_or(&curr_dir);
+
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let output_dir = output_dir.map(|d| curr_dir.join(d));
+            let mut session = edit_session(
+                &grp_file_path,
+                output_dir.as_deref(),
+                &format!("grp-apply {}", edits_file_path.display()),
+            )?;
+            // Where the added and replaced entries come from is recorded
+            // once the edits are applied, with the digests of the written
+            // entries. Removed entries are recorded as `None`.
+            let mut provenance =
+                provenance::ProvenanceFile::read_for(&grp_file_path, session.target())?;
+            let mut records = Vec::new();
+            let mut summary = Table::new();
+            for table in toml_subset::parse(&edits_source)? {
+                if table.name.is_empty() && table.values.is_empty() {
+                    continue;
+                }
+                let name = table.require_str("name")?;
+                let data = || -> Result<(EntryData, provenance::Provenance), String> {
+                    let path = edits_dir.join(table.require_str("path")?);
+                    let record = provenance::Provenance {
+                        name: name.to_string(),
+                        source: path.display().to_string(),
+                        sha256: String::new(),
+                        converter: table.get_str("converter")?.unwrap_or("copy").to_string(),
+                        tool: duke3d_file_utility::TOOL.to_string(),
+                    };
+                    Ok((EntryData::Path(path), record))
+                };
+                match (table.name.as_str(), table.is_array_item) {
+                    ("add", true) => {
+                        let (data, record) = data()?;
+                        records.push((name.to_string(), Some(record)));
+                        session.add(name, data)
+                    }
+                    ("replace", true) => {
+                        let (data, record) = data()?;
+                        records.push((name.to_string(), Some(record)));
+                        session.replace(name, data)
+                    }
+                    ("remove", true) => {
+                        records.push((name.to_string(), None));
+                        session.remove(name)
+                    }
+                    _ => {
+                        return Err(messages::get("unknown-edit", &[("edit", &table.name)]));
+                    }
+                };
+                summary.row([table.name.as_str(), name]);
+            }
+            summary.print();
+            let target_provenance_path = provenance::sidecar_path(session.target());
+            let digests = session.apply_with_digests()?;
+            for (name, record) in records {
+                match record {
+                    Some(mut record) => {
+                        // Entries that a later edit removed were not written.
+                        let Some((_, sha256)) = digests.iter().find(|(n, _)| *n == name.as_str())
+                        else {
+                            continue;
+                        };
+                        record.sha256 = verify::to_hex(sha256);
+                        provenance.record(record);
+                    }
+                    None => provenance.remove(&name),
+                }
+            }
+            provenance.write(&target_provenance_path)?;
+        }
+        "provenance" => {
+            let action = args.next();
+            let entry_name = args.next();
+            let mut grp_file_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                if option == "--input-file" {
+                    grp_file_path = Some(value);
+                }
+            }
+
+            let (Some(action), Some(entry_name), Some(grp_file_path)) =
+                (action, entry_name, grp_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if action != "show" {
+                return Err(messages::get(
+                    "unknown-action",
+                    &[("action", &action), ("actions", &"show")],
+                ));
+            }
+            let curr_dir = std::env::current_dir().unwrap();
+            let provenance_path = provenance::sidecar_path(&curr_dir.join(grp_file_path));
+            let provenance = provenance::ProvenanceFile::read(&provenance_path)?;
+            let Some(record) = provenance.get(&entry_name) else {
+                return Err(messages::get(
+                    "provenance-missing",
+                    &[("entry", &entry_name), ("file", &provenance_path.display())],
+                ));
+            };
+            let mut table = Table::new();
+            for (label, value) in [
+                ("provenance-entry", &record.name),
+                ("provenance-source", &record.source),
+                ("provenance-sha256", &record.sha256),
+                ("provenance-converter", &record.converter),
+                ("provenance-tool", &record.tool),
+            ] {
+                table.row([messages::get(label, &[]), value.clone()]);
+            }
+            table.print();
+        }
+        "grp-undo" => {
+            let mut grp_file_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                if option == "--input-file" {
+                    grp_file_path = Some(value);
+                }
+            }
+
+            let Some(grp_file_path) = grp_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            match grp::undo::undo_last(&curr_dir.join(grp_file_path))? {
+                Some(description) => println!(
+                    "{}",
+                    messages::get("undo-reverted", &[("description", &description)])
+                ),
+                None => println!("{}", messages::get("undo-nothing", &[])),
+            }
+        }
+        "grp-hexdump" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut entry_file_name: Option<String> = None;
+            let mut offset = 0;
+            let mut length = 256;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_file_name = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--len" => {
+                        length = parse_offset(&value)?;
+                    }
+                    "--offset" => {
+                        offset = parse_offset(&value)?;
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(entry_file_name)) = (grp_file_path, entry_file_name)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let file = File::open(curr_dir.join(&grp_file_path)).map_err(|e| {
+                messages::get("open-failed", &[("file", &grp_file_path), ("error", &e)])
+            })?;
+            let mut grp_reader = GrpFileReader::new(&file)?;
+            let Some(mut entry_reader) = grp_reader.open_entry(&entry_file_name)? else {
+                return Err(messages::get(
+                    "entry-not-found",
+                    &[("entry", &entry_file_name)],
+                ));
+            };
+            // Only the dumped range is read from the archive.
+            let mut data = Vec::new();
+            entry_reader
+                .seek(SeekFrom::Start(offset))
+                .and_then(|_| entry_reader.take(length).read_to_end(&mut data))
+                .map_err(|e| {
+                    messages::get("read-failed", &[("file", &entry_file_name), ("error", &e)])
+                })?;
+            print!("{}", hexdump::format(&data, offset));
+        }
+        "text-view" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut entry_file_name: Option<String> = None;
+            let mut output_file_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_file_name = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(entry_file_name)) = (grp_file_path, entry_file_name)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let file = File::open(curr_dir.join(&grp_file_path)).map_err(|e| {
+                messages::get("open-failed", &[("file", &grp_file_path), ("error", &e)])
+            })?;
+            let mut grp_reader = GrpFileReader::new(&file)?;
+            let Some(entry) = grp_reader.find_file_entry(&entry_file_name)? else {
+                return Err(messages::get(
+                    "entry-not-found",
+                    &[("entry", &entry_file_name)],
+                ));
+            };
+            let text = cp437::decode(&grp_reader.read_file(&entry)?);
+
+            match output_file_path {
+                Some(output_file_path) => fs::write(curr_dir.join(&output_file_path), text)
+                    .map_err(|e| {
+                        messages::get(
+                            "write-failed",
+                            &[("file", &output_file_path), ("error", &e)],
+                        )
+                    })?,
+                None => print!("{}", text),
+            }
+        }
+        "text-write" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut entry_file_name: Option<String> = None;
+            let mut text_file_path: Option<String> = None;
+            let mut output_dir: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_file_name = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--output-dir" => {
+                        output_dir = Some(value);
+                    }
+                    "--text-file" => {
+                        text_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(entry_file_name), Some(text_file_path)) =
+                (grp_file_path, entry_file_name, text_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let text = fs::read_to_string(curr_dir.join(&text_file_path)).map_err(|e| {
+                messages::get("read-failed", &[("file", &text_file_path), ("error", &e)])
+            })?;
+            let bytes = cp437::encode(&text)?;
+
+            let output_dir = output_dir.map(|d| curr_dir.join(d));
+            let mut session = edit_session(
+                &curr_dir.join(grp_file_path),
+                output_dir.as_deref(),
+                &format!("text-write --entry {}", entry_file_name),
+            )?;
+            session.replace(&entry_file_name, EntryData::Bytes(bytes));
+            session.apply()?;
+        }
+        "con-defines" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut entry_file_name = game::active().main_script.to_string();
+            let mut output_file_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_file_name = value;
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(grp_file_path) = grp_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let file = File::open(curr_dir.join(&grp_file_path)).map_err(|e| {
+                messages::get("open-failed", &[("file", &grp_file_path), ("error", &e)])
+            })?;
+            let mut grp_reader = GrpFileReader::new(&file)?;
+
+            let defines = con::defines::collect_defines(&entry_file_name, |name| match grp_reader
+                .find_file_entry(name)?
+            {
+                Some(entry) => grp_reader.read_file(&entry).map(Some),
+                None => Ok(None),
+            })?;
+            let conflicts = con::defines::find_conflicts(&defines);
+            for (name, sources) in &conflicts {
+                println!("{}", messages::get("define-conflict", &[("name", name)]));
+                for source in sources {
+                    println!(
+                        "  {}:{}: {}",
+                        source.file, source.define.line, source.define.value
+                    );
+                }
+            }
+
+            if let Some(output_file_path) = output_file_path {
+                fs::write(
+                    curr_dir.join(&output_file_path),
+                    con::defines::canonical_block(&defines),
+                )
+                .map_err(|e| {
+                    messages::get(
+                        "write-failed",
+                        &[("file", &output_file_path), ("error", &e)],
+                    )
+                })?;
+            }
+
+            if !conflicts.is_empty() {
+                return Err(messages::get(
+                    "defines-conflicting",
+                    &[("count", &conflicts.len())],
+                ));
+            }
+            println!(
+                "{}",
+                messages::get("defines-checked", &[("count", &defines.len())])
+            );
+        }
+        "rename-asset" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut from_name: Option<String> = None;
+            let mut to_name: Option<String> = None;
+            let mut output_dir: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--from" => {
+                        from_name = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--output-dir" => {
+                        output_dir = Some(value);
+                    }
+                    "--to" => {
+                        to_name = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(from_name), Some(to_name)) =
+                (grp_file_path, from_name, to_name)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let to_name = grp::EntryName::new(&to_name)?.to_string();
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let mut grp_reader = GrpFileReader::new(&file)?;
+            if grp_reader.find_file_entry(&from_name)?.is_none() {
+                return Err(messages::get("entry-not-found", &[("entry", &from_name)]));
+            }
+
+            let output_dir = output_dir.map(|d| curr_dir.join(d));
+            let mut session = edit_session(
+                &grp_file_path,
+                output_dir.as_deref(),
+                &format!("rename-asset --from {} --to {}", from_name, to_name),
+            )?;
+
+            // Rewrite the references in every script before renaming,
+            // so that a renamed script itself is rewritten too.
+            for entry in grp_reader.get_file_entries()? {
+                let name = entry.name().to_string();
+                if !game::active().is_con(&name) {
+                    continue;
+                }
+                let source = grp_reader.read_file(&entry)?;
+                let (rewritten, count) = con::replace_tokens(&source, |token| {
+                    token
+                        .eq_ignore_ascii_case(from_name.as_bytes())
+                        .then(|| to_name.as_bytes().to_vec())
+                });
+                if count > 0 {
+                    println!(
+                        "{}",
+                        messages::get("references", &[("name", &name), ("count", &count)])
+                    );
+                    session.replace(&name, EntryData::Bytes(rewritten));
+                }
+            }
+            drop(grp_reader);
+            drop(file);
+
+            session.rename(&from_name, &to_name);
+            session.apply()?;
+            println!(
+                "{}",
+                messages::get("asset-renamed", &[("from", &from_name), ("to", &to_name)])
+            );
+        }
+        "retile" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut from_tile: Option<String> = None;
+            let mut to_tile: Option<String> = None;
+            let game = game::active();
+            let mut patterns = format!("*.{},*.{}", game.map_extension, game.con_extension);
+            let mut output_dir: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--from" => {
+                        from_tile = Some(value);
+                    }
+                    "--in" => {
+                        patterns = value;
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--output-dir" => {
+                        output_dir = Some(value);
+                    }
+                    "--to" => {
+                        to_tile = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(from_tile), Some(to_tile)) =
+                (grp_file_path, from_tile, to_tile)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let parse_tile = |tile: &str| {
+                tile.parse::<i16>()
+                    .ok()
+                    .filter(|t| *t >= 0)
+                    .ok_or_else(|| messages::get("invalid-tile-number", &[("tile", &tile)]))
+            };
+            let (from_tile, to_tile) = (parse_tile(&from_tile)?, parse_tile(&to_tile)?);
+            let tiles = BTreeMap::from([(from_tile, to_tile)]);
+            let patterns = patterns.split(',').map(str::trim).collect::<Vec<_>>();
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let mut grp_reader = GrpFileReader::new(&file)?;
+
+            let output_dir = output_dir.map(|d| curr_dir.join(d));
+            let mut session = edit_session(
+                &grp_file_path,
+                output_dir.as_deref(),
+                &format!("retile --from {} --to {}", from_tile, to_tile),
+            )?;
+
+            let tile_names = retile::tile_names(&mut grp_reader)?;
+            for entry in grp_reader.get_file_entries()? {
+                let name = entry.name().to_string();
+                if !patterns
+                    .iter()
+                    .any(|p| glob_matches(p.as_bytes(), name.as_bytes()))
+                {
+                    continue;
+                }
+                let mut data = grp_reader.read_file(&entry)?;
+                let replaced = if game.is_map(&name) {
+                    retile::retile_map(&mut data, &tiles).map_err(|e| {
+                        messages::get("read-failed", &[("file", &name), ("error", &e)])
+                    })?
+                } else if game.is_con(&name) {
+                    let (rewritten, replaced) = retile::retile_con(&data, &tiles, &tile_names);
+                    data = rewritten;
+                    replaced
+                } else {
+                    return Err(messages::get("retile-unsupported", &[("entry", &name)]));
+                };
+                if replaced > 0 {
+                    println!(
+                        "{}",
+                        messages::get("references", &[("name", &name), ("count", &replaced)])
+                    );
+                    session.replace(&name, EntryData::Bytes(data));
+                }
+            }
+            drop(grp_reader);
+            drop(file);
+
+            if session.is_empty() {
+                println!(
+                    "{}",
+                    messages::get("no-tile-references", &[("tile", &from_tile)])
+                );
+            } else {
+                session.apply()?;
+            }
+        }
+        "art-export" => {
+            let mut art_file_path: Option<String> = None;
+            let mut base_palette: Option<String> = None;
+            let mut format = "png".to_string();
+            let mut metadata_file_path: Option<String> = None;
+            let mut output_dir: Option<String> = None;
+            let mut output_file_path: Option<String> = None;
+            let mut palette_file_path: Option<String> = None;
+            let mut state_file_path: Option<String> = None;
+            let mut tile_number: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--base-palette" => {
+                        base_palette = Some(value);
+                    }
+                    "--format" => {
+                        format = value.to_lowercase();
+                    }
+                    "--input-file" => {
+                        art_file_path = Some(value);
+                    }
+                    "--metadata-file" => {
+                        metadata_file_path = Some(value);
+                    }
+                    "--output-dir" => {
+                        output_dir = Some(value);
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    "--palette" => {
+                        palette_file_path = Some(value);
+                    }
+                    "--state-file" => {
+                        state_file_path = Some(value);
+                    }
+                    "--tile" => {
+                        tile_number = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            // Either one tile into --output-file or every tile into --output-dir.
+            let (Some(art_file_path), Some(palette_file_path)) = (art_file_path, palette_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if output_dir.is_none() && (tile_number.is_none() || output_file_path.is_none()) {
+                return Err(messages::get("missing-arguments", &[]));
+            }
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let palette_file_path = curr_dir.join(palette_file_path);
+            let palette_data = fs::read(&palette_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &palette_file_path.display()), ("error", &e)],
+                )
+            })?;
+            // With --base-palette, --palette is LOOKUP.DAT, e.g. to export
+            // tiles as they look under water.
+            let palette = match &base_palette {
+                Some(base_palette) => palette::LookupDat::parse(&palette_data)
+                    .and_then(|lookup| lookup.base_palette(base_palette).cloned())
+                    .map_err(|e| {
+                        messages::get(
+                            "read-failed",
+                            &[("file", &palette_file_path.display()), ("error", &e)],
+                        )
+                    })?,
+                None => palette::Palette::from_palette_dat(&palette_data)?,
+            };
+
+            let art_file_path = curr_dir.join(art_file_path);
+            let file = File::open(&art_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &art_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let mut art_reader = ArtFileReader::new(&file)?;
+            let file_name = |path: &Path| {
+                path.file_name()
+                    .map_or(String::new(), |n| n.to_string_lossy().into_owned())
+            };
+            let mut metadata = tile_metadata::TileMetadata {
+                source: file_name(&art_file_path),
+                palette_file: file_name(&palette_file_path),
+                base_palette,
+                tiles: Vec::new(),
+            };
+
+            if let Some(output_dir) = output_dir {
+                if format != "png" && format != "bmp" {
+                    return Err(messages::get(
+                        "unknown-format",
+                        &[("format", &format), ("formats", &"png, bmp")],
+                    ));
+                }
+                let output_dir = curr_dir.join(output_dir);
+                fs::create_dir_all(&output_dir).map_err(|e| {
+                    messages::get(
+                        "create-failed",
+                        &[("file", &output_dir.display()), ("error", &e)],
+                    )
+                })?;
+                let state_file_path = state_file_path.map(|p| curr_dir.join(p));
+                let mut state = match &state_file_path {
+                    Some(state_file_path) => export_state::ExportState::read(state_file_path)?,
+                    None => Default::default(),
+                };
+
+                let (mut exported, mut unchanged) = (0, 0);
+                for tile in art_reader.read_tiles()? {
+                    if tile.pixel_count() == 0 {
+                        continue;
+                    }
+                    let pixels = art_reader.read_pixels(&tile)?;
+                    let hash = export_state::tile_hash(&palette, &tile, &pixels, &format);
+                    let image_file_name = format!("TILE{}.{}", tile.number(), format);
+                    let output_file_path = output_dir.join(&image_file_name);
+                    metadata.tiles.push((tile.clone(), image_file_name));
+                    if state.is_unchanged(tile.number(), &hash) && output_file_path.exists() {
+                        unchanged += 1;
+                        continue;
+                    }
+                    let rgba = tile.to_rgba(&pixels, &palette);
+                    let (width, height) = (tile.width() as u32, tile.height() as u32);
+                    let image = if format == "png" {
+                        image::encode_png(width, height, &rgba)
+                    } else {
+                        image::encode_bmp(width, height, &rgba)
+                    };
+                    let mut output_file = TempFile::new(&output_file_path)?;
+                    output_file.file().write_all(&image).map_err(|e| {
+                        messages::get(
+                            "write-failed",
+                            &[("file", &output_file_path.display()), ("error", &e)],
+                        )
+                    })?;
+                    output_file.persist()?;
+                    state.record(tile.number(), hash);
+                    exported += 1;
+                }
+                // The metadata lists every tile, also the unchanged ones, under
+                // the name of the .art file, e.g. TILES000.json.
+                let metadata_file_path = output_dir.join(
+                    art_file_path
+                        .with_extension("json")
+                        .file_name()
+                        .unwrap_or_default(),
+                );
+                write_metadata(&metadata_file_path, &metadata)?;
+                if let Some(state_file_path) = state_file_path {
+                    state.write(&state_file_path)?;
+                }
+                println!(
+                    "{}",
+                    messages::get(
+                        "tiles-exported",
+                        &[
+                            ("exported", &exported.to_string()),
+                            ("unchanged", &unchanged.to_string())
+                        ]
+                    )
+                );
+                return Ok(());
+            }
+
+            let (Some(tile_number), Some(output_file_path)) = (tile_number, output_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let tile_number = tile_number
+                .parse::<u32>()
+                .map_err(|_| messages::get("invalid-tile-number", &[("tile", &tile_number)]))?;
+            let tile = art_reader.get_tile(tile_number)?;
+            if tile.pixel_count() == 0 {
+                return Err(messages::get("tile-empty", &[("tile", &tile_number)]));
+            }
+            let rgba = tile.to_rgba(&art_reader.read_pixels(&tile)?, &palette);
+            let (width, height) = (tile.width() as u32, tile.height() as u32);
+
+            let output_file_path = curr_dir.join(output_file_path);
+            let image = match output_file_path.extension() {
+                Some(e) if e.eq_ignore_ascii_case("png") => image::encode_png(width, height, &rgba),
+                Some(e) if e.eq_ignore_ascii_case("bmp") => image::encode_bmp(width, height, &rgba),
+                _ => return Err(messages::get("image-extension", &[])),
+            };
+            let mut output_file = TempFile::new(&output_file_path)?;
+            output_file.file().write_all(&image).map_err(|e| {
+                messages::get(
+                    "write-failed",
+                    &[("file", &output_file_path.display()), ("error", &e)],
+                )
+            })?;
+            output_file.persist()?;
+            if let Some(metadata_file_path) = metadata_file_path {
+                metadata
+                    .tiles
+                    .push((tile.clone(), file_name(&output_file_path)));
+                write_metadata(&curr_dir.join(metadata_file_path), &metadata)?;
+            }
+
+            let animation = tile.animation();
+            println!(
+                "{}",
+                messages::get(
+                    "tile-size",
+                    &[
+                        ("tile", &tile_number),
+                        ("width", &width),
+                        ("height", &height)
+                    ]
+                )
+            );
+            println!(
+                "{}",
+                messages::get(
+                    "tile-offset",
+                    &[("x", &animation.x_offset), ("y", &animation.y_offset)]
+                )
+            );
+            if animation.frames > 0 {
+                println!(
+                    "{}",
+                    messages::get(
+                        "tile-animation",
+                        &[
+                            ("type", &format!("{:?}", animation.animation_type)),
+                            ("frames", &animation.frames),
+                            ("speed", &animation.speed)
+                        ]
+                    )
+                );
+            }
+        }
+        "art-renumber" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut entry_name: Option<String> = None;
+            let mut base: Option<String> = None;
+            let mut apply = false;
+            let mut output_dir: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--base" => {
+                        base = Some(value);
+                    }
+                    "--entry" => {
+                        entry_name = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--mode" => {
+                        apply = match value.as_str() {
+                            "apply" => true,
+                            "plan" => false,
+                            _ => return Err(messages::get("unknown-mode", &[("mode", &value)])),
+                        };
+                    }
+                    "--output-dir" => {
+                        output_dir = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(entry_name), Some(base)) =
+                (grp_file_path, entry_name, base)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let base = base
+                .parse::<u32>()
+                .map_err(|_| messages::get("invalid-tile-number", &[("tile", &base)]))?;
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let mut grp_reader = GrpFileReader::new(&file)?;
+            let Some(art_entry) = grp_reader.find_file_entry(&entry_name)? else {
+                return Err(messages::get("entry-not-found", &[("entry", &entry_name)]));
+            };
+            let art_name = art_entry.name().to_string();
+            let mut art_data = grp_reader.read_file(&art_entry)?;
+            let (first, last) = art::renumber(&mut art_data, base)
+                .map_err(|e| messages::get("read-failed", &[("file", &art_name), ("error", &e)]))?;
+            let new_last = base + (last - first);
+
+            // Tile numbers are stored as 16-bit signed integers in maps.
+            let tiles = (first..=last)
+                .map(|tile| {
+                    let old = i16::try_from(tile).ok()?;
+                    let new = i16::try_from(base + (tile - first)).ok()?;
+                    Some((old, new))
+                })
+                .collect::<Option<BTreeMap<_, _>>>()
+                .ok_or_else(|| messages::get("tile-number-too-large", &[("max", &i16::MAX)]))?;
+
+            let entries = grp_reader.get_file_entries()?;
+            for entry in &entries {
+                let name = entry.name().to_string();
+                if name == art_name || !game::active().is_art(&name) {
+                    continue;
+                }
+                let (other_first, other_last) = art::read_tile_range(&grp_reader.read_file(entry)?)
+                    .map_err(|e| messages::get("read-failed", &[("file", &name), ("error", &e)]))?;
+                if base <= other_last && other_first <= new_last {
+                    return Err(messages::get(
+                        "tiles-overlap",
+                        &[
+                            ("first", &base),
+                            ("last", &new_last),
+                            ("other-first", &other_first),
+                            ("other-last", &other_last),
+                            ("file", &name),
+                        ],
+                    ));
+                }
+            }
+
+            println!(
+                "{}",
+                messages::get(
+                    "tiles-renumbered",
+                    &[
+                        ("file", &art_name),
+                        ("first", &first),
+                        ("last", &last),
+                        ("base", &base),
+                        ("new-last", &new_last)
+                    ]
+                )
+            );
+            // A plan only reads the archive, so it takes no lock and
+            // checks nothing about writing it.
+            let output_dir = output_dir.map(|d| curr_dir.join(d));
+            let session = if apply {
+                Some(edit_session(
+                    &grp_file_path,
+                    output_dir.as_deref(),
+                    &format!("art-renumber --entry {} --base {}", art_name, base),
+                )?)
+            } else {
+                None
+            };
+            let mut replacements = vec![(art_name.clone(), art_data)];
+            let tile_names = retile::tile_names(&mut grp_reader)?;
+            for entry in &entries {
+                let name = entry.name().to_string();
+                let (data, replaced) = if game::active().is_map(&name) {
+                    let mut data = grp_reader.read_file(entry)?;
+                    let replaced = retile::retile_map(&mut data, &tiles).map_err(|e| {
+                        messages::get("read-failed", &[("file", &name), ("error", &e)])
+                    })?;
+                    (data, replaced)
+                } else if game::active().is_con(&name) {
+                    retile::retile_con(&grp_reader.read_file(entry)?, &tiles, &tile_names)
+                } else {
+                    continue;
+                };
+                if replaced > 0 {
+                    println!(
+                        "{}",
+                        messages::get("references", &[("name", &name), ("count", &replaced)])
+                    );
+                    replacements.push((name, data));
+                }
+            }
+            drop(grp_reader);
+            drop(file);
+
+            if let Some(mut session) = session {
+                for (name, data) in replacements {
+                    session.replace(&name, EntryData::Bytes(data));
+                }
+                session.apply()?;
+            } else {
+                println!("{}", messages::get("plan-not-applied", &[]));
+            }
+        }
+        "palette-diff" => {
+            let palette_file_paths = args.filter(|a| !a.starts_with("--")).collect::<Vec<_>>();
+
+            let [old_file_path, new_file_path] = palette_file_paths.as_slice() else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let old_palette = read_palette(&curr_dir.join(old_file_path))?;
+            let new_palette = read_palette(&curr_dir.join(new_file_path))?;
+
+            let changed = old_palette.changed_indices(&new_palette);
+            if changed.is_empty() {
+                println!("{}", messages::get("no-differences", &[]));
+                return Ok(());
+            }
+            let hex = |[r, g, b]: [u8; 3]| format!("#{:02X}{:02X}{:02X}", r, g, b);
+            let mut table = Table::new()
+                .header(&[
+                    &messages::get("column-index", &[]),
+                    old_file_path,
+                    new_file_path,
+                ])
+                .right_align(0);
+            for index in &changed {
+                table.row([
+                    index.to_string(),
+                    hex(old_palette.color(*index)),
+                    hex(new_palette.color(*index)),
+                ]);
+            }
+            table.print();
+            println!(
+                "{}",
+                messages::get("colors-changed", &[("count", &changed.len())])
+            );
+        }
+        "palette-remap" => {
+            let mut art_file_path: Option<String> = None;
+            let mut from_file_path: Option<String> = None;
+            let mut output_file_path: Option<String> = None;
+            let mut to_file_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--art-file" => {
+                        art_file_path = Some(value);
+                    }
+                    "--from" => {
+                        from_file_path = Some(value);
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    "--to" => {
+                        to_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(from_file_path), Some(to_file_path), Some(output_file_path)) =
+                (from_file_path, to_file_path, output_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let from_palette = read_palette(&curr_dir.join(from_file_path))?;
+            let to_palette = read_palette(&curr_dir.join(to_file_path))?;
+            let remap_table = from_palette.remap_table(&to_palette);
+
+            // Without --art-file the table itself is written, one target
+            // index per source index.
+            let output = match art_file_path {
+                Some(art_file_path) => {
+                    let art_file_path = curr_dir.join(art_file_path);
+                    let mut art = fs::read(&art_file_path).map_err(|e| {
+                        messages::get(
+                            "open-failed",
+                            &[("file", &art_file_path.display()), ("error", &e)],
+                        )
+                    })?;
+                    let changed = art::remap_pixels(&mut art, &remap_table).map_err(|e| {
+                        messages::get(
+                            "read-failed",
+                            &[("file", &art_file_path.display()), ("error", &e)],
+                        )
+                    })?;
+                    println!(
+                        "{}",
+                        messages::get("pixels-remapped", &[("count", &changed)])
+                    );
+                    art
+                }
+                None => {
+                    let moved = (0..=u8::MAX)
+                        .zip(remap_table)
+                        .filter(|(from, to)| from != to)
+                        .count();
+                    println!("{}", messages::get("colors-remapped", &[("count", &moved)]));
+                    remap_table.to_vec()
+                }
+            };
+            let output_file_path = curr_dir.join(output_file_path);
+            let mut output_file = TempFile::new(&output_file_path)?;
+            output_file.file().write_all(&output).map_err(|e| {
+                messages::get(
+                    "write-failed",
+                    &[("file", &output_file_path.display()), ("error", &e)],
+                )
+            })?;
+            output_file.persist()?;
+        }
+        "palette-translucency" => {
+            let mut palette_file_path: Option<String> = None;
+            let mut output_file_path: Option<String> = None;
+            let mut preview_file_path: Option<String> = None;
+            let mut ratio: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--input-file" => {
+                        palette_file_path = Some(value);
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    "--preview-file" => {
+                        preview_file_path = Some(value);
+                    }
+                    "--ratio" => {
+                        ratio = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(palette_file_path), Some(ratio)) = (palette_file_path, ratio) else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if output_file_path.is_none() && preview_file_path.is_none() {
+                return Err(messages::get("missing-arguments", &[]));
+            }
+            let ratio = ratio
+                .parse::<u8>()
+                .map_err(|_| messages::get("invalid-number", &[("number", &ratio)]))?;
+            let curr_dir = std::env::current_dir().unwrap();
+            let palette_file_path = curr_dir.join(palette_file_path);
+            let mut palette_dat = fs::read(&palette_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &palette_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let palette = palette::Palette::from_palette_dat(&palette_dat).map_err(|e| {
+                messages::get(
+                    "read-failed",
+                    &[("file", &palette_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let table = palette.translucency_table(ratio)?;
+
+            // The preview has a row per foreground and a column per
+            // background color, showing the color of their blend.
+            let mut outputs = Vec::new();
+            if let Some(preview_file_path) = preview_file_path {
+                let rgba = palette.to_rgba(&table, false);
+                outputs.push((preview_file_path, image::encode_png(256, 256, &rgba)));
+            }
+            if let Some(output_file_path) = output_file_path {
+                palette::write_translucency_table(&mut palette_dat, &table).map_err(|e| {
+                    messages::get(
+                        "read-failed",
+                        &[("file", &palette_file_path.display()), ("error", &e)],
+                    )
+                })?;
+                outputs.push((output_file_path, palette_dat));
+            }
+            for (output_file_path, data) in outputs {
+                let output_file_path = curr_dir.join(output_file_path);
+                let mut output_file = TempFile::new(&output_file_path)?;
+                output_file.file().write_all(&data).map_err(|e| {
+                    messages::get(
+                        "write-failed",
+                        &[("file", &output_file_path.display()), ("error", &e)],
+                    )
+                })?;
+                output_file.persist()?;
+            }
+        }
+        "map-info" => {
+            let mut input_file_path: Option<String> = None;
+            let mut entry_name: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_name = Some(value);
+                    }
+                    "--input-file" => {
+                        input_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(input_file_path) = input_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let map = read_map(&curr_dir.join(input_file_path), entry_name.as_deref())?;
+
+            let start = &map.player_start;
+            println!(
+                "{}",
+                messages::get(
+                    "map-player-start",
+                    &[
+                        ("x", &start.x),
+                        ("y", &start.y),
+                        ("z", &start.z),
+                        ("angle", &start.angle),
+                        ("sector", &start.sector)
+                    ]
+                )
+            );
+            println!(
+                "{}",
+                messages::get("map-sectors", &[("count", &map.sectors.len())])
+            );
+            println!(
+                "{}",
+                messages::get("map-walls", &[("count", &map.walls.len())])
+            );
+            println!(
+                "{}",
+                messages::get("map-sprites", &[("count", &map.sprites.len())])
+            );
+
+            let mut sprites_by_picnum = BTreeMap::<i16, usize>::new();
+            for sprite in &map.sprites {
+                *sprites_by_picnum.entry(sprite.picnum).or_default() += 1;
+            }
+            for (picnum, count) in sprites_by_picnum {
+                println!(
+                    "  {}",
+                    messages::get("picnum-count", &[("picnum", &picnum), ("count", &count)])
+                );
+            }
+        }
+        "tileset-stats" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut top = 10;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--top" => {
+                        top = value
+                            .parse::<usize>()
+                            .map_err(|_| messages::get("invalid-number", &[("number", &value)]))?;
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(grp_file_path) = grp_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let tile_set = read_tile_set(&mut GrpFileReader::new(&file)?)?;
+
+            let tiles = tile_set.tiles().collect::<Vec<_>>();
+            println!(
+                "{}",
+                messages::get("tiles-count", &[("count", &tiles.len())])
+            );
+            let non_empty = tiles.iter().filter(|t| t.pixel_count() > 0).count();
+            println!(
+                "{}",
+                messages::get("tiles-non-empty", &[("count", &non_empty)])
+            );
+            let pixel_bytes = tiles.iter().map(|t| t.pixel_count()).sum::<usize>();
+            println!(
+                "{}",
+                messages::get("tiles-pixel-bytes", &[("count", &pixel_bytes)])
+            );
+
+            output::heading(&messages::get("heading-files", &[]));
+            for file in tile_set.files() {
+                println!(
+                    "  {}: {}-{}",
+                    file.name,
+                    file.first_tile_number(),
+                    file.last_tile_number()
+                );
+            }
+            output::heading(&messages::get("heading-gaps", &[]));
+            for (first, last) in tile_set.gaps() {
+                println!("  {}-{}", first, last);
+            }
+
+            let mut largest = tiles;
+            largest.sort_by_key(|t| std::cmp::Reverse(t.pixel_count()));
+            output::heading(&messages::get("heading-largest-tiles", &[]));
+            for tile in largest.iter().take(top).filter(|t| t.pixel_count() > 0) {
+                println!(
+                    "  {}",
+                    messages::get(
+                        "tile-pixels",
+                        &[
+                            ("tile", &tile.number()),
+                            ("width", &tile.width()),
+                            ("height", &tile.height()),
+                            ("count", &tile.pixel_count())
+                        ]
+                    )
+                );
+            }
+        }
+        "stats" => {
+            let mut format = "text".to_string();
+            let mut grp_file_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--format" => {
+                        format = value;
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(grp_file_path) = grp_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if format != "text" && format != "json" {
+                return Err(messages::get(
+                    "unknown-format",
+                    &[("format", &format), ("formats", &"text, json")],
+                ));
+            }
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let stats = stats::GameStats::collect(&mut GrpFileReader::new(&file)?)?;
+            if format == "json" {
+                println!("{}", stats.to_json());
+                return Ok(());
+            }
+
+            println!(
+                "{}",
+                messages::get("stats-entries", &[("count", &stats.entries)])
+            );
+            println!(
+                "{}",
+                messages::get("stats-bytes", &[("count", &stats.bytes)])
+            );
+            output::heading(&messages::get("heading-types", &[]));
+            let mut table = Table::new()
+                .header(&[
+                    &messages::get("column-type", &[]),
+                    &messages::get("column-count", &[]),
+                    &messages::get("column-bytes", &[]),
+                ])
+                .right_align(1)
+                .right_align(2);
+            for (extension, type_stats) in &stats.types {
+                let extension = if extension.is_empty() {
+                    messages::get("no-extension", &[])
+                } else {
+                    extension.to_string()
+                };
+                table.row([
+                    extension,
+                    type_stats.count.to_string(),
+                    type_stats.bytes.to_string(),
+                ]);
+            }
+            table.print();
+            output::heading(&messages::get("heading-tiles", &[]));
+            println!(
+                "  {}",
+                messages::get("tiles-count", &[("count", &stats.tiles.tiles)])
+            );
+            println!(
+                "  {}",
+                messages::get(
+                    "tiles-utilization",
+                    &[
+                        ("count", &stats.tiles.non_empty),
+                        ("limit", &game::active().tile_limit),
+                        ("percent", &format!("{:.1}", stats.tiles.utilization()))
+                    ]
+                )
+            );
+            println!(
+                "  {}",
+                messages::get("tiles-pixel-bytes", &[("count", &stats.tiles.pixel_bytes)])
+            );
+            output::heading(&messages::get("heading-sounds", &[]));
+            println!(
+                "  {}",
+                messages::get("sounds-count", &[("count", &stats.sounds.count)])
+            );
+            println!(
+                "  {}",
+                messages::get(
+                    "sounds-seconds",
+                    &[("seconds", &format!("{:.1}", stats.sounds.seconds))]
+                )
+            );
+            if stats.sounds.unreadable > 0 {
+                println!(
+                    "  {}",
+                    messages::get("sounds-unreadable", &[("count", &stats.sounds.unreadable)])
+                );
+            }
+            output::heading(&messages::get("heading-maps", &[]));
+            for (episode, count) in &stats.episodes {
+                println!(
+                    "  {}",
+                    messages::get("maps-episode", &[("episode", episode), ("count", count)])
+                );
+            }
+            println!(
+                "  {}",
+                messages::get("maps-user", &[("count", &stats.user_maps)])
+            );
+        }
+        "tileset-alloc" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut count: Option<String> = None;
+            let mut contiguous = false;
+            let mut define_prefix: Option<String> = None;
+            let mut reserve_file_path: Option<String> = None;
+            let mut tile_limit = game::active().tile_limit;
+
+            while let Some(option) = args.next() {
+                let Some(value) = args.next() else {
+                    return Err(messages::get("missing-value", &[("option", &option)]));
+                };
+                match option.as_str() {
+                    "--contiguous" => {
+                        contiguous = parse_switch(&option, &value)?;
+                    }
+                    "--count" => {
+                        count = Some(value);
+                    }
+                    "--define-prefix" => {
+                        define_prefix = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--reserve-file" => {
+                        reserve_file_path = Some(value);
+                    }
+                    "--tile-limit" => {
+                        tile_limit = value
+                            .parse::<u32>()
+                            .map_err(|_| messages::get("invalid-number", &[("number", &value)]))?;
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(count)) = (grp_file_path, count) else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let count = count
+                .parse::<u32>()
+                .ok()
+                .filter(|count| *count > 0)
+                .ok_or_else(|| messages::get("invalid-number", &[("number", &count)]))?;
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let tile_set = read_tile_set(&mut GrpFileReader::new(&file)?)?;
+
+            let mut free = tile_set.free_ranges(tile_limit);
+            let reserve_file_path = reserve_file_path.map(|path| curr_dir.join(path));
+            if let Some(reserve_file_path) = &reserve_file_path {
+                free =
+                    tile_alloc::subtract(&free, &tile_alloc::read_reservations(reserve_file_path)?);
+            }
+            let Some(allocated) = tile_alloc::allocate(&free, count, contiguous) else {
+                let id = if contiguous {
+                    "tiles-not-free-contiguous"
+                } else {
+                    "tiles-not-free"
+                };
+                return Err(messages::get(
+                    id,
+                    &[("count", &count), ("limit", &tile_limit)],
+                ));
+            };
+
+            for (first, last) in &allocated {
+                println!(
+                    "{}",
+                    messages::get("tiles-allocated", &[("first", first), ("last", last)])
+                );
+            }
+            if let Some(define_prefix) = define_prefix {
+                let tiles = allocated.iter().flat_map(|(first, last)| *first..=*last);
+                for (i, tile) in tiles.enumerate() {
+                    println!("define {}{} {}", define_prefix, i, tile);
+                }
+            }
+            if let Some(reserve_file_path) = &reserve_file_path {
+                tile_alloc::append_reservations(reserve_file_path, &allocated)?;
+                println!(
+                    "{}",
+                    messages::get("tiles-reserved", &[("file", &reserve_file_path.display())])
+                );
+            }
+        }
+        "map-extract-region" => {
+            let mut input_file_path: Option<String> = None;
+            let mut bounding_box: Option<String> = None;
+            let mut entry_name: Option<String> = None;
+            let mut lotag: Option<String> = None;
+            let mut output_file_path: Option<String> = None;
+            let mut sectors: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--box" => {
+                        bounding_box = Some(value);
+                    }
+                    "--entry" => {
+                        entry_name = Some(value);
+                    }
+                    "--input-file" => {
+                        input_file_path = Some(value);
+                    }
+                    "--lotag" => {
+                        lotag = Some(value);
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    "--sectors" => {
+                        sectors = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(input_file_path), Some(output_file_path)) =
+                (input_file_path, output_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if sectors.is_none() && lotag.is_none() && bounding_box.is_none() {
+                return Err(messages::get("no-sector-filter", &[]));
+            }
+            let sectors = sectors.map(|s| parse_ranges(&s)).transpose()?;
+            let lotag = lotag
+                .map(|l| {
+                    l.parse::<i16>()
+                        .map_err(|_| messages::get("invalid-lotag", &[("lotag", &l)]))
+                })
+                .transpose()?;
+            let bounding_box = match bounding_box.map(|b| parse_numbers::<i32>(&b)).transpose()? {
+                Some(numbers) => match numbers[..] {
+                    [x1, y1, x2, y2] => Some((x1.min(x2), y1.min(y2), x1.max(x2), y1.max(y2))),
+                    _ => return Err(messages::get("invalid-box", &[])),
+                },
+                None => None,
+            };
+
+            let curr_dir = std::env::current_dir().unwrap();
+            let map = read_map(&curr_dir.join(input_file_path), entry_name.as_deref())?;
+
+            // A sector is selected when it matches every given filter.
+            let selected = (0..map.sectors.len())
+                .filter(|i| sectors.as_ref().is_none_or(|s| s.contains(&(*i as u32))))
+                .filter(|i| lotag.is_none_or(|l| map.sectors[*i].lotag == l))
+                .filter(|i| {
+                    bounding_box.is_none_or(|(x1, y1, x2, y2)| {
+                        map.sector_walls(*i)
+                            .iter()
+                            .all(|w| (x1..=x2).contains(&w.x) && (y1..=y2).contains(&w.y))
+                    })
+                })
+                .collect::<Vec<_>>();
+            if selected.is_empty() {
+                return Err(messages::get("no-sectors-selected", &[]));
+            }
+
+            let region = map.extract_sectors(&selected)?;
+            println!(
+                "{}",
+                messages::get(
+                    "region-extracted",
+                    &[
+                        ("sectors", &region.sectors.len()),
+                        ("walls", &region.walls.len()),
+                        ("sprites", &region.sprites.len())
+                    ]
+                )
+            );
+            write_map(&curr_dir.join(output_file_path), &region)?;
+        }
+        "map-stitch" => {
+            let mut input_file_path: Option<String> = None;
+            let mut merge_file_path: Option<String> = None;
+            let mut offset = (0, 0, 0);
+            let mut output_file_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--input-file" => {
+                        input_file_path = Some(value);
+                    }
+                    "--merge-file" => {
+                        merge_file_path = Some(value);
+                    }
+                    "--offset" => {
+                        offset = match parse_numbers::<i32>(&value)?[..] {
+                            [x, y] => (x, y, 0),
+                            [x, y, z] => (x, y, z),
+                            _ => return Err(messages::get("invalid-offset", &[])),
+                        };
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(input_file_path), Some(merge_file_path), Some(output_file_path)) =
+                (input_file_path, merge_file_path, output_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let mut map = read_map(&curr_dir.join(input_file_path), None)?;
+            let other = read_map(&curr_dir.join(merge_file_path), None)?;
+
+            let first_sector = map.sectors.len();
+            map.append(&other, offset)?;
+            println!(
+                "{}",
+                messages::get(
+                    "sectors-merged",
+                    &[
+                        ("count", &other.sectors.len()),
+                        ("first", &first_sector),
+                        ("last", &map.sectors.len().saturating_sub(1))
+                    ]
+                )
+            );
+            write_map(&curr_dir.join(output_file_path), &map)?;
+        }
+        "map-transform" => {
+            let mut input_file_path: Option<String> = None;
+            let mut entry_name: Option<String> = None;
+            let mut mirror: Option<Axis> = None;
+            let mut output_file_path: Option<String> = None;
+            let mut quarter_turns = 0;
+            let mut translation = (0, 0, 0);
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_name = Some(value);
+                    }
+                    "--input-file" => {
+                        input_file_path = Some(value);
+                    }
+                    "--mirror" => {
+                        mirror = match value.as_str() {
+                            "x" => Some(Axis::X),
+                            "y" => Some(Axis::Y),
+                            _ => return Err(messages::get("unknown-axis", &[("axis", &value)])),
+                        };
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    "--rotate" => {
+                        quarter_turns = match value.parse::<i32>() {
+                            Ok(degrees) if degrees % 90 == 0 => degrees.rem_euclid(360) as u32 / 90,
+                            _ => {
+                                return Err(messages::get(
+                                    "invalid-rotation",
+                                    &[("rotation", &value)],
+                                ))
+                            }
+                        };
+                    }
+                    "--translate" => {
+                        translation = match parse_numbers::<i32>(&value)?[..] {
+                            [x, y] => (x, y, 0),
+                            [x, y, z] => (x, y, z),
+                            _ => return Err(messages::get("invalid-translation", &[])),
+                        };
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(input_file_path), Some(output_file_path)) =
+                (input_file_path, output_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let mut map = read_map(&curr_dir.join(input_file_path), entry_name.as_deref())?;
+
+            // The operations are applied in this order, whatever the order of the options.
+            if let Some(axis) = mirror {
+                map.mirror(axis)?;
+            }
+            map.rotate(quarter_turns);
+            let (dx, dy, dz) = translation;
+            map.translate(dx, dy, dz);
+            write_map(&curr_dir.join(output_file_path), &map)?;
+        }
+        "map-convert" => {
+            let mut input_file_path: Option<String> = None;
+            let mut entry_name: Option<String> = None;
+            let mut output_file_path: Option<String> = None;
+            let mut to_version = MAP_VERSION;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_name = Some(value);
+                    }
+                    "--input-file" => {
+                        input_file_path = Some(value);
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    "--to-version" => {
+                        to_version = value
+                            .parse()
+                            .map_err(|_| messages::get("invalid-number", &[("number", &value)]))?;
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(input_file_path), Some(output_file_path)) =
+                (input_file_path, output_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if to_version != MAP_VERSION {
+                return Err(messages::get(
+                    "map-version-target",
+                    &[("version", &MAP_VERSION)],
+                ));
+            }
+            let curr_dir = std::env::current_dir().unwrap();
+            let input_file_path = curr_dir.join(input_file_path);
+            let data = read_map_data(&input_file_path, entry_name.as_deref())?;
+            let name = entry_name.unwrap_or(input_file_path.display().to_string());
+
+            let map = match map::read_version(&data)? {
+                MAP_VERSION => {
+                    println!(
+                        "{}",
+                        messages::get(
+                            "map-version-current",
+                            &[("file", &name), ("version", &MAP_VERSION)]
+                        )
+                    );
+                    Map::parse(&data).map_err(|e| {
+                        messages::get("read-failed", &[("file", &name), ("error", &e)])
+                    })?
+                }
+                MAP_VERSION_6 => {
+                    let (map, warnings) = Map::parse_v6(&data).map_err(|e| {
+                        messages::get("read-failed", &[("file", &name), ("error", &e)])
+                    })?;
+                    for warning in warnings {
+                        output::warning(&messages::get("warning", &[("warning", &warning)]));
+                    }
+                    map
+                }
+                version => {
+                    return Err(messages::get(
+                        "map-version-unsupported",
+                        &[("version", &version)],
+                    ))
+                }
+            };
+            write_map(&curr_dir.join(output_file_path), &map)?;
+        }
+        "map-check-tiles" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut entry_name: Option<String> = None;
+            let mut map_file_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_name = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--map-file" => {
+                        map_file_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(grp_file_path) = grp_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            // The map is either a separate file or an entry of the archive with the tiles.
+            let map = match (map_file_path, entry_name) {
+                (Some(map_file_path), None) => read_map(&curr_dir.join(map_file_path), None)?,
+                (None, Some(entry_name)) => read_map(&grp_file_path, Some(&entry_name))?,
+                _ => return Err(messages::get("map-file-or-entry", &[])),
+            };
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let tile_set = read_tile_set(&mut GrpFileReader::new(&file)?)?;
+
+            let problems = texture_check::check(&map, &tile_set);
+            let mut table = Table::new()
+                .header(&[
+                    &messages::get("column-surface", &[]),
+                    &messages::get("column-tile", &[]),
+                    &messages::get("column-problem", &[]),
+                ])
+                .right_align(1);
+            for problem in &problems {
+                table.row([
+                    problem.surface.to_string().into(),
+                    problem.picnum.to_string().into(),
+                    Cell::styled(problem.reason.clone(), Style::Yellow),
+                ]);
+            }
+            if !table.is_empty() {
+                table.print();
+            }
+            println!(
+                "{}",
+                messages::get("problems-found", &[("count", &problems.len())])
+            );
+        }
+        "catalog" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut output_file_path: Option<String> = None;
+            let mut palette_file_path: Option<String> = None;
+            let mut preview_dir: Option<String> = None;
+            let mut preview_tiles: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--output-file" => {
+                        output_file_path = Some(value);
+                    }
+                    "--palette" => {
+                        palette_file_path = Some(value);
+                    }
+                    "--preview-dir" => {
+                        preview_dir = Some(value);
+                    }
+                    "--preview-tiles" => {
+                        preview_tiles = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(grp_file_path) = grp_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let size = file
+                .metadata()
+                .map_err(|e| {
+                    messages::get(
+                        "read-failed",
+                        &[("file", &grp_file_path.display()), ("error", &e)],
+                    )
+                })?
+                .len();
+            let sha256 = verify::hash_reader(&file, &grp_file_path.display().to_string())?;
+            (&file).rewind().map_err(|e| {
+                messages::get(
+                    "read-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let file_name = grp_file_path
+                .file_name()
+                .map_or(String::new(), |n| n.to_string_lossy().into_owned());
+            let mut grp_reader = GrpFileReader::new(&file)?;
+            let mut catalog = catalog::Catalog::collect(file_name, size, sha256, &mut grp_reader)?;
+
+            if let Some(preview_dir) = preview_dir {
+                let preview_dir = curr_dir.join(preview_dir);
+                let palette = match palette_file_path {
+                    Some(palette_file_path) => read_palette(&curr_dir.join(palette_file_path))?,
+                    None => {
+                        let palette_entry = game::active().palette_entry;
+                        let Some(entry) = grp_reader.find_file_entry(palette_entry)? else {
+                            return Err(messages::get("preview-palette-missing", &[]));
+                        };
+                        palette::Palette::from_palette_dat(&grp_reader.read_file(&entry)?).map_err(
+                            |e| {
+                                messages::get(
+                                    "read-failed",
+                                    &[("file", &palette_entry), ("error", &e)],
+                                )
+                            },
+                        )?
+                    }
+                };
+                // By default the largest tile, which is usually a title or loading screen.
+                let tile_set = read_tile_set(&mut grp_reader)?;
+                let tile_numbers = match preview_tiles {
+                    Some(preview_tiles) => preview_tiles
+                        .split(',')
+                        .map(|t| {
+                            t.trim()
+                                .parse::<u32>()
+                                .map_err(|_| messages::get("invalid-tile-number", &[("tile", &t)]))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                    None => tile_set
+                        .tiles()
+                        .filter(|t| t.pixel_count() > 0)
+                        .max_by_key(|t| t.pixel_count())
+                        .map(|t| t.number())
+                        .into_iter()
+                        .collect(),
+                };
+                fs::create_dir_all(&preview_dir).map_err(|e| {
+                    messages::get(
+                        "create-failed",
+                        &[("file", &preview_dir.display()), ("error", &e)],
+                    )
+                })?;
+                for tile_number in tile_numbers {
+                    let Some(file) = tile_set.file_of(tile_number) else {
+                        return Err(messages::get("tile-not-in-art", &[("tile", &tile_number)]));
+                    };
+                    let Some(entry) = grp_reader.find_file_entry(&file.name)? else {
+                        return Err(messages::get("entry-not-found", &[("entry", &file.name)]));
+                    };
+                    let mut art_reader = ArtFileReader::new(grp_reader.open_file(&entry)?)?;
+                    let tile = art_reader.get_tile(tile_number)?;
+                    if tile.pixel_count() == 0 {
+                        return Err(messages::get("tile-empty", &[("tile", &tile_number)]));
+                    }
+                    let rgba = tile.to_rgba(&art_reader.read_pixels(&tile)?, &palette);
+                    let (width, height, rgba) = catalog::thumbnail(
+                        tile.width() as u32,
+                        tile.height() as u32,
+                        &rgba,
+                        catalog::THUMBNAIL_SIZE,
+                    );
+                    let file_name = format!("TILE{}.png", tile_number);
+                    let preview_file_path = preview_dir.join(&file_name);
+                    let mut preview_file = TempFile::new(&preview_file_path)?;
+                    preview_file
+                        .file()
+                        .write_all(&image::encode_png(width, height, &rgba))
+                        .map_err(|e| {
+                            messages::get(
+                                "write-failed",
+                                &[("file", &preview_file_path.display()), ("error", &e)],
+                            )
+                        })?;
+                    preview_file.persist()?;
+                    catalog.previews.push(catalog::Preview {
+                        tile: tile_number,
+                        file_name,
+                        width,
+                        height,
+                    });
+                }
+            }
+
+            let record = catalog.to_json();
+            match output_file_path {
+                Some(output_file_path) => {
+                    let output_file_path = curr_dir.join(output_file_path);
+                    let mut output_file = TempFile::new(&output_file_path)?;
+                    writeln!(output_file.file(), "{}", record).map_err(|e| {
+                        messages::get(
+                            "write-failed",
+                            &[("file", &output_file_path.display()), ("error", &e)],
+                        )
+                    })?;
+                    output_file.persist()?;
+                }
+                None => println!("{}", record),
+            }
+        }
+        "describe" => {
+            let mut input_file_path: Option<String> = None;
+            let mut entry_name: Option<String> = None;
+            let mut format: Option<String> = None;
+            let mut limit = 16;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--entry" => {
+                        entry_name = Some(value);
+                    }
+                    "--format" => {
+                        format = Some(value.to_uppercase());
+                    }
+                    "--input-file" => {
+                        input_file_path = Some(value);
+                    }
+                    "--limit" => {
+                        limit = value
+                            .parse::<usize>()
+                            .map_err(|_| messages::get("invalid-number", &[("number", &value)]))?;
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(input_file_path) = input_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let input_file_path = curr_dir.join(&input_file_path);
+            let mut file = File::open(&input_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &input_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let (name, data) = match entry_name {
+                Some(entry_name) => {
+                    let mut grp_reader = GrpFileReader::new(&file)?;
+                    let Some(entry) = grp_reader.find_file_entry(&entry_name)? else {
+                        return Err(messages::get("entry-not-found", &[("entry", &entry_name)]));
+                    };
+                    let data = grp_reader.read_file(&entry)?;
+                    (entry_name, data)
+                }
+                None => {
+                    let mut data = Vec::new();
+                    file.read_to_end(&mut data).map_err(|e| {
+                        messages::get(
+                            "read-failed",
+                            &[("file", &input_file_path.display()), ("error", &e)],
+                        )
+                    })?;
+                    (input_file_path.display().to_string(), data)
+                }
+            };
+
+            let Some(format) = format
+                .as_deref()
+                .or_else(|| describe::detect_format(&name, &data))
+            else {
+                return Err(messages::get("format-not-detected", &[("file", &name)]));
+            };
+            let (fields, error) = describe::describe(format, &data, limit);
+            print!("{}", describe::format_fields(&fields));
+            if let Some(error) = error {
+                return Err(error);
+            }
+        }
+        "grp-meta" => {
+            let action = args.next();
+            let mut grp_file_path: Option<String> = None;
+            let mut meta_file_path: Option<String> = None;
+            let mut output_dir: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--meta-file" => {
+                        meta_file_path = Some(value);
+                    }
+                    "--output-dir" => {
+                        output_dir = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(action), Some(grp_file_path), Some(meta_file_path)) =
+                (action, grp_file_path, meta_file_path)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let meta_file_path = curr_dir.join(meta_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let entries = GrpFileReader::new(&file)?.get_file_entries()?;
+            drop(file);
+
+            match action.as_str() {
+                "export" => {
+                    let archive_name = grp_file_path.file_name().unwrap_or_default();
+                    let source = grp_meta::export(&archive_name.to_string_lossy(), &entries);
+                    let mut output_file = TempFile::new(&meta_file_path)?;
+                    output_file
+                        .file()
+                        .write_all(source.as_bytes())
+                        .map_err(|e| {
+                            messages::get(
+                                "write-failed",
+                                &[("file", &meta_file_path.display()), ("error", &e)],
+                            )
+                        })?;
+                    output_file.persist()?;
+                    println!(
+                        "{}",
+                        messages::get(
+                            "entries-listed",
+                            &[
+                                ("count", &entries.len()),
+                                ("file", &meta_file_path.display())
+                            ],
+                        )
+                    );
+                }
+                "apply" => {
+                    let source = fs::read_to_string(&meta_file_path).map_err(|e| {
+                        messages::get(
+                            "read-failed",
+                            &[("file", &meta_file_path.display()), ("error", &e)],
+                        )
+                    })?;
+                    let arrangement = grp_meta::parse(&source).map_err(|e| {
+                        messages::get(
+                            "read-failed",
+                            &[("file", &meta_file_path.display()), ("error", &e)],
+                        )
+                    })?;
+                    let unchanged = arrangement.len() == entries.len()
+                        && arrangement
+                            .iter()
+                            .zip(&entries)
+                            .all(|((original, name), entry)| {
+                                original == name && entry.name() == original.as_str()
+                            });
+                    if unchanged {
+                        println!("{}", messages::get("no-differences", &[]));
+                        return Ok(());
+                    }
+
+                    let mut table = Table::new();
+                    for (original, name) in arrangement.iter().filter(|(o, n)| o != n) {
+                        table.row([format!("{}:", original), name.clone()]);
+                    }
+                    let output_dir = output_dir.map(|d| curr_dir.join(d));
+                    let mut session = edit_session(
+                        &grp_file_path,
+                        output_dir.as_deref(),
+                        &format!("grp-meta apply --meta-file {}", meta_file_path.display()),
+                    )?;
+                    let count = arrangement.len();
+                    session.rearrange(arrangement);
+                    session.apply()?;
+                    table.print();
+                    println!(
+                        "{}",
+                        messages::get("entries-rearranged", &[("count", &count)])
+                    );
+                }
+                _ => {
+                    return Err(messages::get(
+                        "unknown-action",
+                        &[("action", &action), ("actions", &"export, apply")],
+                    ))
+                }
+            }
+        }
+        "grp-verify" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut manifest_path: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--manifest" => {
+                        manifest_path = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(grp_file_path), Some(manifest_path)) = (grp_file_path, manifest_path) else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let manifest_path = curr_dir.join(manifest_path);
+            let manifest = fs::read_to_string(&manifest_path).map_err(|e| {
+                messages::get(
+                    "read-failed",
+                    &[("file", &manifest_path.display()), ("error", &e)],
+                )
+            })?;
+            let expected = verify::parse_manifest(&manifest).map_err(|e| {
+                messages::get(
+                    "read-failed",
+                    &[("file", &manifest_path.display()), ("error", &e)],
+                )
+            })?;
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let verdicts = verify::verify(&mut GrpFileReader::new(&file)?, &expected)?;
+
+            // Only drift is listed, verified entries are counted.
+            let mut table = Table::new();
+            for (name, verdict) in &verdicts {
+                let label = match verdict {
+                    verify::Verdict::Verified => continue,
+                    verify::Verdict::Changed => {
+                        Cell::styled(messages::get("status-changed", &[]), Style::Red)
+                    }
+                    verify::Verdict::Missing => {
+                        Cell::styled(messages::get("status-missing", &[]), Style::Red)
+                    }
+                    verify::Verdict::Unlisted => {
+                        Cell::styled(messages::get("status-unlisted", &[]), Style::Yellow)
+                    }
+                };
+                table.row([label, name.to_string().into()]);
+            }
+            table.print();
+            let verified = verdicts
+                .iter()
+                .filter(|(_, v)| *v == verify::Verdict::Verified)
+                .count();
+            println!(
+                "{}",
+                messages::get("entries-verified", &[("count", &verified)])
+            );
+            if !table.is_empty() {
+                return Err(messages::get(
+                    "entries-drifted",
+                    &[("count", &(verdicts.len() - verified))],
+                ));
+            }
+        }
+        "grp-hash" => {
+            let mut grp_file_path: Option<String> = None;
+            let mut jobs = workers::default_jobs();
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--jobs" => {
+                        jobs = value
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|jobs| *jobs > 0)
+                            .ok_or_else(|| {
+                                messages::get("invalid-number", &[("number", &value)])
+                            })?;
+                    }
+                    _ => {}
+                }
+            }
+
+            let Some(grp_file_path) = grp_file_path else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let open = || {
+                File::open(&grp_file_path)
+                    .map_err(|e| {
+                        messages::get(
+                            "open-failed",
+                            &[("file", &grp_file_path.display()), ("error", &e)],
+                        )
+                    })
+                    .and_then(GrpFileReader::new)
+            };
+            let entries = open()?.get_file_entries()?;
+
+            // Each job reads the archive through its own file handle. The
+            // output is a manifest for grp-verify, in archive order.
+            let hashes = workers::run_ordered(&entries, jobs, open, verify::hash_entry)?;
+            for (entry, hash) in entries.iter().zip(hashes) {
+                println!(
+                    "{}  {}",
+                    verify::to_hex(&hash),
+                    fs_name::to_fs_name(&entry.name())
+                );
+            }
+        }
+        "grp-export" => {
+            let mut format: Option<String> = None;
+            let mut grp_file_path: Option<String> = None;
+            let mut mtime = MtimePolicy::default();
+            let mut output: Option<String> = None;
+
+            while let (Some(option), Some(value)) = (args.next(), args.next()) {
+                match option.as_str() {
+                    "--format" => {
+                        format = Some(value);
+                    }
+                    "--input-file" => {
+                        grp_file_path = Some(value);
+                    }
+                    "--mtime" => {
+                        mtime = MtimePolicy::parse(&value)?;
+                    }
+                    "--output" => {
+                        output = Some(value);
+                    }
+                    _ => {}
+                }
+            }
+
+            let (Some(format), Some(grp_file_path), Some(output)) = (format, grp_file_path, output)
+            else {
+                return Err(messages::get("missing-arguments", &[]));
+            };
+            if format != "tar" {
+                return Err(messages::get(
+                    "unknown-format",
+                    &[("format", &format), ("formats", &"tar")],
+                ));
+            }
+            let curr_dir = std::env::current_dir().unwrap();
+            let grp_file_path = curr_dir.join(grp_file_path);
+            let file = File::open(&grp_file_path).map_err(|e| {
+                messages::get(
+                    "open-failed",
+                    &[("file", &grp_file_path.display()), ("error", &e)],
+                )
+            })?;
+            let mut grp_reader = GrpFileReader::new(&file)?;
+            let mtime = mtime
+                .resolve(&grp_file_path)?
+                .unwrap_or_else(std::time::SystemTime::now)
+                .duration_since(std::time::SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+
+            // With `--output -` the tar is streamed to stdout, e.g. to `tar -x`,
+            // so nothing else may be printed there.
+            if output == "-" {
+                write_tar(&mut grp_reader, mtime, std::io::stdout().lock())?;
+            } else {
+                let output_file_path = curr_dir.join(output);
+                let mut output_file = TempFile::new(&output_file_path)?;
+                write_tar(&mut grp_reader, mtime, output_file.file())?;
+                output_file.persist()?;
+            }
+        }
+        "selftest" => {
+            let dir = std::env::temp_dir().join(format!("duke3d-selftest-{}", std::process::id()));
+            let results = selftest::run(&dir);
+            let _ = fs::remove_dir_all(&dir);
+
+            let mut table = Table::new().header(&[
+                &messages::get("column-check", &[]),
+                &messages::get("column-result", &[]),
+            ]);
+            for (name, result) in &results {
+                let result = match result {
+                    Ok(()) => Cell::styled(messages::get("check-passed", &[]), Style::Green),
+                    Err(e) => {
+                        Cell::styled(messages::get("check-failed", &[("error", e)]), Style::Red)
+                    }
+                };
+                table.row([name.to_string().into(), result]);
+            }
+            table.print();
+            let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+            if failed > 0 {
+                return Err(messages::get("checks-failed", &[("count", &failed)]));
+            }
+            println!(
+                "{}",
+                messages::get("checks-passed", &[("count", &results.len())])
+            );
+        }
+        "formats" => {
+            // One tab-separated line per format: name, capabilities,
+            // extensions, magic bytes in hex and description.
+            for format in duke3d_file_utility::formats::supported() {
+                let capabilities = [
+                    ("read", format.capabilities.read),
+                    ("write", format.capabilities.write),
+                    ("convert", format.capabilities.convert),
+                ]
+                .iter()
+                .filter(|(_, supported)| *supported)
+                .map(|(name, _)| *name)
+                .collect::<Vec<_>>();
+                let magic = match format.magic {
+                    Some(magic) => magic.iter().map(|b| format!("{:02X}", b)).collect(),
+                    None => "-".to_string(),
+                };
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    format.name,
+                    capabilities.join(","),
+                    format.extensions.join(","),
+                    magic,
+                    format.description
+                );
+            }
+        }
+        _ => {
+            let Some(executable) = plugin::find(&command) else {
+                return Err(messages::get("unknown-command", &[("command", &command)]));
+            };
+            plugin::run(&executable, &command, &args.collect::<Vec<_>>())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the value of a switch such as `--recursive true`, so that a
+/// mistyped value is reported instead of being taken as false.
+fn parse_switch(option: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(messages::get("invalid-switch", &[("option", &option)])),
+    }
+}
+
+/// Reads the tiles of all .art files in an archive.
+fn read_tile_set(grp_reader: &mut GrpFileReader<&File>) -> Result<TileSet, String> {
+    let mut tile_set = TileSet::new();
+    for entry in grp_reader.get_file_entries()? {
+        let name = entry.name().to_string();
+        if !game::active().is_art(&name) {
+            continue;
+        }
+        let tiles = ArtFileReader::new(grp_reader.open_file(&entry)?)
+            .and_then(|mut art_reader| art_reader.read_tiles())
+            .map_err(|e| messages::get("read-failed", &[("file", &name), ("error", &e)]))?;
+        tile_set.add_file(name, tiles);
+    }
+    Ok(tile_set)
+}
+
+fn read_palette(palette_file_path: &Path) -> Result<palette::Palette, String> {
+    let palette_data = fs::read(palette_file_path).map_err(|e| {
+        messages::get(
+            "open-failed",
+            &[("file", &palette_file_path.display()), ("error", &e)],
+        )
+    })?;
+    palette::Palette::from_palette_dat(&palette_data).map_err(|e| {
+        messages::get(
+            "read-failed",
+            &[("file", &palette_file_path.display()), ("error", &e)],
+        )
+    })
+}
+
+/// Reads a map file, or with `entry_name` a map inside an archive.
+fn read_map(input_file_path: &Path, entry_name: Option<&str>) -> Result<Map, String> {
+    let data = read_map_data(input_file_path, entry_name)?;
+    Map::parse(&data).map_err(|e| {
+        let name = entry_name.map_or(input_file_path.display().to_string(), str::to_string);
+        messages::get("read-failed", &[("file", &name), ("error", &e)])
+    })
+}
+
+/// The bytes of a map file, or with `entry_name` of a map inside an archive.
+fn read_map_data(input_file_path: &Path, entry_name: Option<&str>) -> Result<Vec<u8>, String> {
+    let file = File::open(input_file_path).map_err(|e| {
+        messages::get(
+            "open-failed",
+            &[("file", &input_file_path.display()), ("error", &e)],
+        )
+    })?;
+    let mut data = Vec::new();
+    match entry_name {
+        Some(entry_name) => {
+            let mut grp_reader = GrpFileReader::new(&file)?;
+            let Some(mut entry_reader) = grp_reader.open_entry(entry_name)? else {
+                return Err(messages::get("entry-not-found", &[("entry", &entry_name)]));
+            };
+            entry_reader.read_to_end(&mut data).map_err(|e| {
+                messages::get("read-failed", &[("file", &entry_name), ("error", &e)])
+            })?;
+        }
+        None => {
+            (&file).read_to_end(&mut data).map_err(|e| {
+                messages::get(
+                    "read-failed",
+                    &[("file", &input_file_path.display()), ("error", &e)],
+                )
+            })?;
+        }
+    }
+    Ok(data)
+}
+
+/// Writes every entry of the archive to a tar, named as by `grp-extract-all`.
+fn write_tar<R: Read + Seek, W: Write>(
+    grp_reader: &mut GrpFileReader<R>,
+    mtime: u64,
+    writer: W,
+) -> Result<(), String> {
+    let mut tar = tar::TarWriter::new(std::io::BufWriter::new(writer));
+    for entry in grp_reader.get_file_entries()? {
+        let mut entry_reader = grp_reader.open_file(&entry)?;
+        let size = entry_reader.size();
+        tar.append(
+            &fs_name::to_fs_name(&entry.name()),
+            size,
+            mtime,
+            &mut entry_reader,
+        )?;
+    }
+    tar.finish().map(drop)
+}
+
+fn write_metadata(
+    metadata_file_path: &Path,
+    metadata: &tile_metadata::TileMetadata,
+) -> Result<(), String> {
+    let mut metadata_file = TempFile::new(metadata_file_path)?;
+    writeln!(metadata_file.file(), "{}", metadata.to_json()).map_err(|e| {
+        messages::get(
+            "write-failed",
+            &[("file", &metadata_file_path.display()), ("error", &e)],
+        )
+    })?;
+    metadata_file.persist()
+}
+
+fn write_map(output_file_path: &Path, map: &Map) -> Result<(), String> {
+    let mut output_file = TempFile::new(output_file_path)?;
+    output_file.file().write_all(&map.to_bytes()).map_err(|e| {
+        messages::get(
+            "write-failed",
+            &[("file", &output_file_path.display()), ("error", &e)],
+        )
+    })?;
+    output_file.persist()
+}
+
+/// Starts an edit of an archive, which is written under the same name into
+/// `output_dir` when given, and checks up front that the result can be written.
+///
+/// Install directories, e.g. of Steam, are often read-only, and it is better
+/// to learn that before preparing the edits than from a failed rewrite.
+fn edit_session(
+    grp_file_path: &Path,
+    output_dir: Option<&Path>,
+    undo_description: &str,
+) -> Result<EditSession, String> {
+    let mut session = EditSession::new(grp_file_path);
+    if let Some(output_dir) = output_dir {
+        session.output(output_dir.join(grp_file_path.file_name().unwrap_or_default()));
+    }
+    session.record_undo(undo_description);
+    session
+        .preflight()
+        .map_err(|e| format!("{} {}", e, messages::get("use-output-dir", &[])))?;
+    Ok(session)
+}
+
+/// Parses a byte offset or length, in hex when it starts with `0x`.
+fn parse_offset(value: &str) -> Result<u64, String> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    }
+    .map_err(|_| messages::get("invalid-number", &[("number", &value)]))
+}
+
+/// Parses comma-separated numbers such as `1024,-512`.
+fn parse_numbers<T: std::str::FromStr>(value: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|n| {
+            n.trim()
+                .parse::<T>()
+                .map_err(|_| messages::get("invalid-number", &[("number", &n)]))
+        })
+        .collect()
+}
+
+/// Parses comma-separated numbers and inclusive ranges such as `0,3,5-9`.
+fn parse_ranges(value: &str) -> Result<BTreeSet<u32>, String> {
+    let mut numbers = BTreeSet::new();
+    for part in value.split(',') {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        match (first.trim().parse::<u32>(), last.trim().parse::<u32>()) {
+            (Ok(first), Ok(last)) if first <= last => numbers.extend(first..=last),
+            _ => return Err(messages::get("invalid-range", &[("range", &part)])),
+        }
+    }
+    Ok(numbers)
 }
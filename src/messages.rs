@@ -0,0 +1,137 @@
+//! The catalog of user-facing messages of the CLI, for translating them.
+//!
+//! Messages are written in a subset of the Fluent syntax: one `id = text`
+//! per line with `{ $name }` placeables and `#` comments. The catalogs in
+//! `locales/` are embedded in the executable, and the language is picked
+//! from `LC_ALL`, `LC_MESSAGES` or `LANG` like other command-line tools do.
+//! Messages missing from a translation fall back to English.
+//!
+//! Errors from the format crates are not translated yet and are shown as is.
+
+use std::{collections::BTreeMap, fmt::Display, sync::OnceLock};
+
+const ENGLISH: &str = include_str!("../locales/en.ftl");
+/// The translations by language code.
+const TRANSLATIONS: &[(&str, &str)] = &[("fi", include_str!("../locales/fi.ftl"))];
+
+type Catalog = BTreeMap<String, String>;
+
+/// Parses a catalog into its messages by id.
+fn parse(source: &str) -> Result<Catalog, String> {
+    let mut catalog = Catalog::new();
+    for (line_index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((id, text)) = line.split_once('=') else {
+            return Err(format!("Line {}: expected id = text.", line_index + 1));
+        };
+        catalog.insert(id.trim().to_string(), text.trim().to_string());
+    }
+    Ok(catalog)
+}
+
+/// The language code of a locale like `fi_FI.UTF-8`.
+fn language(locale: &str) -> String {
+    locale
+        .split(['_', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// The messages for the language of the environment, with English for
+/// anything not translated.
+fn catalog() -> &'static Catalog {
+    static CATALOG: OnceLock<Catalog> = OnceLock::new();
+    CATALOG.get_or_init(|| {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        let mut catalog = parse(ENGLISH).unwrap_or_default();
+        let language = language(&locale);
+        if let Some((_, source)) = TRANSLATIONS.iter().find(|(code, _)| *code == language) {
+            catalog.extend(parse(source).unwrap_or_default());
+        }
+        catalog
+    })
+}
+
+/// Fills in the `{ $name }` placeables of a message text. Placeables
+/// without a value are kept as they are.
+fn format(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut formatted = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        formatted.push_str(&rest[..start]);
+        let name = rest[start + 1..end].trim().trim_start_matches('$');
+        match args.iter().find(|(n, _)| *n == name) {
+            Some((_, value)) => formatted.push_str(&value.to_string()),
+            None => formatted.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    formatted.push_str(rest);
+    formatted
+}
+
+/// The message with the given id in the language of the user, with its
+/// placeables filled in from `args`. Unknown ids are returned as is.
+pub fn get(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    match catalog().get(id) {
+        Some(text) => format(text, args),
+        None => id.to_string(),
+    }
+}
+
+#[test]
+fn should_translate_messages() {
+    let english = parse(ENGLISH).unwrap();
+    assert_eq!(english["no-differences"], "No differences.");
+    assert_eq!(
+        format(
+            &english["asset-renamed"],
+            &[("from", &"A.ART"), ("to", &"B.ART")]
+        ),
+        "Renamed A.ART to B.ART."
+    );
+    assert_eq!(
+        format("{ $missing } { $count }", &[("count", &3)]),
+        "{ $missing } 3"
+    );
+    assert_eq!(language("fi_FI.UTF-8"), "fi");
+    assert_eq!(language("C"), "c");
+
+    // Translations may only leave messages out, and must keep their placeables.
+    for (code, source) in TRANSLATIONS {
+        for (id, text) in parse(source).unwrap() {
+            let Some(english_text) = english.get(&id) else {
+                panic!("{}.ftl: unknown message {}", code, id);
+            };
+            let placeables = |text: &str| {
+                let mut names = text
+                    .split('{')
+                    .skip(1)
+                    .filter_map(|p| p.split_once('}'))
+                    .map(|(name, _)| name.trim().to_string())
+                    .collect::<Vec<_>>();
+                names.sort();
+                names
+            };
+            assert_eq!(
+                placeables(&text),
+                placeables(english_text),
+                "{}.ftl: {}",
+                code,
+                id
+            );
+        }
+    }
+    assert!(parse("no separator").is_err());
+}
@@ -0,0 +1,200 @@
+//! Terminal output of the CLI: styled errors and warnings, headings and
+//! aligned tables.
+//!
+//! Styles are written as ANSI escape codes only when the stream is a
+//! terminal, so that output piped into files and other tools stays plain.
+//! `--no-color` and the `NO_COLOR` environment variable (https://no-color.org)
+//! turn them off for terminals too.
+
+use std::{
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turns off styles for the rest of the run, for `--no-color`.
+pub fn disable_color() {
+    COLOR_DISABLED.store(true, Ordering::Relaxed);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+impl Stream {
+    fn is_colored(self) -> bool {
+        if COLOR_DISABLED.load(Ordering::Relaxed)
+            || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+        {
+            return false;
+        }
+        match self {
+            Stream::Stdout => std::io::stdout().is_terminal(),
+            Stream::Stderr => std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    Bold,
+    Green,
+    Red,
+    Yellow,
+}
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Bold => "1",
+            Style::Green => "32",
+            Style::Red => "1;31",
+            Style::Yellow => "33",
+        }
+    }
+}
+
+/// `text` in `style`, or as is when `colored` is false.
+fn paint(text: &str, style: Option<Style>, colored: bool) -> String {
+    match style {
+        Some(style) if colored => format!("\x1b[{}m{}\x1b[0m", style.code(), text),
+        _ => text.to_string(),
+    }
+}
+
+/// Prints an error message to stderr.
+pub fn error(message: &str) {
+    eprintln!(
+        "{}",
+        paint(message, Some(Style::Red), Stream::Stderr.is_colored())
+    );
+}
+
+/// Prints a warning message to stderr.
+pub fn warning(message: &str) {
+    eprintln!(
+        "{}",
+        paint(message, Some(Style::Yellow), Stream::Stderr.is_colored())
+    );
+}
+
+/// Prints the heading of a section of a report to stdout.
+pub fn heading(text: &str) {
+    println!(
+        "{}",
+        paint(text, Some(Style::Bold), Stream::Stdout.is_colored())
+    );
+}
+
+/// A cell of a [`Table`].
+pub struct Cell {
+    text: String,
+    style: Option<Style>,
+}
+impl Cell {
+    pub fn styled(text: impl Into<String>, style: Style) -> Self {
+        Self {
+            text: text.into(),
+            style: Some(style),
+        }
+    }
+}
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Self { text, style: None }
+    }
+}
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+/// Rows printed with their columns aligned, and an optional bold header.
+///
+/// The last column is not padded, so that lines have no trailing spaces.
+#[derive(Default)]
+pub struct Table {
+    header: Option<Vec<Cell>>,
+    rows: Vec<Vec<Cell>>,
+    right_aligned: Vec<usize>,
+}
+impl Table {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn header(mut self, titles: &[&str]) -> Self {
+        let header = titles.iter().map(|t| Cell::styled(*t, Style::Bold));
+        self.header = Some(header.collect());
+        self
+    }
+    /// Aligns a column to the right, e.g. for sizes.
+    pub fn right_align(mut self, column: usize) -> Self {
+        self.right_aligned.push(column);
+        self
+    }
+    pub fn row<C: Into<Cell>>(&mut self, cells: impl IntoIterator<Item = C>) {
+        self.rows.push(cells.into_iter().map(Into::into).collect());
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+    fn format(&self, colored: bool) -> String {
+        let rows = self.header.iter().chain(&self.rows);
+        let mut widths = Vec::<usize>::new();
+        for row in rows.clone() {
+            for (i, cell) in row.iter().enumerate() {
+                let width = cell.text.chars().count();
+                match widths.get_mut(i) {
+                    Some(max) => *max = (*max).max(width),
+                    None => widths.push(width),
+                }
+            }
+        }
+
+        let mut text = String::new();
+        for row in rows {
+            let mut line = String::new();
+            for (i, cell) in row.iter().enumerate() {
+                if i > 0 {
+                    line.push(' ');
+                }
+                let padding = " ".repeat(widths[i] - cell.text.chars().count());
+                let painted = paint(&cell.text, cell.style, colored);
+                if self.right_aligned.contains(&i) {
+                    line.push_str(&padding);
+                    line.push_str(&painted);
+                } else {
+                    line.push_str(&painted);
+                    if i + 1 < row.len() {
+                        line.push_str(&padding);
+                    }
+                }
+            }
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text
+    }
+    /// Prints the table to stdout.
+    pub fn print(&self) {
+        print!("{}", self.format(Stream::Stdout.is_colored()));
+    }
+}
+
+#[test]
+fn should_align_tables() {
+    let mut table = Table::new().header(&["Name", "Size"]).right_align(1);
+    table.row(["GAME.CON", "151"]);
+    table.row([Cell::styled("E1L1.MAP", Style::Green), "98304".into()]);
+    assert_eq!(
+        table.format(false),
+        "Name      Size\nGAME.CON   151\nE1L1.MAP 98304\n"
+    );
+    assert_eq!(
+        table.format(true).lines().nth(2),
+        Some("\x1b[32mE1L1.MAP\x1b[0m 98304")
+    );
+    assert_eq!(paint("Error", Some(Style::Red), false), "Error");
+}
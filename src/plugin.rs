@@ -0,0 +1,106 @@
+//! External subcommands, which let the tool be extended without changing it.
+//!
+//! An unknown command `NAME` runs the executable `duke3d-file-utility-NAME`
+//! found on `PATH`, like cargo and git do. The plugin gets `--json-lines`
+//! followed by the remaining arguments, and reads one JSON object from its
+//! standard input describing the invocation:
+//!
+//! ```json
+//! {"command":"NAME","args":["--input-file","X.GRP"],"version":"0.1.0","formats":["ART","GRP"]}
+//! ```
+//!
+//! The plugin writes its results to standard output as JSON Lines, one JSON
+//! value per line, and reports failure with a nonzero exit code.
+
+use crate::json;
+use std::{
+    ffi::OsStr,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// Prefix of the executable names of plugins.
+pub const EXECUTABLE_PREFIX: &str = "duke3d-file-utility-";
+
+/// Finds the executable of the plugin for a command on `PATH`.
+pub fn find(command: &str) -> Option<PathBuf> {
+    find_in(command, &std::env::var_os("PATH")?)
+}
+
+fn find_in(command: &str, path_var: &OsStr) -> Option<PathBuf> {
+    // Commands that could escape the searched directories are never plugins.
+    if command.is_empty() || command.contains(['/', '\\', '.']) {
+        return None;
+    }
+    let file_name = format!(
+        "{}{}{}",
+        EXECUTABLE_PREFIX,
+        command,
+        std::env::consts::EXE_SUFFIX
+    );
+    std::env::split_paths(path_var)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| path.is_file())
+}
+
+/// Runs a plugin, letting it write to the standard output and error of this process.
+pub fn run(executable: &Path, command: &str, args: &[String]) -> Result<(), String> {
+    let mut child = Command::new(executable)
+        .arg("--json-lines")
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run \"{}\": {}", executable.display(), e))?;
+
+    let invocation = invocation_json(command, args);
+    // A plugin that does not read its standard input closes it early, which is fine.
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = writeln!(stdin, "{}", invocation);
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to run \"{}\": {}", executable.display(), e))?;
+    if !status.success() {
+        return Err(format!("Command \"{}\" failed with {}.", command, status));
+    }
+    Ok(())
+}
+
+fn invocation_json(command: &str, args: &[String]) -> String {
+    let args = args.iter().map(|a| json::string(a)).collect::<Vec<_>>();
+    let formats = duke3d_file_utility::formats::supported()
+        .iter()
+        .map(|f| json::string(f.name))
+        .collect::<Vec<_>>();
+    format!(
+        "{{\"command\":{},\"args\":[{}],\"version\":{},\"formats\":[{}]}}",
+        json::string(command),
+        args.join(","),
+        json::string(env!("CARGO_PKG_VERSION")),
+        formats.join(",")
+    )
+}
+
+#[test]
+fn should_find_plugins_on_path() {
+    let dir = std::env::temp_dir().join("duke3d_should_find_plugins_on_path");
+    std::fs::create_dir_all(&dir).unwrap();
+    let executable = dir.join(format!(
+        "{}blood{}",
+        EXECUTABLE_PREFIX,
+        std::env::consts::EXE_SUFFIX
+    ));
+    std::fs::write(&executable, b"").unwrap();
+
+    let path_var = std::env::join_paths([dir.join("missing"), dir.clone()]).unwrap();
+    assert_eq!(find_in("blood", &path_var), Some(executable));
+    assert_eq!(find_in("shadow", &path_var), None);
+    assert_eq!(find_in("../blood", &path_var), None);
+
+    let json = invocation_json("blood", &["--input-file".into(), "A \"B\".RFF".into()]);
+    assert!(
+        json.starts_with(r#"{"command":"blood","args":["--input-file","A \"B\".RFF"],"version":""#)
+    );
+}
@@ -0,0 +1,137 @@
+//! Named option profiles from the config file, selected with `--profile NAME`.
+//!
+//! A profile has a table of options for every command it applies to:
+//!
+//! ```toml
+//! [profile.hrp-export.grp-extract-all]
+//! output-dir = "hrp"
+//! recursive = true
+//! ```
+//!
+//! Every key of the table becomes an option of the command, placed after
+//! the positional arguments and before the options given on the command
+//! line. Options given on the command line therefore override the profile,
+//! and repeatable ones such as `--input-file` add to it. Selecting a profile
+//! that has no table for the command is an error.
+
+use crate::toml_subset::{self, TomlValue};
+use std::{fs, path::Path};
+
+/// Config file looked up in the current directory when `--config` is not given.
+pub const DEFAULT_CONFIG_FILE_NAME: &str = "duke3d-file-util.toml";
+
+/// Removes `--profile` and `--config` from the arguments of a command and
+/// inserts the options of the selected profile after its positional arguments.
+pub fn apply(command: &str, args: Vec<String>, curr_dir: &Path) -> Result<Vec<String>, String> {
+    let mut rest = Vec::with_capacity(args.len());
+    let mut profile_name: Option<String> = None;
+    let mut config_path = curr_dir.join(DEFAULT_CONFIG_FILE_NAME);
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => config_path = curr_dir.join(args.next().unwrap_or_default()),
+            "--profile" => profile_name = args.next(),
+            _ => rest.push(arg),
+        }
+    }
+
+    let Some(profile_name) = profile_name else {
+        return Ok(rest);
+    };
+    let source = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read \"{}\": {}", config_path.display(), e))?;
+    let options = profile_options(&source, &profile_name, command)?;
+    // Positional arguments, such as the action of grp-meta, come before any option.
+    let positional_count = rest.iter().take_while(|a| !a.starts_with("--")).count();
+    rest.splice(positional_count..positional_count, options);
+    Ok(rest)
+}
+
+fn profile_options(source: &str, profile_name: &str, command: &str) -> Result<Vec<String>, String> {
+    let tables = toml_subset::parse(source)?;
+    let profile_prefix = format!("profile.{}.", profile_name);
+    if !tables.iter().any(|t| t.name.starts_with(&profile_prefix)) {
+        return Err(format!("Profile \"{}\" not found.", profile_name));
+    }
+    let table_name = format!("{}{}", profile_prefix, command);
+    let Some(table) = tables
+        .into_iter()
+        .find(|t| t.name == table_name && !t.is_array_item)
+    else {
+        return Err(format!(
+            "Profile \"{}\" has no options for {}.",
+            profile_name, command
+        ));
+    };
+
+    let mut options = Vec::with_capacity(table.values.len() * 2);
+    for (key, value) in table.values {
+        options.push(format!("--{}", key));
+        options.push(match value {
+            TomlValue::Bool(value) => value.to_string(),
+            TomlValue::Integer(value) => value.to_string(),
+            TomlValue::String(value) => value,
+        });
+    }
+    Ok(options)
+}
+
+#[test]
+fn should_insert_profile_options() {
+    let source = "[profile.hrp-export.grp-extract-all]\noutput-dir = \"hrp\"\nrecursive = true\n\n\
+                  [profile.hrp-export.grp-meta]\nmeta-file = \"meta.json\"\n\n\
+                  [profile.other.grp-pack]\nx = 1\n";
+    assert_eq!(
+        profile_options(source, "hrp-export", "grp-extract-all").unwrap(),
+        ["--output-dir", "hrp", "--recursive", "true"]
+    );
+    assert!(profile_options(source, "missing", "grp-extract-all").is_err());
+    // Profiles only apply to the commands they have options for.
+    assert_eq!(
+        profile_options(source, "other", "grp-extract-all").unwrap_err(),
+        "Profile \"other\" has no options for grp-extract-all."
+    );
+
+    let dir = std::env::temp_dir().join("duke3d_should_insert_profile_options");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(DEFAULT_CONFIG_FILE_NAME), source).unwrap();
+    let args = [
+        "--input-file",
+        "A.GRP",
+        "--profile",
+        "hrp-export",
+        "--output-dir",
+        "out",
+    ]
+    .map(String::from)
+    .to_vec();
+    assert_eq!(
+        apply("grp-extract-all", args, &dir).unwrap(),
+        [
+            "--output-dir",
+            "hrp",
+            "--recursive",
+            "true",
+            "--input-file",
+            "A.GRP",
+            "--output-dir",
+            "out"
+        ]
+    );
+
+    // The action of grp-meta stays in front of the options.
+    let args = ["export", "--profile", "hrp-export", "--input-file", "A.GRP"]
+        .map(String::from)
+        .to_vec();
+    assert_eq!(
+        apply("grp-meta", args, &dir).unwrap(),
+        [
+            "export",
+            "--meta-file",
+            "meta.json",
+            "--input-file",
+            "A.GRP"
+        ]
+    );
+}
@@ -0,0 +1,179 @@
+//! Where the entries packed by `grp-apply` came from, kept in a sidecar
+//! file next to the archive, e.g. `MOD.GRP.provenance`:
+//!
+//! ```toml
+//! [[entry]]
+//! name = "TILES020.ART"
+//! source = "/home/mod/art/TILES020.ART"
+//! sha256 = "6f1ed002ab5595859014ebf0951522d9d1c1e1e2d0a5a95c6ea6d9d4b5a2f8e3"
+//! converter = "png2art 1.2"
+//! tool = "duke3d-file-util 0.1.0"
+//! ```
+//!
+//! `converter` is taken from the edits file and is `copy` for files that
+//! were packed as they are. Entries that were not added or replaced by
+//! `grp-apply` have no record.
+
+use crate::toml_subset::{self, quote};
+use grp::temp_file::TempFile;
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    pub name: String,
+    pub source: String,
+    pub sha256: String,
+    pub converter: String,
+    pub tool: String,
+}
+
+/// The provenance file of an archive, e.g. `MOD.GRP.provenance`.
+pub fn sidecar_path(archive_path: &Path) -> PathBuf {
+    let mut path = archive_path.as_os_str().to_owned();
+    path.push(".provenance");
+    PathBuf::from(path)
+}
+
+/// The records of all entries, in the order they were recorded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProvenanceFile {
+    pub records: Vec<Provenance>,
+}
+impl ProvenanceFile {
+    /// Reads a provenance file, or starts an empty one if there is none.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(format!("Failed to read \"{}\": {}", path.display(), err)),
+        };
+        let mut records = Vec::new();
+        for table in toml_subset::parse(&source)? {
+            if table.name.is_empty() && table.values.is_empty() {
+                continue;
+            }
+            if table.name != "entry" || !table.is_array_item {
+                return Err(format!(
+                    "Unknown table [{}] (should be [[entry]]).",
+                    table.name
+                ));
+            }
+            records.push(Provenance {
+                name: table.require_str("name")?.to_string(),
+                source: table.require_str("source")?.to_string(),
+                sha256: table.require_str("sha256")?.to_string(),
+                converter: table.require_str("converter")?.to_string(),
+                tool: table.require_str("tool")?.to_string(),
+            });
+        }
+        Ok(Self { records })
+    }
+    /// Reads the provenance of `target`, the archive that editing
+    /// `archive_path` writes. An archive that is written elsewhere for the
+    /// first time starts from the provenance of the archive it is copied
+    /// from, and keeps to its own afterwards.
+    pub fn read_for(archive_path: &Path, target: &Path) -> Result<Self, String> {
+        let target_path = sidecar_path(target);
+        if target != archive_path && !target_path.exists() {
+            return Self::read(&sidecar_path(archive_path));
+        }
+        Self::read(&target_path)
+    }
+    /// The record of an entry, whose name is matched case-insensitively.
+    pub fn get(&self, name: &str) -> Option<&Provenance> {
+        self.records
+            .iter()
+            .find(|r| r.name.eq_ignore_ascii_case(name))
+    }
+    /// Records where an entry came from, replacing its previous record.
+    pub fn record(&mut self, provenance: Provenance) {
+        self.remove(&provenance.name);
+        self.records.push(provenance);
+    }
+    pub fn remove(&mut self, name: &str) {
+        self.records.retain(|r| !r.name.eq_ignore_ascii_case(name));
+    }
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let mut source =
+            String::from("# Where the entries were packed from, written by grp-apply.\n");
+        for record in &self.records {
+            source.push_str(&format!(
+                "\n[[entry]]\nname = {}\nsource = {}\nsha256 = {}\nconverter = {}\ntool = {}\n",
+                quote(&record.name),
+                quote(&record.source),
+                quote(&record.sha256),
+                quote(&record.converter),
+                quote(&record.tool)
+            ));
+        }
+        let mut file = TempFile::new(path)?;
+        file.file()
+            .write_all(source.as_bytes())
+            .map_err(|e| format!("Failed to write \"{}\": {}", path.display(), e))?;
+        file.persist()
+    }
+}
+
+#[test]
+fn should_record_provenance() {
+    let path = std::env::temp_dir().join("duke3d_should_record_provenance.provenance");
+    let _ = fs::remove_file(&path);
+
+    let mut provenance = ProvenanceFile::read(&path).unwrap();
+    let record = |name: &str, converter: &str| Provenance {
+        name: name.to_string(),
+        source: format!("art/{}", name),
+        sha256: "00".repeat(32),
+        converter: converter.to_string(),
+        tool: "duke3d-file-util 0.1.0".to_string(),
+    };
+    provenance.record(record("TILES020.ART", "copy"));
+    provenance.record(record("GAME.CON", "copy"));
+    provenance.record(record("tiles020.art", "png2art \"1.2\""));
+    provenance.remove("GAME.CON");
+    provenance.write(&path).unwrap();
+
+    let provenance = ProvenanceFile::read(&path).unwrap();
+    assert_eq!(
+        provenance.records,
+        [record("tiles020.art", "png2art \"1.2\"")]
+    );
+    assert!(provenance.get("TILES020.ART").is_some());
+    assert!(provenance.get("GAME.CON").is_none());
+}
+
+#[test]
+fn should_read_provenance_of_output_archives() {
+    let dir = std::env::temp_dir().join("duke3d_should_read_provenance_of_output_archives");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("out")).unwrap();
+    let archive_path = dir.join("MOD.GRP");
+    let output_path = dir.join("out").join("MOD.GRP");
+    let record = |name: &str| Provenance {
+        name: name.to_string(),
+        source: format!("art/{}", name),
+        sha256: "00".repeat(32),
+        converter: "copy".to_string(),
+        tool: "duke3d-file-util 0.1.0".to_string(),
+    };
+    let mut provenance = ProvenanceFile::default();
+    provenance.record(record("TILES000.ART"));
+    provenance.write(&sidecar_path(&archive_path)).unwrap();
+
+    // The first edit into the output directory starts from the original.
+    let mut provenance = ProvenanceFile::read_for(&archive_path, &output_path).unwrap();
+    assert!(provenance.get("TILES000.ART").is_some());
+    provenance.record(record("GAME.CON"));
+    provenance.write(&sidecar_path(&output_path)).unwrap();
+
+    // Later edits into it keep what was recorded there.
+    let provenance = ProvenanceFile::read_for(&archive_path, &output_path).unwrap();
+    assert!(provenance.get("TILES000.ART").is_some());
+    assert!(provenance.get("GAME.CON").is_some());
+    let original = ProvenanceFile::read_for(&archive_path, &archive_path).unwrap();
+    assert!(original.get("GAME.CON").is_none());
+}
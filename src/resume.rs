@@ -0,0 +1,71 @@
+//! State files that let interrupted batch commands continue where they stopped.
+
+use std::{
+    collections::BTreeSet,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Completed items of a batch, one per line. Every item is appended as soon
+/// as it is completed, so the state survives the process being killed.
+pub struct ResumeState {
+    completed: BTreeSet<String>,
+    file: File,
+    path: PathBuf,
+}
+impl ResumeState {
+    /// Opens a state file, reading the items completed by previous runs.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let completed = match fs::read_to_string(path) {
+            Ok(contents) => contents.lines().map(str::to_string).collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeSet::new(),
+            Err(err) => return Err(format!("Failed to read \"{}\": {}", path.display(), err)),
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open \"{}\": {}", path.display(), e))?;
+        Ok(Self {
+            completed,
+            file,
+            path: path.to_path_buf(),
+        })
+    }
+    pub fn is_completed(&self, item: &str) -> bool {
+        self.completed.contains(item)
+    }
+    pub fn complete(&mut self, item: &str) -> Result<(), String> {
+        writeln!(self.file, "{}", item)
+            .and_then(|_| self.file.sync_data())
+            .map_err(|e| format!("Failed to write \"{}\": {}", self.path.display(), e))?;
+        self.completed.insert(item.to_string());
+        Ok(())
+    }
+    /// Removes the state file once the whole batch is done,
+    /// so that the next run starts from the beginning.
+    pub fn finish(self) -> Result<(), String> {
+        drop(self.file);
+        fs::remove_file(&self.path)
+            .map_err(|e| format!("Failed to remove \"{}\": {}", self.path.display(), e))
+    }
+}
+
+#[test]
+fn should_remember_completed_items() {
+    let path = std::env::temp_dir().join("duke3d_should_remember_completed_items.state");
+    let _ = fs::remove_file(&path);
+
+    let mut state = ResumeState::open(&path).unwrap();
+    state.complete("GAME.CON").unwrap();
+    assert!(state.is_completed("GAME.CON"));
+    drop(state);
+
+    let mut state = ResumeState::open(&path).unwrap();
+    assert!(state.is_completed("GAME.CON"));
+    assert!(!state.is_completed("E1L1.MAP"));
+    state.complete("E1L1.MAP").unwrap();
+    state.finish().unwrap();
+    assert!(!path.exists());
+}
@@ -0,0 +1,136 @@
+//! Substitution of tile numbers in maps and CON scripts.
+//!
+//! All numbers of a substitution are replaced at once, so shifting a range
+//! of tiles onto itself, e.g. 100 -> 110 and 110 -> 120, works as expected.
+
+use grp::GrpFileReader;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{Read, Seek},
+};
+
+/// Replaces the tile numbers that are keys of `tiles` with their values in
+/// the sectors, walls and sprites of a map in place. Returns the number of
+/// replaced references. The map is left unchanged when it cannot be read.
+pub fn retile_map(data: &mut [u8], tiles: &BTreeMap<i16, i16>) -> Result<usize, String> {
+    let mut map = map::Map::parse(data)?;
+    let mut replaced = 0;
+    let mut retile = |picnum: &mut i16| {
+        if let Some(to) = tiles.get(picnum) {
+            *picnum = *to;
+            replaced += 1;
+        }
+    };
+    for sector in &mut map.sectors {
+        retile(&mut sector.ceiling_picnum);
+        retile(&mut sector.floor_picnum);
+    }
+    for wall in &mut map.walls {
+        retile(&mut wall.picnum);
+        retile(&mut wall.over_picnum);
+    }
+    for sprite in &mut map.sprites {
+        retile(&mut sprite.picnum);
+    }
+
+    // The records keep their size, and anything after them is kept as it is.
+    let bytes = map.to_bytes();
+    if let Some(records) = data.get_mut(..bytes.len()) {
+        records.copy_from_slice(&bytes);
+    }
+    Ok(replaced)
+}
+
+/// Replaces the tile numbers that are keys of `tiles` with their values
+/// wherever a CON script refers to a tile with a number, see
+/// [`con::find_tile_references`]. Only the defines named in `tile_names`
+/// are rewritten, so that defines of sounds and other numbers that equal
+/// a tile number are kept. Returns the rewritten script and the number of
+/// replaced references.
+pub fn retile_con(
+    source: &[u8],
+    tiles: &BTreeMap<i16, i16>,
+    tile_names: &BTreeSet<String>,
+) -> (Vec<u8>, usize) {
+    let mut output = Vec::with_capacity(source.len());
+    let mut copied_until = 0;
+    let mut replaced = 0;
+
+    for token in con::find_tile_references(source, tile_names) {
+        let text = String::from_utf8_lossy(token.text(source));
+        let Some(to) = text.parse().ok().and_then(|tile| tiles.get(&tile)) else {
+            continue;
+        };
+        output.extend_from_slice(&source[copied_until..token.start]);
+        output.extend_from_slice(to.to_string().as_bytes());
+        copied_until = token.end;
+        replaced += 1;
+    }
+    output.extend_from_slice(&source[copied_until..]);
+
+    (output, replaced)
+}
+
+/// The names of the defines that the CON scripts of an archive use as
+/// tiles, see [`con::find_tile_names`].
+pub fn tile_names<R: Read + Seek>(
+    grp_reader: &mut GrpFileReader<R>,
+) -> Result<BTreeSet<String>, String> {
+    let mut names = BTreeSet::new();
+    for entry in grp_reader.get_file_entries()? {
+        let name = entry.name().to_string();
+        if crate::game::active().is_con(&name) {
+            names.extend(con::find_tile_names(&grp_reader.read_file(&entry)?));
+        }
+    }
+    Ok(names)
+}
+
+#[test]
+fn should_retile_maps_and_scripts() {
+    let mut original = map::testing::two_rooms();
+    original.sectors[0].floor_picnum = 1405;
+    original.walls[0].picnum = 1405;
+    original.walls[0].over_picnum = 1406;
+    original.sprites[0].picnum = 1405;
+    let original = original.to_bytes();
+
+    let mut map = original.clone();
+    let forward = BTreeMap::from([(1405, 1406), (1406, 1407)]);
+    assert_eq!(retile_map(&mut map, &forward), Ok(4));
+    assert_eq!(map::Map::parse(&map).unwrap().sectors[0].floor_picnum, 1406);
+    let backward = BTreeMap::from([(1406, 1405), (1407, 1406)]);
+    assert_eq!(retile_map(&mut map, &backward), Ok(4));
+    assert_eq!(map, original);
+    assert!(retile_map(&mut map[..original.len() - 1], &forward).is_err());
+
+    let defs = b"define CRACK1 1405\ndefine CRACKSTRENGTH 1405\n";
+    let game = b"spawn CRACK1 // 1405\nmove 1405\nactor 1405 CRACKSTRENGTH";
+    let tile_names = con::find_tile_names(game);
+    let tiles = BTreeMap::from([(1405, 3585)]);
+    let (script, replaced) = retile_con(defs, &tiles, &tile_names);
+    assert_eq!(script, b"define CRACK1 3585\ndefine CRACKSTRENGTH 1405\n");
+    assert_eq!(replaced, 1);
+    let (script, replaced) = retile_con(game, &tiles, &tile_names);
+    assert_eq!(
+        script,
+        b"spawn CRACK1 // 1405\nmove 1405\nactor 3585 CRACKSTRENGTH"
+    );
+    assert_eq!(replaced, 1);
+}
+
+#[test]
+fn should_keep_non_tile_defines_when_renumbering() {
+    // Renumbering the first .art file moves every small tile number.
+    let tiles = (0..=255).map(|tile| (tile, tile + 1024)).collect();
+    let script = b"define RECON 5\ndefine RECONSTRENGTH 10\ndefine SHORTSOUND 5\n\
+        actor RECON RECONSTRENGTH sound SHORTSOUND spawn 10 enda";
+    let tile_names = con::find_tile_names(script);
+    let (script, replaced) = retile_con(script, &tiles, &tile_names);
+    assert_eq!(
+        String::from_utf8_lossy(&script),
+        "define RECON 1029\ndefine RECONSTRENGTH 10\ndefine SHORTSOUND 5\n\
+        actor RECON RECONSTRENGTH sound SHORTSOUND spawn 1034 enda"
+    );
+    assert_eq!(replaced, 2);
+}
@@ -0,0 +1,206 @@
+//! End-to-end checks of this build on synthetic data, run by `selftest`
+//! before trusting the tool with real game data.
+//!
+//! An archive is packed from a generated .art, .map and .voc file, and every
+//! check then reads, extracts or validates it in the given directory.
+
+use crate::{extract::Extractor, texture_check, verify};
+use art::{testing::ArtFixture, ArtFileReader, TileSet};
+use grp::{GrpBuilder, GrpFileReader};
+use map::Map;
+use std::{fs, fs::File, path::Path};
+
+/// A minimal Creative Voice File: the header, one block of 8-bit sound
+/// data at 11025 Hz and the terminator block.
+pub(crate) fn voc_fixture() -> Vec<u8> {
+    let mut voc = b"Creative Voice File\x1a".to_vec();
+    voc.extend_from_slice(&26u16.to_le_bytes());
+    voc.extend_from_slice(&0x010au16.to_le_bytes());
+    voc.extend_from_slice(&(!0x010au16).wrapping_add(0x1234).to_le_bytes());
+    let samples = (0..64u8)
+        .map(|i| 128u8.wrapping_add(i % 16 * 4))
+        .collect::<Vec<_>>();
+    voc.push(1);
+    voc.extend_from_slice(&(samples.len() as u32 + 2).to_le_bytes()[..3]);
+    voc.push((256 - 1_000_000 / 11025) as u8);
+    voc.push(0);
+    voc.extend_from_slice(&samples);
+    voc.push(0);
+    voc
+}
+
+fn fixtures() -> [(&'static str, Vec<u8>); 3] {
+    [
+        ("TILES000.ART", ArtFixture::new(0).tile(64, 64, 31).build()),
+        ("E1L1.MAP", map::testing::two_rooms().to_bytes()),
+        ("BOSS1.VOC", voc_fixture()),
+    ]
+}
+
+fn open(archive_path: &Path) -> Result<GrpFileReader<File>, String> {
+    File::open(archive_path)
+        .map_err(|e| format!("Failed to open \"{}\": {}", archive_path.display(), e))
+        .and_then(GrpFileReader::new)
+}
+
+fn read_entry(archive_path: &Path, name: &str) -> Result<Vec<u8>, String> {
+    let mut grp_reader = open(archive_path)?;
+    let entry = grp_reader
+        .find_file_entry(name)?
+        .ok_or_else(|| format!("Entry \"{}\" not found.", name))?;
+    grp_reader.read_file(&entry)
+}
+
+fn check_pack(archive_path: &Path) -> Result<(), String> {
+    let entries = open(archive_path)?.get_file_entries()?;
+    let listed = entries
+        .iter()
+        .map(|e| (e.name().to_string(), e.size() as usize))
+        .collect::<Vec<_>>();
+    let expected = fixtures().map(|(name, data)| (name.to_string(), data.len()));
+    if listed != expected {
+        return Err(format!(
+            "Expected entries {:?}, got {:?}.",
+            expected, listed
+        ));
+    }
+    Ok(())
+}
+
+fn check_extract(archive_path: &Path, dir: &Path) -> Result<(), String> {
+    let output_dir = dir.join("extracted");
+    fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create \"{}\": {}", output_dir.display(), e))?;
+    let extractor = Extractor::new(archive_path, &Default::default())?;
+    let mut grp_reader = open(archive_path)?;
+    for (name, data) in fixtures() {
+        let entry = grp_reader
+            .find_file_entry(name)?
+            .ok_or_else(|| format!("Entry \"{}\" not found.", name))?;
+        let path = output_dir.join(name);
+        extractor.extract(&mut grp_reader, &entry, &path)?;
+        let extracted =
+            fs::read(&path).map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+        if extracted != data {
+            return Err(format!("\"{}\" differs from the packed file.", name));
+        }
+    }
+    Ok(())
+}
+
+fn check_hash(archive_path: &Path) -> Result<(), String> {
+    let mut grp_reader = open(archive_path)?;
+    for (entry, (name, data)) in grp_reader.get_file_entries()?.iter().zip(fixtures()) {
+        if verify::hash_entry(&mut grp_reader, entry)? != grp::sha256(&data) {
+            return Err(format!("The hash of \"{}\" does not match.", name));
+        }
+    }
+    Ok(())
+}
+
+fn check_art(archive_path: &Path) -> Result<(), String> {
+    let mut grp_reader = open(archive_path)?;
+    let mut art_reader = grp_reader
+        .open_entry("TILES000.ART")?
+        .ok_or("Entry \"TILES000.ART\" not found.")
+        .map_err(str::to_string)
+        .and_then(ArtFileReader::new)?;
+    let tiles = art_reader.read_tiles()?;
+    let [tile] = tiles.as_slice() else {
+        return Err(format!("Expected 1 tile, got {}.", tiles.len()));
+    };
+    if (tile.number(), tile.width(), tile.height()) != (0, 64, 64) {
+        return Err(format!(
+            "Expected tile 0 of 64x64, got tile {} of {}x{}.",
+            tile.number(),
+            tile.width(),
+            tile.height()
+        ));
+    }
+    if art_reader.read_pixels(tile)?.iter().any(|p| *p != 31) {
+        return Err("The pixels of tile 0 differ from the packed file.".to_string());
+    }
+    Ok(())
+}
+
+fn check_map(archive_path: &Path, dir: &Path) -> Result<Map, String> {
+    let map = Map::parse(&read_entry(archive_path, "E1L1.MAP")?)?;
+    if map != map::testing::two_rooms() {
+        return Err("E1L1.MAP differs from the packed map.".to_string());
+    }
+    // The converted map is written and read back as by map-convert.
+    let path = dir.join("E1L1.MAP");
+    fs::write(&path, map.to_bytes())
+        .map_err(|e| format!("Failed to write \"{}\": {}", path.display(), e))?;
+    let data =
+        fs::read(&path).map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+    if Map::parse(&data)? != map {
+        return Err("E1L1.MAP changed when written and read back.".to_string());
+    }
+    Ok(map)
+}
+
+fn check_tiles(archive_path: &Path, map: &Map) -> Result<(), String> {
+    let mut grp_reader = open(archive_path)?;
+    let tiles = grp_reader
+        .open_entry("TILES000.ART")?
+        .ok_or("Entry \"TILES000.ART\" not found.")
+        .map_err(str::to_string)
+        .and_then(ArtFileReader::new)?
+        .read_tiles()?;
+    let mut tile_set = TileSet::new();
+    tile_set.add_file("TILES000.ART", tiles);
+    match texture_check::check(map, &tile_set).first() {
+        Some(problem) => Err(format!("Tile {}: {}", problem.picnum, problem.reason)),
+        None => Ok(()),
+    }
+}
+
+/// Runs every check in `dir`, which should be empty, and returns the name
+/// and result of each check in the order they were run.
+pub fn run(dir: &Path) -> Vec<(&'static str, Result<(), String>)> {
+    let input_dir = dir.join("input");
+    let archive_path = dir.join("SELFTEST.GRP");
+    let packed = fs::create_dir_all(&input_dir)
+        .map_err(|e| format!("Failed to create \"{}\": {}", input_dir.display(), e))
+        .and_then(|_| {
+            // Packed from files, as by grp-pack.
+            let mut builder = GrpBuilder::new();
+            for (name, data) in fixtures() {
+                let path = input_dir.join(name);
+                fs::write(&path, data)
+                    .map_err(|e| format!("Failed to write \"{}\": {}", path.display(), e))?;
+                builder = builder.add_from_path_streamed(&path)?;
+            }
+            builder.write_to_file(&archive_path)
+        });
+    // The other checks need the archive.
+    let packed = packed.and_then(|_| check_pack(&archive_path));
+    if packed.is_err() {
+        return vec![("pack", packed)];
+    }
+    let mut results = vec![("pack", packed)];
+
+    results.push(("extract", check_extract(&archive_path, dir)));
+    results.push(("hash", check_hash(&archive_path)));
+    results.push(("art", check_art(&archive_path)));
+    match check_map(&archive_path, dir) {
+        Ok(map) => {
+            results.push(("map", Ok(())));
+            results.push(("tiles", check_tiles(&archive_path, &map)));
+        }
+        Err(e) => results.push(("map", Err(e))),
+    }
+    results
+}
+
+#[test]
+fn should_pass_every_check() {
+    let dir = std::env::temp_dir().join("duke3d_should_pass_every_check");
+    let _ = fs::remove_dir_all(&dir);
+    let results = run(&dir);
+    assert_eq!(results.len(), 6);
+    for (name, result) in results {
+        assert_eq!(result, Ok(()), "{}", name);
+    }
+}
@@ -0,0 +1,300 @@
+//! Statistics of the assets of a whole game archive, printed by `stats` as
+//! an overview for preservation catalogs and mod databases.
+//!
+//! Entries are grouped by their extension. Tiles are read from the .art
+//! entries, sound lengths from the headers of the .voc and .wav entries and
+//! episodes from the names of the maps, e.g. E2L3.MAP.
+
+use crate::{game, json};
+use art::{ArtFileReader, TileSet};
+use grp::GrpFileReader;
+use std::{
+    collections::BTreeMap,
+    io::{Read, Seek},
+};
+
+/// The number and total size of the entries of one type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TypeStats {
+    pub count: usize,
+    pub bytes: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TileStats {
+    pub tiles: usize,
+    /// Tiles with pixels, of the tile numbers the game can use.
+    pub non_empty: usize,
+    pub pixel_bytes: usize,
+}
+impl TileStats {
+    /// The share of the tile numbers of the game used by non-empty tiles, in percent.
+    pub fn utilization(&self) -> f64 {
+        self.non_empty as f64 * 100.0 / game::active().tile_limit as f64
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SoundStats {
+    pub count: usize,
+    pub seconds: f64,
+    /// Sounds whose header could not be read, which count for no time.
+    pub unreadable: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameStats {
+    pub entries: usize,
+    pub bytes: u64,
+    /// By the uppercase extension, or an empty string for entries without one.
+    pub types: BTreeMap<String, TypeStats>,
+    pub tiles: TileStats,
+    pub sounds: SoundStats,
+    /// The number of maps of each episode.
+    pub episodes: BTreeMap<u32, usize>,
+    /// Maps not named after an episode and level.
+    pub user_maps: usize,
+}
+impl GameStats {
+    /// Reads the table of the archive and every .art, .voc and .wav entry.
+    pub fn collect<R: Read + Seek>(grp_reader: &mut GrpFileReader<R>) -> Result<Self, String> {
+        let mut stats = Self::default();
+        let mut tile_set = TileSet::new();
+        for entry in grp_reader.get_file_entries()? {
+            let name = entry.name().to_string();
+            let extension = name.rsplit_once('.').map_or("", |(_, e)| e).to_string();
+            stats.entries += 1;
+            stats.bytes += entry.size() as u64;
+            let type_stats = stats.types.entry(extension.clone()).or_default();
+            type_stats.count += 1;
+            type_stats.bytes += entry.size() as u64;
+
+            match extension.as_str() {
+                _ if game::active().is_art(&name) => {
+                    let tiles = ArtFileReader::new(grp_reader.open_file(&entry)?)
+                        .and_then(|mut art_reader| art_reader.read_tiles())
+                        .map_err(|e| format!("Failed to read \"{}\": {}", name, e))?;
+                    tile_set.add_file(name, tiles);
+                }
+                _ if game::active().is_map(&name) => match episode(&name) {
+                    Some(episode) => *stats.episodes.entry(episode).or_default() += 1,
+                    None => stats.user_maps += 1,
+                },
+                "VOC" | "WAV" => {
+                    let data = grp_reader.read_file(&entry)?;
+                    let seconds = if extension == "VOC" {
+                        voc_seconds(&data)
+                    } else {
+                        wav_seconds(&data)
+                    };
+                    stats.sounds.count += 1;
+                    match seconds {
+                        Some(seconds) => stats.sounds.seconds += seconds,
+                        None => stats.sounds.unreadable += 1,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let tiles = tile_set.tiles().collect::<Vec<_>>();
+        stats.tiles = TileStats {
+            tiles: tiles.len(),
+            non_empty: tiles
+                .iter()
+                .filter(|t| t.pixel_count() > 0 && t.number() < game::active().tile_limit)
+                .count(),
+            pixel_bytes: tiles.iter().map(|t| t.pixel_count()).sum(),
+        };
+        Ok(stats)
+    }
+
+    pub fn to_json(&self) -> String {
+        let types = self
+            .types
+            .iter()
+            .map(|(extension, t)| {
+                format!(
+                    "{{\"type\":{},\"count\":{},\"bytes\":{}}}",
+                    json::string(extension),
+                    t.count,
+                    t.bytes
+                )
+            })
+            .collect::<Vec<_>>();
+        let episodes = self
+            .episodes
+            .iter()
+            .map(|(episode, count)| format!("\"{}\":{}", episode, count))
+            .collect::<Vec<_>>();
+        format!(
+            concat!(
+                "{{\"entries\":{},\"bytes\":{},\"types\":[{}],",
+                "\"tiles\":{{\"tiles\":{},\"non_empty\":{},\"limit\":{},\"pixel_bytes\":{}}},",
+                "\"sounds\":{{\"count\":{},\"seconds\":{:.3},\"unreadable\":{}}},",
+                "\"maps\":{{\"episodes\":{{{}}},\"user\":{}}}}}"
+            ),
+            self.entries,
+            self.bytes,
+            types.join(","),
+            self.tiles.tiles,
+            self.tiles.non_empty,
+            game::active().tile_limit,
+            self.tiles.pixel_bytes,
+            self.sounds.count,
+            self.sounds.seconds,
+            self.sounds.unreadable,
+            episodes.join(","),
+            self.user_maps
+        )
+    }
+}
+
+/// The episode of a map named after its episode and level, e.g. 2 for E2L3.MAP.
+pub fn episode(name: &str) -> Option<u32> {
+    let (base, extension) = name.rsplit_once('.')?;
+    if !extension.eq_ignore_ascii_case(game::active().map_extension) {
+        return None;
+    }
+    let (episode, level) = base.strip_prefix('E')?.split_once('L')?;
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    (is_number(episode) && is_number(level))
+        .then(|| episode.parse().ok())
+        .flatten()
+}
+
+/// The length of a Creative Voice File, from its sound data and silence blocks.
+pub fn voc_seconds(data: &[u8]) -> Option<f64> {
+    if !data.starts_with(b"Creative Voice File\x1a") {
+        return None;
+    }
+    let first_block = u16::from_le_bytes(*data.get(20..)?.first_chunk()?) as usize;
+    let mut blocks = data.get(first_block..)?;
+    let mut seconds = 0.0;
+    // Continuation blocks have the sample rate of the block before them.
+    let mut sample_rate = 0.0;
+    while let Some((&block_type, rest)) = blocks.split_first() {
+        if block_type == 0 {
+            return Some(seconds);
+        }
+        let (&[a, b, c], rest) = rest.split_first_chunk::<3>()?;
+        let length = u32::from_le_bytes([a, b, c, 0]) as usize;
+        let block = rest.get(..length)?;
+        match block_type {
+            1 => {
+                let &[rate_byte, _codec] = block.first_chunk::<2>()?;
+                sample_rate = 1_000_000.0 / (256 - rate_byte as u32) as f64;
+                seconds += (length - 2) as f64 / sample_rate;
+            }
+            2 if sample_rate > 0.0 => seconds += length as f64 / sample_rate,
+            3 => {
+                let &[low, high, rate_byte] = block.first_chunk::<3>()?;
+                let samples = u16::from_le_bytes([low, high]) as u32 + 1;
+                seconds += samples as f64 * (256 - rate_byte as u32) as f64 / 1_000_000.0;
+            }
+            9 => {
+                let (rate, format) = block.split_first_chunk::<4>()?;
+                let &[bits, channels] = format.first_chunk::<2>()?;
+                let bytes_per_second =
+                    u32::from_le_bytes(*rate) as f64 * bits as f64 / 8.0 * channels as f64;
+                if bytes_per_second > 0.0 {
+                    seconds += length.saturating_sub(12) as f64 / bytes_per_second;
+                }
+            }
+            _ => {}
+        }
+        blocks = rest.get(length..)?;
+    }
+    // Some files end without the terminator block.
+    Some(seconds)
+}
+
+/// The length of a RIFF WAVE file, from the size of its data chunk.
+pub fn wav_seconds(data: &[u8]) -> Option<f64> {
+    if data.get(..4)? != b"RIFF" || data.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let mut chunks = data.get(12..)?;
+    let mut bytes_per_second = None;
+    while let Some((id, rest)) = chunks.split_first_chunk::<4>() {
+        let (size, rest) = rest.split_first_chunk::<4>()?;
+        let size = u32::from_le_bytes(*size) as usize;
+        match id {
+            b"fmt " => {
+                let byte_rate = rest.get(8..12)?.first_chunk::<4>()?;
+                bytes_per_second = Some(u32::from_le_bytes(*byte_rate)).filter(|r| *r > 0);
+            }
+            b"data" => return Some(size as f64 / bytes_per_second? as f64),
+            _ => {}
+        }
+        // Chunks are padded to an even size.
+        chunks = rest.get((size + size % 2).min(rest.len())..)?;
+    }
+    None
+}
+
+#[test]
+fn should_collect_game_stats() {
+    let mut wav = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&[1, 0, 1, 0]);
+    wav.extend_from_slice(&8000u32.to_le_bytes());
+    wav.extend_from_slice(&8000u32.to_le_bytes());
+    wav.extend_from_slice(&[1, 0, 8, 0]);
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&4000u32.to_le_bytes());
+    wav.extend_from_slice(&[128; 4000]);
+
+    let grp = grp::testing::GrpFixture::new()
+        .entry(
+            "TILES000.ART",
+            &art::testing::ArtFixture::new(0)
+                .tile(8, 8, 1)
+                .tile(0, 0, 0)
+                .build(),
+        )
+        .entry("E1L1.MAP", &[7, 0, 0, 0])
+        .entry("E1L2.MAP", &[7, 0, 0, 0])
+        .entry("E4L11.MAP", &[7, 0, 0, 0])
+        .entry("DUKEDC1.MAP", &[7, 0, 0, 0])
+        .entry("BOSS1.VOC", &crate::selftest::voc_fixture())
+        .entry("JUMP.WAV", &wav)
+        .entry("BROKEN.VOC", b"Creative")
+        .entry("LICENSE", b"")
+        .build();
+    let mut grp_reader = GrpFileReader::new(std::io::Cursor::new(grp)).unwrap();
+    let stats = GameStats::collect(&mut grp_reader).unwrap();
+
+    assert_eq!(stats.entries, 9);
+    assert_eq!(
+        stats.types["MAP"],
+        TypeStats {
+            count: 4,
+            bytes: 16
+        }
+    );
+    assert_eq!(stats.types[""].count, 1);
+    assert_eq!(
+        stats.tiles,
+        TileStats {
+            tiles: 2,
+            non_empty: 1,
+            pixel_bytes: 64
+        }
+    );
+    assert_eq!((stats.sounds.count, stats.sounds.unreadable), (3, 1));
+    // Half a second of the WAV and 64 samples at 1000000 / 90 Hz.
+    assert!((stats.sounds.seconds - (0.5 + 64.0 * 90.0 / 1_000_000.0)).abs() < 1e-9);
+    assert_eq!(stats.episodes, BTreeMap::from([(1, 2), (4, 1)]));
+    assert_eq!(stats.user_maps, 1);
+
+    let json = json::parse(&stats.to_json()).unwrap();
+    assert_eq!(json.get("entries").and_then(|e| e.as_u64()), Some(9));
+    let maps = json.get("maps").unwrap();
+    assert_eq!(
+        maps.get("episodes")
+            .and_then(|e| e.get("1"))
+            .and_then(|c| c.as_u64()),
+        Some(2)
+    );
+}
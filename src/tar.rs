@@ -0,0 +1,141 @@
+//! A minimal writer of ustar archives, enough for `tar -x` to unpack the
+//! entries of an archive as regular files.
+//!
+//! Entries are written as they are read, so a tar can be streamed to a pipe.
+
+use std::io::{self, Read, Write};
+
+const BLOCK_SIZE: usize = 512;
+
+/// Writes regular files one after another, followed by the end-of-archive
+/// marker on [`TarWriter::finish`].
+pub struct TarWriter<W: Write> {
+    writer: W,
+}
+impl<W: Write> TarWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+    /// Writes a file of `size` bytes read from `data`, which must provide
+    /// exactly that many.
+    pub fn append(
+        &mut self,
+        name: &str,
+        size: u64,
+        mtime: u64,
+        data: &mut impl Read,
+    ) -> Result<(), String> {
+        let header = header(name, size, mtime)?;
+        let written = self
+            .writer
+            .write_all(&header)
+            .and_then(|_| io::copy(&mut data.take(size), &mut self.writer))
+            .map_err(|e| format!("Failed to write \"{}\": {}", name, e))?;
+        if written != size {
+            return Err(format!(
+                "Failed to write \"{}\": expected {} bytes, got {}.",
+                name, size, written
+            ));
+        }
+        // Contents are padded to whole blocks.
+        let padding = (BLOCK_SIZE - (size % BLOCK_SIZE as u64) as usize) % BLOCK_SIZE;
+        self.writer
+            .write_all(&[0; BLOCK_SIZE][..padding])
+            .map_err(|e| format!("Failed to write \"{}\": {}", name, e))
+    }
+    /// Writes the two empty blocks that end an archive and returns the writer.
+    pub fn finish(mut self) -> Result<W, String> {
+        self.writer
+            .write_all(&[0; 2 * BLOCK_SIZE])
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| format!("Failed to write the end of the tar: {}", e))?;
+        Ok(self.writer)
+    }
+}
+
+/// Writes `value` in octal, padded with zeros and terminated with NUL.
+fn write_octal(field: &mut [u8], value: u64) -> Result<(), String> {
+    let digits = format!(
+        "{:0width$o}\0",
+        value,
+        width = field.len().saturating_sub(1)
+    );
+    if digits.len() != field.len() {
+        return Err(format!("{} does not fit a tar header.", value));
+    }
+    field.copy_from_slice(digits.as_bytes());
+    Ok(())
+}
+
+fn header(name: &str, size: u64, mtime: u64) -> Result<[u8; BLOCK_SIZE], String> {
+    if name.len() > 100 {
+        return Err(format!("\"{}\" is too long for a tar header.", name));
+    }
+    let mut header = [0; BLOCK_SIZE];
+    let fields = [
+        (0..100, name.as_bytes()),
+        (156..157, b"0".as_slice()),
+        (257..263, b"ustar\0"),
+        (263..265, b"00"),
+    ];
+    for (range, value) in fields {
+        if let Some(field) = header.get_mut(range.start..range.start + value.len()) {
+            field.copy_from_slice(value);
+        }
+    }
+    let numbers = [
+        (100..108, 0o644),
+        (108..116, 0),
+        (116..124, 0),
+        (124..136, size),
+        (136..148, mtime),
+    ];
+    for (range, value) in numbers {
+        if let Some(field) = header.get_mut(range) {
+            write_octal(field, value)?;
+        }
+    }
+    // The checksum is the sum of the header bytes with its own field
+    // counted as spaces, written as six digits, NUL and a space.
+    let checksum = header.iter().map(|b| *b as u64).sum::<u64>() + 8 * b' ' as u64;
+    if let Some(field) = header.get_mut(148..156) {
+        write_octal(field.get_mut(..7).unwrap_or_default(), checksum)?;
+        if let Some(last) = field.last_mut() {
+            *last = b' ';
+        }
+    }
+    Ok(header)
+}
+
+#[test]
+fn should_write_ustar_archive() {
+    let mut tar = TarWriter::new(Vec::new());
+    tar.append("GAME.CON", 6, 817_776_000, &mut &b"define"[..])
+        .unwrap();
+    tar.append("EMPTY.TXT", 0, 0, &mut &b""[..]).unwrap();
+    let bytes = tar.finish().unwrap();
+
+    assert_eq!(bytes.len(), 5 * BLOCK_SIZE);
+    assert_eq!(&bytes[..9], b"GAME.CON\0");
+    assert_eq!(&bytes[124..136], b"00000000006\0");
+    assert_eq!(&bytes[136..148], b"06057442600\0");
+    assert_eq!(&bytes[257..265], b"ustar\x0000");
+    let checksum = bytes[..BLOCK_SIZE]
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            if (148..156).contains(&i) {
+                32
+            } else {
+                *b as u64
+            }
+        })
+        .sum::<u64>();
+    assert_eq!(&bytes[148..156], format!("{:06o}\0 ", checksum).as_bytes());
+    assert_eq!(&bytes[BLOCK_SIZE..BLOCK_SIZE + 7], b"define\0");
+    assert_eq!(&bytes[2 * BLOCK_SIZE..2 * BLOCK_SIZE + 10], b"EMPTY.TXT\0");
+    assert!(bytes[3 * BLOCK_SIZE..].iter().all(|b| *b == 0));
+
+    let mut tar = TarWriter::new(Vec::new());
+    assert!(tar.append("SHORT.TXT", 10, 0, &mut &b"abc"[..]).is_err());
+}
@@ -0,0 +1,221 @@
+//! The trigram index of the text entries of an archive, kept in a sidecar
+//! file next to it so that `grp-grep` only reads entries that can match.
+//!
+//! The index lists, per text entry, every sequence of three bytes found in
+//! it, folded to lowercase. An entry can only contain a pattern if it
+//! contains all trigrams of the pattern. The sidecar records the size and
+//! modification time of the archive and is ignored once they change.
+
+use grp::{EntryName, GrpFileReader};
+use std::{
+    fs,
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+const SIGNATURE: &[u8; 8] = b"D3DTIDX1";
+
+/// The path of the sidecar index of an archive, e.g. `MOD.GRP.index`.
+pub fn sidecar_path(archive_path: &Path) -> PathBuf {
+    let mut path = archive_path.as_os_str().to_owned();
+    path.push(".index");
+    PathBuf::from(path)
+}
+
+/// Whether an entry looks like text: it has no NUL bytes and no control
+/// characters other than whitespace and the DOS end-of-file marker.
+pub fn is_text(data: &[u8]) -> bool {
+    data.iter()
+        .all(|b| *b >= 0x20 || matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1a))
+}
+
+/// The distinct trigrams of `data` in ascending order, case-insensitively.
+pub fn trigrams(data: &[u8]) -> Vec<u32> {
+    let mut trigrams = data
+        .windows(3)
+        .map(|w| {
+            w.iter()
+                .fold(0, |t, b| t << 8 | b.to_ascii_lowercase() as u32)
+        })
+        .collect::<Vec<_>>();
+    trigrams.sort_unstable();
+    trigrams.dedup();
+    trigrams
+}
+
+/// The lines of `data` that contain `pattern`, case-insensitively, with
+/// their line numbers starting from 1.
+pub fn matching_lines<'a>(data: &'a [u8], pattern: &[u8]) -> Vec<(usize, &'a [u8])> {
+    data.split(|b| *b == b'\n')
+        .enumerate()
+        .filter(|(_, line)| {
+            pattern.is_empty()
+                || line
+                    .windows(pattern.len())
+                    .any(|w| w.eq_ignore_ascii_case(pattern))
+        })
+        .map(|(i, line)| (i + 1, line.strip_suffix(b"\r").unwrap_or(line)))
+        .collect()
+}
+
+/// The archive an index was built from, to detect a stale sidecar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArchiveStamp {
+    pub size: u64,
+    pub modified: SystemTime,
+}
+impl ArchiveStamp {
+    pub fn of(archive_path: &Path) -> Result<Self, String> {
+        let metadata = fs::metadata(archive_path)
+            .map_err(|e| format!("Failed to read \"{}\": {}", archive_path.display(), e))?;
+        Ok(Self {
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .map_err(|e| format!("Failed to read \"{}\": {}", archive_path.display(), e))?,
+        })
+    }
+}
+
+/// The trigram index of the text entries of one archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextIndex {
+    pub stamp: ArchiveStamp,
+    pub entries: Vec<(EntryName, Vec<u32>)>,
+}
+impl TextIndex {
+    /// Reads every entry of the archive and indexes the text entries.
+    pub fn build<R: Read + Seek>(
+        grp_reader: &mut GrpFileReader<R>,
+        stamp: ArchiveStamp,
+    ) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for entry in grp_reader.get_file_entries()? {
+            let data = grp_reader.read_file(&entry)?;
+            if is_text(&data) {
+                entries.push((entry.name(), trigrams(&data)));
+            }
+        }
+        Ok(Self { stamp, entries })
+    }
+    /// The text entries that contain all trigrams of `pattern`, which is
+    /// every text entry for patterns shorter than three bytes.
+    pub fn candidates(&self, pattern: &[u8]) -> Vec<EntryName> {
+        let wanted = trigrams(pattern);
+        self.entries
+            .iter()
+            .filter(|(_, trigrams)| wanted.iter().all(|t| trigrams.binary_search(t).is_ok()))
+            .map(|(name, _)| *name)
+            .collect()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let modified = self
+            .stamp
+            .modified
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let mut bytes = SIGNATURE.to_vec();
+        bytes.extend_from_slice(&self.stamp.size.to_le_bytes());
+        bytes.extend_from_slice(&modified.as_secs().to_le_bytes());
+        bytes.extend_from_slice(&modified.subsec_nanos().to_le_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for (name, trigrams) in &self.entries {
+            bytes.extend_from_slice(name.raw());
+            bytes.extend_from_slice(&(trigrams.len() as u32).to_le_bytes());
+            for trigram in trigrams {
+                bytes.extend_from_slice(&trigram.to_le_bytes());
+            }
+        }
+        bytes
+    }
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes };
+        if reader.take::<8>()? != *SIGNATURE {
+            return Err("Not a text index.".to_string());
+        }
+        let size = u64::from_le_bytes(reader.take()?);
+        let secs = u64::from_le_bytes(reader.take()?);
+        let nanos = u32::from_le_bytes(reader.take()?);
+        let entry_count = u32::from_le_bytes(reader.take()?);
+        let mut entries = Vec::new();
+        for _ in 0..entry_count {
+            let name = EntryName::from_raw(reader.take()?);
+            let trigram_count = u32::from_le_bytes(reader.take()?);
+            let trigrams = (0..trigram_count)
+                .map(|_| reader.take().map(u32::from_le_bytes))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.push((name, trigrams));
+        }
+        Ok(Self {
+            stamp: ArchiveStamp {
+                size,
+                modified: SystemTime::UNIX_EPOCH + Duration::new(secs, nanos),
+            },
+            entries,
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let Some((value, rest)) = self.bytes.split_first_chunk::<N>() else {
+            return Err("The text index is truncated.".to_string());
+        };
+        self.bytes = rest;
+        Ok(*value)
+    }
+}
+
+/// Reads the sidecar index of an archive, or `None` if there is none or it
+/// was built from a different version of the archive.
+pub fn read_sidecar(archive_path: &Path) -> Result<Option<TextIndex>, String> {
+    let path = sidecar_path(archive_path);
+    let Ok(bytes) = fs::read(&path) else {
+        return Ok(None);
+    };
+    let index = TextIndex::parse(&bytes)
+        .map_err(|e| format!("Failed to read \"{}\": {}", path.display(), e))?;
+    Ok((index.stamp == ArchiveStamp::of(archive_path)?).then_some(index))
+}
+
+#[test]
+fn should_narrow_search_to_candidate_entries() {
+    let grp = grp::testing::GrpFixture::new()
+        .entry("GAME.CON", b"define RPG_WEAPON 4\r\n")
+        .entry("USER.CON", b"definesound BOSS1 \"BOSS1.VOC\"\r\n")
+        .entry("E1L1.MAP", &[7, 0, 0, 0])
+        .build();
+    let mut grp_reader = GrpFileReader::new(std::io::Cursor::new(grp)).unwrap();
+    let stamp = ArchiveStamp {
+        size: 1234,
+        modified: SystemTime::UNIX_EPOCH + Duration::new(817_776_000, 5),
+    };
+    let index = TextIndex::build(&mut grp_reader, stamp).unwrap();
+    assert_eq!(index.entries.len(), 2);
+
+    let names = |pattern: &[u8]| {
+        index
+            .candidates(pattern)
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(b"rpg_weapon"), ["GAME.CON"]);
+    assert_eq!(names(b"boss1.voc"), ["USER.CON"]);
+    assert_eq!(names(b"define"), ["GAME.CON", "USER.CON"]);
+    assert_eq!(names(b"de"), ["GAME.CON", "USER.CON"]);
+    assert!(names(b"nukem").is_empty());
+    assert_eq!(
+        matching_lines(b"define A 1\r\n// a\r\nDEFINE B 2\r\n", b"define"),
+        [(1, &b"define A 1"[..]), (3, &b"DEFINE B 2"[..])]
+    );
+
+    let bytes = index.to_bytes();
+    assert_eq!(TextIndex::parse(&bytes).unwrap(), index);
+    assert!(TextIndex::parse(&bytes[..bytes.len() - 1]).is_err());
+}
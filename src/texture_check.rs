@@ -0,0 +1,145 @@
+//! Checking the tiles that maps use as wall, ceiling and floor textures.
+//!
+//! The classic renderer wraps textures with bit masks, so floors and
+//! ceilings need tiles whose width and height are powers of two, and walls
+//! need a height that is a power of two. Other tiles render incorrectly,
+//! and tiles without pixels crash old renderers.
+
+use art::{ArtTile, TileSet};
+use map::Map;
+use std::{collections::BTreeMap, fmt};
+
+/// Where a map uses a tile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Surface {
+    Ceiling(usize),
+    Floor(usize),
+    Wall(usize),
+    /// The masked or one-way texture of a wall.
+    MaskedWall(usize),
+}
+impl fmt::Display for Surface {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Surface::Ceiling(sector) => write!(f, "Sector {} ceiling", sector),
+            Surface::Floor(sector) => write!(f, "Sector {} floor", sector),
+            Surface::Wall(wall) => write!(f, "Wall {}", wall),
+            Surface::MaskedWall(wall) => write!(f, "Wall {} masked texture", wall),
+        }
+    }
+}
+
+/// A texture that does not render correctly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub surface: Surface,
+    pub picnum: i16,
+    pub reason: String,
+}
+
+/// Every surface of the map whose tile is missing, empty or of a size the
+/// renderer cannot wrap.
+pub fn check(map: &Map, tile_set: &TileSet) -> Vec<Problem> {
+    let tiles = tile_set
+        .tiles()
+        .map(|t| (t.number(), t))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut textures = Vec::new();
+    for (i, sector) in map.sectors.iter().enumerate() {
+        textures.push((Surface::Ceiling(i), sector.ceiling_picnum));
+        textures.push((Surface::Floor(i), sector.floor_picnum));
+    }
+    for (i, wall) in map.walls.iter().enumerate() {
+        textures.push((Surface::Wall(i), wall.picnum));
+        // Bit 4 makes the wall masked and bit 5 one-way, both drawing overpicnum.
+        if wall.cstat & (1 << 4 | 1 << 5) != 0 {
+            textures.push((Surface::MaskedWall(i), wall.over_picnum));
+        }
+    }
+
+    let mut problems = Vec::new();
+    for (surface, picnum) in textures {
+        let tile = u32::try_from(picnum).ok().and_then(|n| tiles.get(&n));
+        if let Some(reason) = tile_problem(surface, tile) {
+            problems.push(Problem {
+                surface,
+                picnum,
+                reason,
+            });
+        }
+    }
+    problems
+}
+
+fn tile_problem(surface: Surface, tile: Option<&&ArtTile>) -> Option<String> {
+    let Some(tile) = tile else {
+        return Some("the tile does not exist".to_string());
+    };
+    let (width, height) = (tile.width(), tile.height());
+    if width <= 0 || height <= 0 {
+        return Some(format!(
+            "the tile is {}x{} and has no pixels",
+            width, height
+        ));
+    }
+    let power_of_two = |size: i16| (size as u16).is_power_of_two();
+    let wraps = match surface {
+        Surface::Ceiling(_) | Surface::Floor(_) => power_of_two(width) && power_of_two(height),
+        Surface::Wall(_) | Surface::MaskedWall(_) => power_of_two(height),
+    };
+    if wraps {
+        return None;
+    }
+    Some(format!(
+        "the tile is {}x{}, which is not a power of two",
+        width, height
+    ))
+}
+
+#[test]
+fn should_flag_textures_that_do_not_wrap() {
+    let bytes = art::testing::ArtFixture::new(0)
+        .tile(64, 64, 1)
+        .tile(64, 48, 1)
+        .tile(48, 64, 1)
+        .tile(0, 0, 0)
+        .build();
+    let tiles = art::ArtFileReader::new(std::io::Cursor::new(bytes))
+        .and_then(|mut art_reader| art_reader.read_tiles())
+        .unwrap();
+    let mut tile_set = TileSet::new();
+    tile_set.add_file("TILES000.ART".to_string(), tiles);
+
+    let mut map = map::testing::two_rooms();
+    for sector in &mut map.sectors {
+        (sector.ceiling_picnum, sector.floor_picnum) = (0, 0);
+    }
+    for wall in &mut map.walls {
+        wall.picnum = 2;
+    }
+    assert_eq!(check(&map, &tile_set), []);
+
+    map.sectors[1].floor_picnum = 2;
+    map.walls[3].picnum = 1;
+    map.walls[5].cstat = 1 << 4;
+    map.walls[5].over_picnum = 3;
+    map.walls[6].picnum = 9;
+    let problems = check(&map, &tile_set);
+    assert_eq!(
+        problems
+            .iter()
+            .map(|p| (p.surface, p.picnum))
+            .collect::<Vec<_>>(),
+        [
+            (Surface::Floor(1), 2),
+            (Surface::Wall(3), 1),
+            (Surface::MaskedWall(5), 3),
+            (Surface::Wall(6), 9),
+        ]
+    );
+    assert_eq!(
+        problems[1].reason,
+        "the tile is 64x48, which is not a power of two"
+    );
+}
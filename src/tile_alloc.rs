@@ -0,0 +1,121 @@
+//! Allocation of free tile numbers for new art.
+//!
+//! Allocated tiles can be recorded in a reservation file, one range per line
+//! such as `3584-3615`, so that later allocations by other authors of the
+//! same project skip them.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+/// Picks `count` tile numbers from the free ranges, lowest first. With
+/// `contiguous`, the tiles are taken from the first range that fits them
+/// all. Returns `None` when there are not enough free tiles.
+pub fn allocate(free: &[(u32, u32)], count: u32, contiguous: bool) -> Option<Vec<(u32, u32)>> {
+    if contiguous {
+        return free
+            .iter()
+            .find(|(first, last)| last - first + 1 >= count)
+            .map(|(first, _)| vec![(*first, first + count - 1)]);
+    }
+
+    let mut allocated = Vec::new();
+    let mut remaining = count;
+    for (first, last) in free {
+        if remaining == 0 {
+            break;
+        }
+        let taken = remaining.min(last - first + 1);
+        allocated.push((*first, first + taken - 1));
+        remaining -= taken;
+    }
+    (remaining == 0).then_some(allocated)
+}
+
+/// Removes the reserved tiles from the free ranges.
+pub fn subtract(free: &[(u32, u32)], reserved: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut ranges = free.to_vec();
+    for (reserved_first, reserved_last) in reserved {
+        ranges = ranges
+            .into_iter()
+            .flat_map(|(first, last)| {
+                let before =
+                    (first < *reserved_first).then(|| (first, last.min(reserved_first - 1)));
+                let after = (last > *reserved_last).then(|| (first.max(reserved_last + 1), last));
+                before.into_iter().chain(after)
+            })
+            .collect();
+    }
+    ranges
+}
+
+/// Reads the reserved ranges, of which there are none when the file does not exist.
+pub fn read_reservations(path: &Path) -> Result<Vec<(u32, u32)>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(format!("Failed to read \"{}\": {}", path.display(), err)),
+    };
+
+    let mut reserved = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        // Everything after # is a comment, e.g. the name of the author.
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let range = line.split_once('-').unwrap_or((line, line));
+        match (range.0.trim().parse(), range.1.trim().parse()) {
+            (Ok(first), Ok(last)) if first <= last => reserved.push((first, last)),
+            _ => {
+                return Err(format!(
+                    "Invalid tile range on line {} of \"{}\": {}",
+                    i + 1,
+                    path.display(),
+                    line
+                ))
+            }
+        }
+    }
+    Ok(reserved)
+}
+
+/// Appends ranges to the reservation file, creating it when needed.
+pub fn append_reservations(path: &Path, ranges: &[(u32, u32)]) -> Result<(), String> {
+    let describe = |e: std::io::Error| format!("Failed to write \"{}\": {}", path.display(), e);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(describe)?;
+    for (first, last) in ranges {
+        writeln!(file, "{}-{}", first, last).map_err(describe)?;
+    }
+    file.sync_data().map_err(describe)
+}
+
+#[test]
+fn should_allocate_around_reservations() {
+    let free = [(0, 3), (9, 10), (12, 19)];
+    assert_eq!(allocate(&free, 5, true), Some(vec![(12, 16)]));
+    assert_eq!(allocate(&free, 5, false), Some(vec![(0, 3), (9, 9)]));
+    assert_eq!(allocate(&free, 9, true), None);
+    assert_eq!(allocate(&free, 15, false), None);
+
+    let path = std::env::temp_dir().join("duke3d_should_allocate_around_reservations.txt");
+    let _ = fs::remove_file(&path);
+    assert_eq!(read_reservations(&path).unwrap(), []);
+    fs::write(&path, "# Stock tiles\n2-9 # a\n").unwrap();
+    append_reservations(&path, &[(14, 14)]).unwrap();
+    let reserved = read_reservations(&path).unwrap();
+    assert_eq!(reserved, [(2, 9), (14, 14)]);
+    assert_eq!(
+        subtract(&free, &reserved),
+        [(0, 1), (10, 10), (12, 13), (15, 19)]
+    );
+
+    fs::write(&path, "9-2\n").unwrap();
+    assert!(read_reservations(&path).is_err());
+}
@@ -0,0 +1,138 @@
+//! The metadata written next to exported tiles, for viewers and engines
+//! that load the images:
+//!
+//! ```json
+//! {
+//!   "schema": "duke3d-file-utility/tile-metadata/1",
+//!   "tool": "duke3d-file-util 0.1.0",
+//!   "source": "TILES000.ART",
+//!   "palette": {"file": "LOOKUP.DAT", "base_palette": "water"},
+//!   "tiles": [{
+//!     "tile": 0, "file": "TILE0.png", "width": 64, "height": 64,
+//!     "x_offset": -2, "y_offset": 1, "picanm": 33685123,
+//!     "animation": {"type": "forward", "frames": 3, "speed": 2}
+//!   }]
+//! }
+//! ```
+//!
+//! `tool` is the name and version of the program that exported the tiles.
+//! `source` and the palette `file` are file names without directories.
+//! `base_palette` is `null` unless the tiles were drawn with a base palette
+//! of LOOKUP.DAT. The offsets and animation are decoded from `picanm`, which
+//! is kept as the raw value for fields added by later engines. The animation
+//! type is one of `none`, `oscillating`, `forward` and `backward`.
+//!
+//! The schema name changes only when fields are removed or change their
+//! meaning, so readers should ignore fields they do not know.
+
+use crate::json;
+use art::{AnimationType, ArtTile};
+
+pub const SCHEMA: &str = "duke3d-file-utility/tile-metadata/1";
+
+/// The metadata of the tiles exported from one .art file.
+#[derive(Clone, Debug)]
+pub struct TileMetadata {
+    pub source: String,
+    pub palette_file: String,
+    pub base_palette: Option<String>,
+    /// The exported tiles with the file names of their images.
+    pub tiles: Vec<(ArtTile, String)>,
+}
+impl TileMetadata {
+    pub fn to_json(&self) -> String {
+        let tiles = self
+            .tiles
+            .iter()
+            .map(|(tile, file_name)| {
+                let animation = tile.animation();
+                let animation_type = match animation.animation_type {
+                    AnimationType::None => "none",
+                    AnimationType::Oscillating => "oscillating",
+                    AnimationType::Forward => "forward",
+                    AnimationType::Backward => "backward",
+                };
+                format!(
+                    concat!(
+                        "{{\"tile\":{},\"file\":{},\"width\":{},\"height\":{},",
+                        "\"x_offset\":{},\"y_offset\":{},\"picanm\":{},",
+                        "\"animation\":{{\"type\":\"{}\",\"frames\":{},\"speed\":{}}}}}"
+                    ),
+                    tile.number(),
+                    json::string(file_name),
+                    tile.width(),
+                    tile.height(),
+                    animation.x_offset,
+                    animation.y_offset,
+                    tile.picanm(),
+                    animation_type,
+                    animation.frames,
+                    animation.speed
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "{{\"schema\":{},\"tool\":{},\"source\":{},\"palette\":{{\"file\":{},\"base_palette\":{}}},\"tiles\":[{}]}}",
+            json::string(SCHEMA),
+            json::string(duke3d_file_utility::TOOL),
+            json::string(&self.source),
+            json::string(&self.palette_file),
+            self.base_palette
+                .as_deref()
+                .map_or("null".to_string(), json::string),
+            tiles.join(",")
+        )
+    }
+}
+
+#[test]
+fn should_describe_exported_tiles() {
+    use art::{testing::ArtFixture, ArtFileReader};
+
+    let art = ArtFixture::new(2472)
+        .tile(2, 3, 7)
+        .picanm(0x0201_FE83)
+        .build();
+    let tiles = ArtFileReader::new(std::io::Cursor::new(art))
+        .unwrap()
+        .read_tiles()
+        .unwrap();
+    let metadata = TileMetadata {
+        source: "TILES009.ART".to_string(),
+        palette_file: "LOOKUP.DAT".to_string(),
+        base_palette: Some("water".to_string()),
+        tiles: tiles
+            .into_iter()
+            .map(|t| (t, "TILE2472.png".to_string()))
+            .collect(),
+    };
+
+    let document = json::parse(&metadata.to_json()).unwrap();
+    assert_eq!(
+        document.get("schema").and_then(|s| s.as_str()),
+        Some(SCHEMA)
+    );
+    assert_eq!(
+        document.get("tool").and_then(|t| t.as_str()),
+        Some(duke3d_file_utility::TOOL)
+    );
+    let tile = &document.get("tiles").and_then(|t| t.as_array()).unwrap()[0];
+    assert_eq!(tile.get("tile").and_then(|t| t.as_u64()), Some(2472));
+    assert_eq!(tile.get("x_offset"), Some(&json::JsonValue::Number(-2.0)));
+    assert_eq!(
+        tile.get("picanm").and_then(|p| p.as_u64()),
+        Some(0x0201_FE83)
+    );
+    let animation = tile.get("animation").unwrap();
+    assert_eq!(
+        animation.get("type").and_then(|t| t.as_str()),
+        Some("forward")
+    );
+    assert_eq!(
+        document
+            .get("palette")
+            .and_then(|p| p.get("base_palette"))
+            .and_then(|b| b.as_str()),
+        Some("water")
+    );
+}
@@ -0,0 +1,141 @@
+//! Parser for the small subset of TOML used by the edit and manifest files
+//! of the CLI: `[table]` and `[[array.of.tables]]` headers, `key = value`
+//! pairs with basic strings, integers and booleans, and `#` comments.
+
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TomlValue {
+    Bool(bool),
+    Integer(i64),
+    String(String),
+}
+
+/// A table in document order. Keys before the first header go into a table
+/// with an empty name.
+#[derive(Debug)]
+pub struct TomlTable {
+    pub name: String,
+    /// Whether the table was declared with `[[name]]`.
+    pub is_array_item: bool,
+    pub values: BTreeMap<String, TomlValue>,
+}
+impl TomlTable {
+    /// Gets a string value, failing with a message naming the table otherwise.
+    pub fn get_str(&self, key: &str) -> Result<Option<&str>, String> {
+        match self.values.get(key) {
+            None => Ok(None),
+            Some(TomlValue::String(s)) => Ok(Some(s)),
+            Some(_) => Err(format!(
+                "Value of \"{}\" in [{}] should be a string.",
+                key, self.name
+            )),
+        }
+    }
+    pub fn require_str(&self, key: &str) -> Result<&str, String> {
+        self.get_str(key)?
+            .ok_or_else(|| format!("Missing \"{}\" in [{}].", key, self.name))
+    }
+}
+
+pub fn parse(source: &str) -> Result<Vec<TomlTable>, String> {
+    let mut tables = vec![TomlTable {
+        name: String::new(),
+        is_array_item: false,
+        values: BTreeMap::new(),
+    }];
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix("[[").and_then(|l| l.strip_suffix("]]")) {
+            tables.push(TomlTable {
+                name: name.trim().to_string(),
+                is_array_item: true,
+                values: BTreeMap::new(),
+            });
+        } else if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            tables.push(TomlTable {
+                name: name.trim().to_string(),
+                is_array_item: false,
+                values: BTreeMap::new(),
+            });
+        } else if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().trim_matches('"').to_string();
+            let value = parse_value(value.trim())
+                .ok_or_else(|| format!("Invalid value on line {}: {}", line_number, line))?;
+            let table = tables.last_mut().unwrap();
+            if table.values.insert(key.clone(), value).is_some() {
+                return Err(format!(
+                    "Duplicate key \"{}\" on line {}.",
+                    key, line_number
+                ));
+            }
+        } else {
+            return Err(format!("Invalid line {}: {}", line_number, line));
+        }
+    }
+
+    Ok(tables)
+}
+
+/// `value` as a basic string, escaped so that [`parse`] reads it back.
+pub fn quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+/// Removes a `#` comment, unless the `#` is inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' if in_string => escaped = !escaped,
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => escaped = false,
+        }
+    }
+    line
+}
+
+fn parse_value(value: &str) -> Option<TomlValue> {
+    match value {
+        "true" => return Some(TomlValue::Bool(true)),
+        "false" => return Some(TomlValue::Bool(false)),
+        _ => {}
+    }
+
+    if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        let mut string = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                string.push(c);
+                continue;
+            }
+            match chars.next()? {
+                '\\' => string.push('\\'),
+                '"' => string.push('"'),
+                'n' => string.push('\n'),
+                't' => string.push('\t'),
+                _ => return None,
+            }
+        }
+        return Some(TomlValue::String(string));
+    }
+    if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        return Some(TomlValue::String(inner.to_string()));
+    }
+
+    value.replace('_', "").parse().ok().map(TomlValue::Integer)
+}
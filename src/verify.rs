@@ -0,0 +1,228 @@
+//! Checking the entries of an archive against a manifest of SHA-256 hashes.
+//!
+//! Manifests are either the output of `sha256sum` over extracted entries:
+//!
+//! ```text
+//! 6f1ed002ab5595859014ebf0951522d9d1c1e1e2d0a5a95c6ea6d9d4b5a2f8e3  GAME.CON
+//! ```
+//!
+//! or JSON with an object per entry, whose size is optional:
+//!
+//! ```json
+//! {"entries": [{"name": "GAME.CON", "size": 151, "sha256": "6f1ed002..."}]}
+//! ```
+//!
+//! Names are the file names of extracted entries, which are matched
+//! case-insensitively. Paths in `sha256sum` files are reduced to their
+//! file names.
+
+use crate::json;
+use grp::{fs_name, EntryName, GrpFileEntry, GrpFileReader, Sha256};
+use std::{
+    collections::BTreeMap,
+    io::{Read, Seek},
+};
+
+/// An entry as recorded in a manifest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpectedEntry {
+    pub name: EntryName,
+    pub size: Option<u64>,
+    pub sha256: [u8; 32],
+}
+
+/// How an entry compares with the manifest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Verified,
+    /// The size or the hash differ.
+    Changed,
+    /// The entry is in the manifest, but not in the archive.
+    Missing,
+    /// The entry is in the archive, but not in the manifest.
+    Unlisted,
+}
+
+fn parse_hash(hex: &str) -> Option<[u8; 32]> {
+    let mut hash = [0; 32];
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    for (byte, digits) in hash.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+    Some(hash)
+}
+
+/// The hash in lowercase hexadecimal, as written by `sha256sum`.
+pub fn to_hex(hash: &[u8; 32]) -> String {
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Computes the SHA-256 hash of an entry, reading it in chunks.
+pub fn hash_entry<R: Read + Seek>(
+    grp_reader: &mut GrpFileReader<R>,
+    entry: &GrpFileEntry,
+) -> Result<[u8; 32], String> {
+    hash_reader(grp_reader.open_file(entry)?, &entry.name().to_string())
+}
+
+/// Computes the SHA-256 hash of everything `reader` reads, e.g. of a whole
+/// archive file, with `name` for errors.
+pub fn hash_reader(mut reader: impl Read, name: &str) -> Result<[u8; 32], String> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let read = reader
+            .read(&mut buf)
+            .map_err(|e| format!("Failed to read \"{}\": {}", name, e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher.finish())
+}
+
+/// Parses a manifest in either format, telling them apart by the first character.
+pub fn parse_manifest(source: &str) -> Result<Vec<ExpectedEntry>, String> {
+    if source.trim_start().starts_with('{') {
+        parse_json_manifest(source)
+    } else {
+        parse_sha256sum(source)
+    }
+}
+
+fn parse_sha256sum(source: &str) -> Result<Vec<ExpectedEntry>, String> {
+    let mut entries = Vec::new();
+    for (line_index, line) in source.lines().enumerate() {
+        let invalid = || format!("Line {}: expected a hash and a file name.", line_index + 1);
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // The hash is followed by a space and a space or, in binary mode, `*`.
+        let (hex, path) = line.split_once(' ').ok_or_else(invalid)?;
+        let path = path
+            .strip_prefix([' ', '*'])
+            .ok_or_else(invalid)?
+            .trim_end_matches('\r');
+        let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        entries.push(ExpectedEntry {
+            name: fs_name::from_fs_name(file_name)?,
+            size: None,
+            sha256: parse_hash(hex).ok_or_else(invalid)?,
+        });
+    }
+    Ok(entries)
+}
+
+fn parse_json_manifest(source: &str) -> Result<Vec<ExpectedEntry>, String> {
+    let manifest = json::parse(source)?;
+    let Some(items) = manifest.get("entries").and_then(|e| e.as_array()) else {
+        return Err("The manifest has no \"entries\" array.".to_string());
+    };
+    let mut entries = Vec::with_capacity(items.len());
+    for (i, item) in items.iter().enumerate() {
+        let invalid = |key: &str| format!("Entry {} of the manifest has no valid \"{}\".", i, key);
+        let name = item
+            .get("name")
+            .and_then(|n| n.as_str())
+            .ok_or_else(|| invalid("name"))?;
+        let size = match item.get("size") {
+            Some(size) => Some(size.as_u64().ok_or_else(|| invalid("size"))?),
+            None => None,
+        };
+        let sha256 = item
+            .get("sha256")
+            .and_then(|h| h.as_str())
+            .and_then(parse_hash)
+            .ok_or_else(|| invalid("sha256"))?;
+        entries.push(ExpectedEntry {
+            name: fs_name::from_fs_name(name)?,
+            size,
+            sha256,
+        });
+    }
+    Ok(entries)
+}
+
+/// Hashes every entry of the archive and compares it with the manifest,
+/// in the order of the archive followed by the entries it is missing.
+pub fn verify<R: Read + Seek>(
+    grp_reader: &mut GrpFileReader<R>,
+    expected: &[ExpectedEntry],
+) -> Result<Vec<(EntryName, Verdict)>, String> {
+    let mut expected = expected
+        .iter()
+        .map(|e| (e.name, e))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut verdicts = Vec::new();
+    for entry in grp_reader.get_file_entries()? {
+        let name = entry.name();
+        let Some(expected_entry) = expected.remove(&name) else {
+            verdicts.push((name, Verdict::Unlisted));
+            continue;
+        };
+        if expected_entry
+            .size
+            .is_some_and(|size| size != entry.size() as u64)
+        {
+            verdicts.push((name, Verdict::Changed));
+            continue;
+        }
+
+        let verdict = if hash_entry(grp_reader, &entry)? == expected_entry.sha256 {
+            Verdict::Verified
+        } else {
+            Verdict::Changed
+        };
+        verdicts.push((name, verdict));
+    }
+    verdicts.extend(expected.into_keys().map(|name| (name, Verdict::Missing)));
+    Ok(verdicts)
+}
+
+#[test]
+fn should_report_drift_from_manifest() {
+    let hex = |data: &[u8]| to_hex(&grp::sha256(data));
+    let grp = grp::testing::GrpFixture::new()
+        .entry("GAME.CON", b"define")
+        .entry("USER.CON", b"define RPG 2")
+        .entry("E1L1.MAP", &[7, 0, 0, 0])
+        .build();
+
+    let sha256sum = format!(
+        "{}  GAME.CON\n{} *extracted/user.con\n{}  E1L2.MAP\n",
+        hex(b"define"),
+        hex(b"define RPG 1"),
+        hex(b"")
+    );
+    let expected = parse_manifest(&sha256sum).unwrap();
+    let mut grp_reader = GrpFileReader::new(std::io::Cursor::new(&grp)).unwrap();
+    let verdicts = verify(&mut grp_reader, &expected).unwrap();
+    assert_eq!(
+        verdicts
+            .iter()
+            .map(|(name, verdict)| (name.to_string(), *verdict))
+            .collect::<Vec<_>>(),
+        [
+            ("GAME.CON".to_string(), Verdict::Verified),
+            ("USER.CON".to_string(), Verdict::Changed),
+            ("E1L1.MAP".to_string(), Verdict::Unlisted),
+            ("E1L2.MAP".to_string(), Verdict::Missing),
+        ]
+    );
+
+    let json_manifest = format!(
+        r#"{{"entries": [{{"name": "E1L1.MAP", "size": 5, "sha256": "{}"}}]}}"#,
+        hex(&[7, 0, 0, 0])
+    );
+    let expected = parse_manifest(&json_manifest).unwrap();
+    assert_eq!(expected[0].size, Some(5));
+    let verdicts = verify(&mut grp_reader, &expected).unwrap();
+    assert_eq!(verdicts[2].1, Verdict::Changed);
+
+    assert!(parse_manifest("abc  GAME.CON").is_err());
+    assert!(parse_manifest(r#"{"entries": [{"name": "GAME.CON"}]}"#).is_err());
+}
@@ -0,0 +1,102 @@
+//! Distributing work over threads while keeping results in input order.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+/// The number of jobs used when none is given: one per available core.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Runs `work` for each item on up to `jobs` threads and returns the results
+/// in the order of the items.
+///
+/// Each thread calls `init` once for its own state, e.g. an open archive,
+/// and takes the next unprocessed item until none are left. After the first
+/// error no more items are taken, and the error of the earliest failed item
+/// is returned.
+pub fn run_ordered<T, U, S>(
+    items: &[T],
+    jobs: usize,
+    init: impl Fn() -> Result<S, String> + Sync,
+    work: impl Fn(&mut S, &T) -> Result<U, String> + Sync,
+) -> Result<Vec<U>, String>
+where
+    T: Sync,
+    U: Send,
+{
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+    let worker = || {
+        let mut finished = Vec::new();
+        match init() {
+            Ok(mut state) => {
+                while !failed.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    let result = work(&mut state, item);
+                    failed.fetch_or(result.is_err(), Ordering::Relaxed);
+                    finished.push((index, result));
+                }
+            }
+            Err(e) => {
+                failed.store(true, Ordering::Relaxed);
+                finished.push((0, Err(e)));
+            }
+        }
+        if let Ok(mut results) = results.lock() {
+            results.append(&mut finished);
+        }
+    };
+    thread::scope(|scope| {
+        for _ in 1..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(worker);
+        }
+        worker();
+    });
+
+    let mut results = results
+        .into_inner()
+        .map_err(|_| "A worker thread panicked.".to_string())?;
+    results.sort_by_key(|(index, result)| (*index, result.is_ok()));
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[test]
+fn should_keep_results_in_order() {
+    let items = (0..100).collect::<Vec<u32>>();
+    for jobs in [0, 1, 4, 200] {
+        let squares = run_ordered(&items, jobs, || Ok(()), |_, i| Ok(i * i)).unwrap();
+        assert_eq!(squares, items.iter().map(|i| i * i).collect::<Vec<_>>());
+    }
+
+    let result = run_ordered(
+        &items,
+        4,
+        || Ok(()),
+        |_, i| {
+            if *i >= 10 {
+                Err(format!("{}", i))
+            } else {
+                Ok(*i)
+            }
+        },
+    );
+    assert_eq!(result, Err("10".to_string()));
+    let result = run_ordered(
+        &items,
+        4,
+        || Err::<(), _>("init".to_string()),
+        |_, i| Ok(*i),
+    );
+    assert_eq!(result, Err("init".to_string()));
+}
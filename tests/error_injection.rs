@@ -0,0 +1,152 @@
+//! Malformed and unreadable input must make the library parsers return
+//! errors, never panic. Every test cuts, corrupts or fails a valid fixture at
+//! every byte and only requires the parsers to return.
+
+#![cfg(all(feature = "art", feature = "grp", feature = "map"))]
+
+use duke3d_file_utility::{art, grp, map};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+/// A reader over `data` whose reads fail once they reach `fail_at`.
+struct FailingReader {
+    data: Cursor<Vec<u8>>,
+    fail_at: u64,
+}
+impl Read for FailingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.fail_at.saturating_sub(self.data.position());
+        if remaining == 0 && !buf.is_empty() {
+            return Err(io::Error::other("injected read error"));
+        }
+        let len = buf.len().min(remaining as usize);
+        self.data.read(&mut buf[..len])
+    }
+}
+impl Seek for FailingReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.data.seek(pos)
+    }
+}
+
+/// Variants of `data`: cut at every byte, and with every byte set to values
+/// that make the largest and smallest integers.
+fn damaged(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut variants = (0..data.len())
+        .map(|i| data[..i].to_vec())
+        .collect::<Vec<_>>();
+    for i in 0..data.len() {
+        for value in [0x7F, 0x80, 0xFF] {
+            let mut corrupt = data.to_vec();
+            corrupt[i] = value;
+            variants.push(corrupt);
+        }
+    }
+    variants
+}
+
+fn read_grp<R: Read + Seek>(reader: R) -> Result<(), String> {
+    let mut grp_reader = grp::GrpFileReader::new(reader)?;
+    for entry in grp_reader.get_file_entries()? {
+        grp_reader.read_file(&entry)?;
+        grp_reader
+            .open_file(&entry)?
+            .read_to_end(&mut Vec::new())
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn read_art<R: Read + Seek>(reader: R) -> Result<(), String> {
+    let mut art_reader = art::ArtFileReader::new(reader)?;
+    for tile in art_reader.read_tiles()? {
+        art_reader.read_pixels(&tile)?;
+    }
+    Ok(())
+}
+
+fn read_map(data: &[u8]) {
+    let maps = [
+        map::Map::parse(data),
+        map::Map::parse_v6(data).map(|(map, _)| map),
+    ];
+    for mut map in maps.into_iter().flatten() {
+        let _ = map.extract_sectors(&[0]);
+        let _ = map.append(&map.clone(), (1, 1, 1));
+        let _ = map.mirror(map::Axis::X);
+        map.rotate(1);
+    }
+}
+
+fn grp_fixture() -> Vec<u8> {
+    grp::testing::GrpFixture::new()
+        .entry("GAME.CON", b"define")
+        .entry("E1L1.MAP", &[7, 0, 0, 0])
+        .build()
+}
+
+fn art_fixture() -> Vec<u8> {
+    art::testing::ArtFixture::new(10)
+        .tile(2, 3, 1)
+        .tile(0, 0, 0)
+        .tile(1, 2, 5)
+        .build()
+}
+
+#[test]
+fn should_not_panic_on_damaged_files() {
+    for data in damaged(&grp_fixture()) {
+        let _ = read_grp(Cursor::new(data));
+    }
+    for data in damaged(&art_fixture()) {
+        let _ = read_art(Cursor::new(&data));
+        let _ = art::read_tile_range(&data);
+        let _ = art::renumber(&mut data.clone(), u32::MAX);
+    }
+    for data in damaged(&map::testing::two_rooms().to_bytes()) {
+        read_map(&data);
+    }
+}
+
+#[test]
+fn should_not_overflow_at_the_last_tile_number() {
+    let data = art::testing::ArtFixture::new(u32::MAX - 1)
+        .tile(2, 2, 1)
+        .tile(1, 1, 1)
+        .build();
+    let tiles = art::ArtFileReader::new(Cursor::new(data))
+        .unwrap()
+        .read_tiles()
+        .unwrap();
+    let mut tile_set = art::TileSet::new();
+    tile_set.add_file("TILES999.ART", tiles);
+    assert_eq!(tile_set.gaps(), [(0, u32::MAX - 2)]);
+    assert_eq!(tile_set.free_ranges(u32::MAX), [(0, u32::MAX - 2)]);
+}
+
+#[test]
+fn should_return_read_errors() {
+    let grp = grp_fixture();
+    for fail_at in 0..grp.len() as u64 {
+        let reader = FailingReader {
+            data: Cursor::new(grp.clone()),
+            fail_at,
+        };
+        assert!(read_grp(reader).is_err(), "GRP read failing at {}", fail_at);
+    }
+    let art = art_fixture();
+    for fail_at in 0..art.len() as u64 {
+        let reader = FailingReader {
+            data: Cursor::new(art.clone()),
+            fail_at,
+        };
+        assert!(read_art(reader).is_err(), "ART read failing at {}", fail_at);
+    }
+    let map = map::testing::two_rooms().to_bytes();
+    for fail_at in 0..map.len() as u64 {
+        let reader = FailingReader {
+            data: Cursor::new(map.clone()),
+            fail_at,
+        };
+        assert!(map::MapFileReader::new(reader).read_map().is_err());
+    }
+}